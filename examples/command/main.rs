@@ -1,57 +1,96 @@
-use clap::{load_yaml, App, ArgMatches};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use std::io;
-use crossterm::event::{Event, KeyCode};
 use tui::backend::{Backend, CrosstermBackend};
 use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::widgets::{Block, Borders};
 use tui::Terminal;
-use tui_clap::{Events, TuiClap};
+use tui_clap::{
+    tokenize, Candidate, Config, Events, HighlightStyles, HistoryDuplicates, PathCompleter,
+    TuiClap, Validation,
+};
 
 fn main() -> Result<(), io::Error> {
-    let yaml = load_yaml!("cli.yaml");
-    let app = App::from(yaml);
+    let app = Command::new("myapp")
+        .version("1.0")
+        .author("Kevin K. <kbknapp@gmail.com>")
+        .about("Does awesome things")
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Sets a custom config file"),
+        )
+        .arg(
+            Arg::new("INPUT")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .action(ArgAction::Count)
+                .help("Sets the level of verbosity"),
+        )
+        .subcommand(
+            Command::new("test")
+                .about("controls testing features")
+                .version("1.3")
+                .author("Someone E. <someone_else@other.com>")
+                .arg(Arg::new("debug").short('d').action(ArgAction::SetTrue)),
+        );
 
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut tui = TuiClap::from_app(app);
+    let mut tui = TuiClap::with_handler(app, handle_matches);
     tui.input_widget().prompt("prompt > ");
-
+    tui.input_widget().placeholder("type `--help` for commands");
+    tui.set_highlighting(Some(HighlightStyles::default()));
+    tui.set_ansi_parsing(true);
+    tui.set_echo_commands(true);
+    tui.set_help_popup(true);
+    tui.state().autosuggest(true);
+    tui.state().history_limit(Some(1000));
+    tui.state()
+        .history_duplicates(HistoryDuplicates::MoveToFront);
+    tui.state().history_ignore_space(true);
+    tui.output_state().scrollback_limit(Some(10_000));
+    tui.set_history_file(std::env::temp_dir().join("tui-clap-example-history"))?;
+    tui.set_rc_file(".tui-clap-examplerc");
+    tui.set_arg_completer("config", |_: &str, _: usize| {
+        vec![
+            Candidate::new("default.conf"),
+            Candidate::with_description("debug.conf", "verbose logging"),
+        ]
+    });
+    tui.set_arg_completer("INPUT", PathCompleter::files());
+    tui.set_validator(|line| match tokenize(line) {
+        Ok(_) => Validation::Valid,
+        Err(err) => Validation::Invalid(Some(err.to_string())),
+    });
+
+    enable_raw_mode()?;
     terminal.clear().expect("Could not clear terminal");
 
-    let events = Events::default();
+    let mut events = Events::from_config(Config {
+        mouse_capture: true,
+        ..Config::default()
+    });
+    events.disable_exit_key();
 
     loop {
         draw(&mut terminal, &mut tui)?;
-        handle_input(&mut tui, &events)
-    }
-}
-
-fn handle_input(tui: &mut TuiClap, events: &Events) {
-    if let Ok(Some(Event::Key(key_event))) = events.next() {
-        match key_event.code {
-            KeyCode::Backspace => {
-                tui.state().del_char()
-            }
-            KeyCode::Enter => {
-                if let Ok(matches) = tui.parse() {
-                    match handle_matches(matches) {
-                        Ok(output) => {
-                            for message in output {
-                                tui.write_to_output(message)
-                            }
-                        }
-                        Err(err) => tui.write_to_output(err)
-                    }
-                }
-            }
-            KeyCode::Char(char) => {
-                tui.state().add_char(char)
-            },
-            _ => {}
+        if !tui.fetch_event(&events) {
+            break;
         }
     }
+
+    disable_raw_mode()?;
+    terminal.clear()
 }
 
 fn draw<B: Backend>(terminal: &mut Terminal<B>, tui: &mut TuiClap) -> io::Result<()> {
@@ -62,13 +101,19 @@ fn draw<B: Backend>(terminal: &mut Terminal<B>, tui: &mut TuiClap) -> io::Result
             .constraints(
                 [
                     Constraint::Percentage(10),
-                    Constraint::Percentage(80),
-                    Constraint::Percentage(10),
+                    Constraint::Min(0),
+                    Constraint::Length(tui.state().line_count() as u16 + 2),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
                 ]
                 .as_ref(),
             )
             .split(f.size());
-        let block = Block::default().title("Block").borders(Borders::ALL);
+        let title = tui
+            .state()
+            .vi_mode()
+            .map_or("Block", |mode| mode.indicator());
+        let block = Block::default().title(title).borders(Borders::ALL);
         f.render_widget(block, chunks[0]);
         let chunks_output = Layout::default()
             .direction(Direction::Horizontal)
@@ -79,11 +124,19 @@ fn draw<B: Backend>(terminal: &mut Terminal<B>, tui: &mut TuiClap) -> io::Result
         f.render_widget(block, chunks_output[0]);
         let inset_area = edge_inset(&chunks_output[0], 1);
         tui.render_output(f, inset_area);
-        let block = Block::default().title("Command").borders(Borders::ALL);
+        let title = match tui.state().validation() {
+            Validation::Invalid(Some(message)) => format!("Command ({})", message),
+            _ => "Command".to_string(),
+        };
+        let block = Block::default().title(title).borders(Borders::ALL);
         f.render_widget(block, chunks[2]);
 
         let inset_area = edge_inset(&chunks[2], 1);
         tui.render_input(f, inset_area);
+        tui.render_signature_help(f, chunks[3]);
+        tui.render_diagnostics(f, chunks[4]);
+        tui.render_history_popup(f, chunks_output[1]);
+        tui.render_help_popup(f, chunks[1]);
     })?;
     Ok(())
 }
@@ -101,18 +154,23 @@ fn edge_inset(area: &Rect, margin: u16) -> Rect {
 fn handle_matches(matches: ArgMatches) -> Result<Vec<String>, String> {
     let mut output = vec![];
 
-    let config = matches.value_of("config").unwrap_or("default.conf");
+    let config = matches
+        .get_one::<String>("config")
+        .map_or("default.conf", String::as_str);
     let out = format!("Value for config: {}", config);
     output.push(out);
 
     // Calling .unwrap() is safe here because "INPUT" is required (if "INPUT" wasn't
     // required we could have used an 'if let' to conditionally get the value)
-    let out = format!("Using input file: {}", matches.value_of("INPUT").unwrap());
+    let out = format!(
+        "Using input file: {}",
+        matches.get_one::<String>("INPUT").unwrap()
+    );
     output.push(out);
 
     // Vary the output based on how many times the user used the "verbose" flag
     // (i.e. 'myprog -v -v -v' or 'myprog -vvv' vs 'myprog -v'
-    let out = match matches.occurrences_of("v") {
+    let out = match matches.get_count("verbose") {
         0 => "No verbose info".to_string(),
         1 => "Some verbose info".to_string(),
         2 => "Tons of verbose info".to_string(),
@@ -123,7 +181,7 @@ fn handle_matches(matches: ArgMatches) -> Result<Vec<String>, String> {
     // You can handle information about subcommands by requesting their matches by name
     // (as below), requesting just the name used, or both at the same time
     if let Some(matches) = matches.subcommand_matches("test") {
-        if matches.is_present("debug") {
+        if matches.get_flag("debug") {
             let out = "Printing debug info...".to_string();
             output.push(out);
         } else {
@@ -0,0 +1,53 @@
+//! Lines of the output carrying a payload, which the user can activate by clicking them or pressing `Enter` on them
+
+use crate::output::Level;
+use crate::{split_lines, CommandOutputState, OutputHandle, TuiClap};
+use tui::style::Style;
+
+impl CommandOutputState {
+    /// The payload of the line at `index`, see `TuiClap::write_item`
+    pub fn payload(&self, index: usize) -> Option<&str> {
+        self.history.get(index)?.payload.as_deref()
+    }
+}
+
+impl TuiClap {
+    /// Write `string` to the output widget like `TuiClap::write_to_output`, attaching `payload` to its lines,
+    /// e.g. the id of a search result. Activating one of the lines calls the callback of
+    /// `TuiClap::on_line_activated` with the payload.
+    pub fn write_item(&mut self, string: String, payload: impl Into<String>) -> OutputHandle {
+        let payload = payload.into();
+        let lines = split_lines(string, Style::default(), self.ansi_parsing);
+        let state = &mut self.command_output_state;
+        let count = lines.len();
+        let handle = state.push_block(lines, Level::Info);
+        let start = state.history.len().saturating_sub(count);
+        for line in state.history.range_mut(start..) {
+            line.payload = Some(payload.clone());
+        }
+        handle
+    }
+
+    /// Sets the callback called with the payload of a line written with `TuiClap::write_item` when the user activates
+    /// it, by pressing `Enter` on it while selecting lines of the output or by clicking it with mouse capture enabled.
+    /// `Enter` on other lines copies them to the clipboard as before.
+    pub fn on_line_activated(&mut self, callback: impl FnMut(&str) + 'static) {
+        self.line_activated = Some(Box::new(callback));
+    }
+
+    /// Calls the callback of `on_line_activated` with the payload of the line at `index`.
+    /// Returns `false` if the line has no payload or no callback is set.
+    pub(crate) fn activate_line(&mut self, index: usize) -> bool {
+        let payload = match self.command_output_state.payload(index) {
+            Some(payload) => payload.to_string(),
+            None => return false,
+        };
+        match &mut self.line_activated {
+            Some(callback) => {
+                callback(&payload);
+                true
+            }
+            None => false,
+        }
+    }
+}
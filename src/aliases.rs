@@ -0,0 +1,116 @@
+//! Aliases expanding the first word of a command before it is run, like `alias ll='list --long'` of a shell
+
+use crate::completion::Candidate;
+use crate::tokenizer::words;
+use crate::TuiClap;
+
+impl TuiClap {
+    /// Registers the alias `name` for `expansion`, replacing any previous alias of that name. Commands starting with
+    /// `name` run `expansion` followed by the rest of the command instead, e.g. `ll -a` runs `list --long -a` for the
+    /// alias `ll` of `list --long`. Aliases are completed as first word and can be defined with the built-in command
+    /// `alias ll='list --long'` as well.
+    pub fn add_alias(&mut self, name: &str, expansion: &str) {
+        self.aliases
+            .insert(name.to_string(), expansion.trim().to_string());
+    }
+
+    /// Removes the alias `name`, also done by the built-in command `unalias`.
+    /// Returns `false` if there is no such alias.
+    pub fn remove_alias(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// The expansion of the alias `name`
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// The aliases and their expansions sorted by name
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(name, expansion)| (name.as_str(), expansion.as_str()))
+    }
+
+    /// The byte range of the first word of `line` if it is an unquoted alias, together with its expansion
+    fn alias_at_start(&self, line: &str) -> Option<(usize, usize, &str)> {
+        let (words, _) = words(line);
+        let word = words.first()?;
+        if line[word.start..word.end] != word.text {
+            return None;
+        }
+        let expansion = self.aliases.get(&word.text)?;
+        Some((word.start, word.end, expansion))
+    }
+
+    /// `line` with its first word expanded if it is an alias
+    pub(crate) fn expand_alias(&self, line: &str) -> String {
+        match self.alias_at_start(line) {
+            Some((start, end, expansion)) => {
+                format!("{}{}{}", &line[..start], expansion, &line[end..])
+            }
+            None => line.to_string(),
+        }
+    }
+
+    /// `line` with its first word expanded if it is an alias and the byte offset `cursor` moved accordingly,
+    /// unless the cursor is within the first word
+    pub(crate) fn expand_alias_before(&self, line: &str, cursor: usize) -> (String, usize) {
+        match self.alias_at_start(line) {
+            Some((start, end, expansion)) if cursor > end => (
+                format!("{}{}{}", &line[..start], expansion, &line[end..]),
+                cursor - (end - start) + expansion.len(),
+            ),
+            _ => (line.to_string(), cursor),
+        }
+    }
+
+    /// The aliases as completion candidates described by their expansion,
+    /// if the word under the byte offset `cursor` of `line` is the first one
+    pub(crate) fn alias_candidates(&self, line: &str, cursor: usize) -> Vec<Candidate> {
+        if line[..cursor].trim_start().contains(char::is_whitespace) {
+            return vec![];
+        }
+        self.aliases()
+            .map(|(name, expansion)| Candidate::with_description(name, expansion))
+            .collect()
+    }
+
+    /// `alias` lists the aliases, `alias <name>` prints the expansion of an alias
+    /// and `alias <name>=<expansion>` defines one
+    pub(crate) fn builtin_alias(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        if args.is_empty() {
+            return Ok(self
+                .aliases()
+                .map(|(name, expansion)| format!("alias {}='{}'", name, expansion))
+                .collect());
+        }
+        let mut lines = vec![];
+        for arg in args {
+            match arg.split_once('=') {
+                Some((name, _)) if name.is_empty() || name.contains(char::is_whitespace) => {
+                    return Err(format!("alias: invalid name '{}'", name));
+                }
+                Some((name, expansion)) => self.add_alias(name, expansion),
+                None => match self.alias(arg) {
+                    Some(expansion) => lines.push(format!("alias {}='{}'", arg, expansion)),
+                    None => return Err(format!("alias: {}: not found", arg)),
+                },
+            }
+        }
+        Ok(lines)
+    }
+
+    /// `unalias <name>…` removes aliases
+    pub(crate) fn builtin_unalias(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        if args.is_empty() {
+            return Err("unalias: missing name".to_string());
+        }
+        for name in args {
+            if !self.remove_alias(name) {
+                return Err(format!("unalias: {}: not found", name));
+            }
+        }
+        Ok(vec![])
+    }
+}
@@ -0,0 +1,186 @@
+//! Conversion of ANSI escape sequences in output written by handlers to styles
+
+use crate::hyperlink::Link;
+use crate::TuiClap;
+use std::ops::Range;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Splits `text` into lines of spans styled according to the SGR escape sequences in it, starting with `base`,
+/// together with the hyperlinks of OSC 8 sequences in them. The style and an open hyperlink carry over to the
+/// following lines like in a terminal. Other escape sequences are removed.
+pub(crate) fn parse_ansi(text: &str, base: Style) -> Vec<(Spans<'static>, Vec<Link>)> {
+    let mut style = base;
+    // URL of the open hyperlink
+    let mut url: Option<String> = None;
+    text.lines()
+        .map(|line| {
+            let mut spans: Vec<Span> = vec![];
+            let mut links = vec![];
+            // start of the open hyperlink in the line
+            let mut link_start = 0;
+            let mut content = String::new();
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c != '\x1b' {
+                    content.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    // CSI: parameters and intermediate bytes up to a final byte in `@`..=`~`
+                    Some('[') => {
+                        let mut parameters = String::new();
+                        let mut end = None;
+                        for c in chars.by_ref() {
+                            if ('@'..='~').contains(&c) {
+                                end = Some(c);
+                                break;
+                            }
+                            parameters.push(c);
+                        }
+                        if end == Some('m') {
+                            if !content.is_empty() {
+                                spans.push(Span::styled(std::mem::take(&mut content), style));
+                            }
+                            style = apply_sgr(style, base, &parameters);
+                        }
+                    }
+                    // OSC: up to BEL or the string terminator `ESC \`
+                    Some(']') => {
+                        let mut command = String::new();
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' {
+                                break;
+                            }
+                            if c == '\x1b' && chars.peek() == Some(&'\\') {
+                                chars.next();
+                                break;
+                            }
+                            command.push(c);
+                        }
+                        // OSC 8: `8;params;URL` opens a hyperlink, an empty URL closes it
+                        if let Some(link) = command.strip_prefix("8;") {
+                            if !content.is_empty() {
+                                spans.push(Span::styled(std::mem::take(&mut content), style));
+                            }
+                            let offset = spans.iter().map(|span| span.content.len()).sum();
+                            if let Some(url) = url.take() {
+                                push_link(&mut links, link_start..offset, url);
+                            }
+                            url = link
+                                .split_once(';')
+                                .map(|(_, url)| url.to_string())
+                                .filter(|url| !url.is_empty());
+                            link_start = offset;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if !content.is_empty() {
+                spans.push(Span::styled(content, style));
+            }
+            if let Some(url) = &url {
+                let end = spans.iter().map(|span| span.content.len()).sum();
+                push_link(&mut links, link_start..end, url.clone());
+            }
+            (Spans::from(spans), links)
+        })
+        .collect()
+}
+
+fn push_link(links: &mut Vec<Link>, range: Range<usize>, url: String) {
+    if !range.is_empty() {
+        links.push(Link { range, url });
+    }
+}
+
+/// Applies the `;` separated parameters of an SGR sequence to `style`, resetting to `base`
+fn apply_sgr(mut style: Style, base: Style, parameters: &str) -> Style {
+    let mut codes = parameters
+        .split(';')
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => base,
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            6 => style.add_modifier(Modifier::RAPID_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(ansi_color(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => Style {
+                fg: base.fg.or(Some(Color::Reset)),
+                ..style
+            },
+            40..=47 => style.bg(ansi_color(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => Style {
+                bg: base.bg.or(Some(Color::Reset)),
+                ..style
+            },
+            90..=97 => style.fg(ansi_color(code - 90 + 8)),
+            100..=107 => style.bg(ansi_color(code - 100 + 8)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// The color of a `5;n` or `2;r;g;b` parameter following 38 or 48
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+/// One of the 16 colors of the ANSI palette, the bright ones from 8 on
+fn ansi_color(index: u8) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+impl TuiClap {
+    /// Sets whether ANSI escape sequences in written output are parsed, e.g. colors of tools the handlers shell out to.
+    /// SGR sequences are converted to styles, OSC 8 sequences to hyperlinks and other escape sequences removed,
+    /// instead of writing the raw escape bytes to the output. Disabled by default.
+    pub fn set_ansi_parsing(&mut self, enabled: bool) {
+        self.ansi_parsing = enabled;
+    }
+}
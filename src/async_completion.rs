@@ -0,0 +1,171 @@
+//! Completers running on worker threads, so that slow completion sources do not block the UI
+
+use crate::completion::{typed_word, value_prefix, with_value_prefix};
+use crate::{Candidate, Completer, TerminalEvent, TuiClap};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+type AsyncCompleter = Arc<dyn Completer + Send + Sync>;
+
+/// Asynchronous completers of `TuiClap` and the completion waiting for them
+pub(crate) struct AsyncCompletion {
+    completers: Vec<AsyncCompleter>,
+    /// Completers for the values of arguments by their id
+    arg_completers: HashMap<String, AsyncCompleter>,
+    tx: Sender<(u64, Vec<Candidate>)>,
+    rx: Receiver<(u64, Vec<Candidate>)>,
+    /// Id of the last started completion, to discard results of earlier ones
+    id: u64,
+    pending: Option<PendingCompletion>,
+}
+
+/// A completion waiting for the candidates of asynchronous completers
+struct PendingCompletion {
+    /// Content and cursor of the input when the completion was started
+    input: (String, usize),
+    /// Candidates collected so far
+    candidates: Vec<Candidate>,
+    /// Number of completers that did not deliver their candidates yet
+    remaining: usize,
+}
+
+impl Default for AsyncCompletion {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        AsyncCompletion {
+            completers: vec![],
+            arg_completers: HashMap::new(),
+            tx,
+            rx,
+            id: 0,
+            pending: None,
+        }
+    }
+}
+
+impl TuiClap {
+    /// Registers `completer` to provide candidates for every word like `TuiClap::add_completer`,
+    /// but runs it on a worker thread, e.g. to complete against a remote API without blocking the UI
+    pub fn add_async_completer(&mut self, completer: impl Completer + Send + Sync + 'static) {
+        self.async_completion.completers.push(Arc::new(completer));
+    }
+
+    /// Registers `completer` to provide candidates for values of the argument with the id `arg`
+    /// like `TuiClap::set_arg_completer`, but runs it on a worker thread
+    pub fn set_async_arg_completer(
+        &mut self,
+        arg: &str,
+        completer: impl Completer + Send + Sync + 'static,
+    ) {
+        self.async_completion
+            .arg_completers
+            .insert(arg.to_string(), Arc::new(completer));
+    }
+
+    /// Whether a completion waits for the candidates of asynchronous completers
+    pub fn is_completing(&self) -> bool {
+        self.async_completion.pending.is_some()
+    }
+
+    /// Discards the completion waiting for asynchronous completers, if any
+    pub fn cancel_completion(&mut self) {
+        self.async_completion.pending = None;
+    }
+
+    pub(crate) fn has_async_completers(&self, arg: Option<&str>) -> bool {
+        let completion = &self.async_completion;
+        !completion.completers.is_empty()
+            || arg.is_some_and(|arg| completion.arg_completers.contains_key(arg))
+    }
+
+    /// Runs the asynchronous completers on worker threads, to present their candidates together with `candidates`
+    /// once all of them finished. Each of them sends `TerminalEvent::CompletionReady` once it delivered its candidates.
+    pub(crate) fn start_async_completion(&mut self, candidates: Vec<Candidate>, arg: Option<&str>) {
+        let state = &self.command_input_state;
+        let line = state.content.clone();
+        let cursor = state.cursor;
+        let waker = self.waker.clone();
+        let prefix = value_prefix(&typed_word(&line, cursor)).to_string();
+
+        let completion = &mut self.async_completion;
+        completion.id += 1;
+        let mut completers: Vec<(AsyncCompleter, String)> = completion
+            .completers
+            .iter()
+            .map(|completer| (completer.clone(), String::new()))
+            .collect();
+        if let Some(completer) = arg.and_then(|arg| completion.arg_completers.get(arg)) {
+            completers.push((completer.clone(), prefix));
+        }
+
+        completion.pending = Some(PendingCompletion {
+            input: (line.clone(), cursor),
+            candidates,
+            remaining: completers.len(),
+        });
+        for (completer, prefix) in completers {
+            let tx = completion.tx.clone();
+            let waker = waker.clone();
+            let id = completion.id;
+            let line = line.clone();
+            thread::spawn(move || {
+                let candidates = with_value_prefix(&prefix, completer.complete(&line, cursor));
+                // the receiver is only gone if `TuiClap` was dropped
+                let _ = tx.send((id, candidates));
+                waker.wake(TerminalEvent::CompletionReady);
+            });
+        }
+    }
+
+    /// Collects the candidates delivered by asynchronous completers and presents them once all of them finished.
+    /// The completion is discarded if the input changed in the meantime.
+    pub(crate) fn poll_async_completion(&mut self) {
+        while let Ok((id, candidates)) = self.async_completion.rx.try_recv() {
+            let pending = match &mut self.async_completion.pending {
+                Some(pending) if id == self.async_completion.id => pending,
+                _ => continue,
+            };
+            pending.candidates.extend(candidates);
+            pending.remaining -= 1;
+            if pending.remaining > 0 {
+                continue;
+            }
+
+            let pending = self.async_completion.pending.take();
+            if let Some(pending) = pending {
+                let state = &self.command_input_state;
+                if pending.input.0 == state.content && pending.input.1 == state.cursor {
+                    self.present_completion(pending.candidates);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::waker::Waker;
+    use crate::{Candidate, TerminalEvent, TuiClap};
+    use clap::Command;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn finished_completer_sends_event() {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        let (tx, rx) = mpsc::channel();
+        tui.waker = Waker::new(tx);
+        tui.add_async_completer(|_: &str, _| vec![Candidate::new("remote")]);
+        tui.state().insert_str("rem");
+        tui.complete();
+        assert!(tui.is_completing());
+
+        let event = rx.recv_timeout(Duration::from_secs(5));
+        assert_eq!(event, Ok(TerminalEvent::CompletionReady));
+        tui.poll_async_completion();
+        assert!(!tui.is_completing());
+        assert_eq!(tui.state().content(), "remote ");
+    }
+}
@@ -0,0 +1,153 @@
+//! Handlers returning futures, which run on a tokio runtime so that slow commands do not block the UI
+
+use crate::spinner::Echo;
+use crate::{CancellationToken, CommandResult, TerminalEvent, TuiClap};
+use clap::ArgMatches;
+use std::future::Future;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+type HandlerResult = Result<Vec<String>, String>;
+
+/// The result of an asynchronous handler which completed
+struct Completion {
+    /// The number of the command which started the handler
+    command: usize,
+    /// The echo of the command, to show the duration behind it
+    echo: Option<Echo>,
+    duration: Duration,
+    result: HandlerResult,
+}
+
+/// Asynchronous handlers of `TuiClap` running at the moment
+pub(crate) struct AsyncHandlers {
+    tx: Sender<Completion>,
+    rx: Receiver<Completion>,
+    running: usize,
+    /// Whether the input is disabled while handlers are running
+    lock_input: bool,
+}
+
+impl Default for AsyncHandlers {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        AsyncHandlers {
+            tx,
+            rx,
+            running: 0,
+            lock_input: false,
+        }
+    }
+}
+
+impl TuiClap {
+    /// Registers `handler` for the subcommand `name` like `TuiClap::on`, but spawns the future it returns on the tokio
+    /// runtime, e.g. to run commands hitting the network without blocking the UI. Once the future completes,
+    /// `TerminalEvent::HandlerCompleted` is sent to the connected `Events`, see `TuiClap::connect_events`, and its lines
+    /// are written to the output and its error to the error stream, see `TuiClap::poll_handlers`.
+    /// The handler gets a `CancellationToken`, which is cancelled once the user presses `Ctrl+C`.
+    /// `TuiClap::dispatch` has to be called within the runtime.
+    pub fn on_async<F, Fut>(&mut self, name: &str, mut handler: F)
+    where
+        F: FnMut(&ArgMatches, CancellationToken) -> Fut + 'static,
+        Fut: Future<Output = HandlerResult> + Send + 'static,
+    {
+        self.register_handler(
+            name,
+            Box::new(move |matches, tui| {
+                let future = handler(matches, tui.cancellation_token());
+                tui.spawn_handler(future);
+                None
+            }),
+        );
+    }
+
+    /// Sets whether the input is disabled while asynchronous handlers are running, showing the spinner instead,
+    /// see `TuiClap::start_spinner`. Disabled by default, so that further commands can be entered meanwhile.
+    pub fn set_lock_input_while_running(&mut self, enabled: bool) {
+        self.async_handlers.lock_input = enabled;
+    }
+
+    /// Number of asynchronous handlers which did not complete yet
+    pub fn running_handlers(&self) -> usize {
+        self.async_handlers.running
+    }
+
+    fn spawn_handler(&mut self, future: impl Future<Output = HandlerResult> + Send + 'static) {
+        let tx = self.async_handlers.tx.clone();
+        let waker = self.waker.clone();
+        let command = self.command_output_state.sections_started;
+        let echo = self.echo.clone();
+        let started = Instant::now();
+        tokio::spawn(async move {
+            let result = future.await;
+            let completion = Completion {
+                command,
+                echo,
+                duration: started.elapsed(),
+                result,
+            };
+            // the receiver is only gone if `TuiClap` was dropped
+            let _ = tx.send(completion);
+            waker.wake(TerminalEvent::HandlerCompleted);
+        });
+        self.async_handlers.running += 1;
+        if self.async_handlers.lock_input {
+            self.set_enabled(false);
+            self.start_spinner();
+        }
+    }
+
+    /// Writes the results of the asynchronous handlers which completed since the last call, see `TuiClap::on_async`.
+    /// Their success is reported to the history unless another command was entered meanwhile.
+    /// Called by `TuiClap::fetch_event` on `TerminalEvent::HandlerCompleted` and by `TuiClap::render_output`,
+    /// applications handling the events of `Events` themselves call it on `TerminalEvent::HandlerCompleted`.
+    pub fn poll_handlers(&mut self) {
+        if self.async_handlers.running == 0 {
+            return;
+        }
+        while let Ok(completion) = self.async_handlers.rx.try_recv() {
+            self.async_handlers.running -= 1;
+            self.record_duration(completion.duration, completion.echo);
+            let result = CommandResult::from(completion.result);
+            if completion.command == self.command_output_state.sections_started {
+                self.command_input_state.report_status(result.status);
+            }
+            self.run_after_execute_hooks(&result);
+            self.write_result(result);
+        }
+        if self.async_handlers.running == 0 && self.async_handlers.lock_input {
+            self.stop_spinner();
+            self.set_enabled(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::waker::Waker;
+    use crate::{TerminalEvent, TuiClap};
+    use clap::Command;
+    use std::sync::mpsc;
+
+    #[test]
+    fn completed_handler_sends_event() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let _guard = runtime.enter();
+        let mut tui = TuiClap::from_app(Command::new("app").subcommand(Command::new("fetch")));
+        let (tx, rx) = mpsc::channel();
+        tui.waker = Waker::new(tx);
+        tui.on_async("fetch", |_, _| async { Ok(vec!["fetched".to_string()]) });
+        tui.state().insert_str("fetch");
+        tui.execute();
+        assert_eq!(tui.running_handlers(), 1);
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Empty));
+
+        runtime.block_on(tokio::task::yield_now());
+        assert_eq!(rx.try_recv(), Ok(TerminalEvent::HandlerCompleted));
+        tui.poll_handlers();
+        assert_eq!(tui.running_handlers(), 0);
+    }
+}
@@ -0,0 +1,359 @@
+//! Built-in commands of the REPL like `help`, `exit`, `clear` and `history`, which are run instead of being parsed by clap
+
+use crate::completion::Candidate;
+use crate::tokenizer::tokenize;
+use crate::{CommandResult, HighlightStyles, TuiClap};
+use clap::error::ErrorKind;
+use std::collections::BTreeMap;
+use std::iter;
+use std::path::Path;
+use tui::style::Style;
+
+/// A built-in command, see `TuiClap::add_builtin`
+pub(crate) struct Builtin {
+    help: String,
+    /// `None` while the built-in command runs, since it can access the `TuiClap`
+    handler: Option<BuiltinHandler>,
+}
+
+type BuiltinHandler = Box<dyn FnMut(&[String], &mut TuiClap) -> Result<Vec<String>, String>>;
+
+/// The built-in commands every `TuiClap` starts with
+pub(crate) fn default_builtins() -> BTreeMap<String, Builtin> {
+    let mut builtins = BTreeMap::new();
+    let mut add = |name: &str, help: &str, handler: BuiltinHandler| {
+        let help = help.to_string();
+        let handler = Some(handler);
+        builtins.insert(name.to_string(), Builtin { help, handler });
+    };
+    add(
+        "help",
+        "Print the help of the app, a subcommand or a built-in command",
+        Box::new(|args, tui| tui.builtin_help(args)),
+    );
+    add(
+        "exit",
+        "Exit the app, or leave the scope of a subcommand",
+        Box::new(|_, tui| {
+            if !tui.leave_scope() {
+                tui.exit_requested = true;
+            }
+            Ok(vec![])
+        }),
+    );
+    add(
+        "clear",
+        "Clear the output",
+        Box::new(|_, tui| {
+            tui.clear_output();
+            Ok(vec![])
+        }),
+    );
+    add(
+        "alias",
+        "List the aliases, print one with `alias <name>` or define one with `alias <name>=<command>`",
+        Box::new(|args, tui| tui.builtin_alias(args)),
+    );
+    add(
+        "unalias",
+        "Remove aliases",
+        Box::new(|args, tui| tui.builtin_unalias(args)),
+    );
+    add(
+        "set",
+        "List the variables or set one with `set <name>=<value>`",
+        Box::new(|args, tui| tui.builtin_set(args)),
+    );
+    add(
+        "unset",
+        "Remove variables",
+        Box::new(|args, tui| tui.builtin_unset(args)),
+    );
+    add(
+        "source",
+        "Run the commands in a file line by line",
+        Box::new(|args, tui| match args {
+            [path] => tui.run_script(Path::new(path)).map(|()| vec![]),
+            _ => Err("source: expected one file".to_string()),
+        }),
+    );
+    add(
+        "jobs",
+        "List the commands running in the background",
+        Box::new(|_, tui| tui.builtin_jobs()),
+    );
+    add(
+        "history",
+        "Print the last n, or all, entries of the history numbered",
+        Box::new(|args, tui| {
+            let history = tui.command_input_state.history();
+            let count = match args.first().map(|count| count.parse::<usize>()) {
+                None => history.len(),
+                Some(Ok(count)) => count.min(history.len()),
+                Some(Err(err)) => return Err(format!("history: {}", err)),
+            };
+            Ok(history
+                .iter()
+                .enumerate()
+                .skip(history.len() - count)
+                .map(|(index, entry)| format!("{:>5}  {}", index + 1, entry.command))
+                .collect())
+        }),
+    );
+    add(
+        "save",
+        "Save the output to a file",
+        Box::new(|args, tui| {
+            // the words are joined again, so that the path may contain spaces without quoting
+            let path = args.join(" ");
+            if path.is_empty() {
+                return Err("save: missing file".to_string());
+            }
+            let lines = tui.command_output_state.len();
+            match tui.command_output_state.dump(&path) {
+                Ok(()) => Ok(vec![format!("saved {} lines to {}", lines, path)]),
+                Err(err) => Err(format!("save: {}: {}", path, err)),
+            }
+        }),
+    );
+    builtins
+}
+
+impl TuiClap {
+    /// Registers the built-in command `name` with its `help`, replacing any previous built-in command of that name.
+    /// Built-in commands are run with their arguments instead of being parsed by clap, unless the app, or the subcommand
+    /// whose scope was entered, has a subcommand of that name, which wins. They are completed as first word
+    /// and listed by the `help` built-in command. The returned lines are written to the output, an error to the
+    /// error stream. `help`, `exit`, `clear`, `alias`, `unalias`, `set`, `unset`, `source`, `jobs`, `history` and
+    /// `save` are registered by default.
+    pub fn add_builtin(
+        &mut self,
+        name: &str,
+        help: &str,
+        handler: impl FnMut(&[String], &mut TuiClap) -> Result<Vec<String>, String> + 'static,
+    ) {
+        let builtin = Builtin {
+            help: help.to_string(),
+            handler: Some(Box::new(handler)),
+        };
+        self.builtins.insert(name.to_string(), builtin);
+    }
+
+    /// Removes the built-in command `name`, e.g. one the app has no subcommand for but which should not be run.
+    /// Returns `false` if there is no such built-in command.
+    pub fn remove_builtin(&mut self, name: &str) -> bool {
+        self.builtins.remove(name).is_some()
+    }
+
+    /// Whether the `exit` built-in command was entered, after which `TuiClap::fetch_event` returns `false`
+    pub fn is_exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    /// Runs `content` if it is a built-in command and reports its success to the history, returns `false` otherwise
+    pub(crate) fn run_builtin(&mut self, content: &str) -> bool {
+        let words = match tokenize(content) {
+            Ok(words) => words,
+            Err(_) => return false,
+        };
+        let name = match words.first() {
+            Some(name) => name,
+            None => return false,
+        };
+        if !self.is_builtin(name) {
+            return false;
+        }
+        let mut handler = match self.builtins.get_mut(name) {
+            Some(builtin) => match builtin.handler.take() {
+                Some(handler) => handler,
+                None => {
+                    self.write_error(format!("error: {} cannot be run from within itself", name));
+                    self.command_input_state.report_success(false);
+                    return true;
+                }
+            },
+            None => return false,
+        };
+        let result = handler(&words[1..], self);
+        if let Some(builtin) = self.builtins.get_mut(name) {
+            builtin.handler.get_or_insert(handler);
+        }
+        let result = CommandResult::from(result);
+        self.command_input_state.report_status(result.status);
+        self.run_after_execute_hooks(&result);
+        self.write_result(result);
+        true
+    }
+
+    /// Whether `name` runs a built-in command, which is not the case if a subcommand of that name hides it.
+    /// The `help` built-in command is not hidden by the `help` subcommand clap generates, since it prints the same help.
+    pub(crate) fn is_builtin(&self, name: &str) -> bool {
+        self.builtins.contains_key(name)
+            && (name == "help" || self.scoped_app().find_subcommand(name).is_none())
+    }
+
+    /// The built-in commands as completion candidates described by their help,
+    /// if the word under the byte offset `cursor` of `line` is the first one
+    pub(crate) fn builtin_candidates(&self, line: &str, cursor: usize) -> Vec<Candidate> {
+        if line[..cursor].trim_start().contains(char::is_whitespace) {
+            return vec![];
+        }
+        self.builtins
+            .iter()
+            .filter(|(name, _)| self.is_builtin(name))
+            .map(|(name, builtin)| {
+                Candidate::with_description(name.as_str(), builtin.help.as_str())
+            })
+            .collect()
+    }
+
+    /// Highlights the name of the built-in command or alias `line` starts with as command, `None` if it is neither
+    pub(crate) fn builtin_highlights(
+        &self,
+        styles: &HighlightStyles,
+        line: &str,
+    ) -> Option<Vec<(usize, usize, Style)>> {
+        let start = line.len() - line.trim_start().len();
+        let end = line[start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |end| start + end);
+        let name = &line[start..end];
+        (self.is_builtin(name) || self.aliases.contains_key(name))
+            .then(|| vec![(start, end, styles.command)])
+    }
+
+    /// `help` writes the help of the app followed by the built-in commands, `help <subcommand>…` the help of a
+    /// subcommand and `help <builtin>` the help of a built-in command, in the help popup if enabled
+    fn builtin_help(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        if let [name] = args {
+            if let Some(builtin) = self.builtins.get(name).filter(|_| self.is_builtin(name)) {
+                return Ok(vec![format!("{}: {}", name, builtin.help)]);
+            }
+        }
+        let mut command = self.scoped_app();
+        for name in args {
+            command = command
+                .find_subcommand(name)
+                .ok_or_else(|| format!("help: unknown command '{}'", name))?;
+        }
+        let words: Vec<String> = iter::once(self.clap.get_name().to_string())
+            .chain(self.scope().iter().cloned())
+            .chain(args.iter().cloned())
+            .chain(iter::once("--help".to_string()))
+            .collect();
+        let mut text = match self.clap.try_get_matches_from_mut(words) {
+            // the error is the help text of the subcommand it was requested for
+            Err(err) if err.kind() == ErrorKind::DisplayHelp => err.to_string(),
+            Err(err) => return Err(err.to_string()),
+            Ok(_) => return Ok(vec![]),
+        };
+        if args.is_empty() {
+            let width = self.builtins.keys().map(String::len).max().unwrap_or(0);
+            text = text.trim_end().to_string();
+            text.push_str("\n\nBuiltins:\n");
+            for (name, builtin) in self
+                .builtins
+                .iter()
+                .filter(|(name, _)| self.is_builtin(name))
+            {
+                text.push_str(&format!(
+                    "  {:width$}  {}\n",
+                    name,
+                    builtin.help,
+                    width = width
+                ));
+            }
+        }
+        if self.help_popup_enabled {
+            self.show_help_popup(&text);
+        } else {
+            self.write_help(text);
+        }
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TuiClap;
+    use clap::{Arg, Command};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A `TuiClap` whose handlers of `names` record that they ran
+    fn with_handlers(
+        app: Command,
+        names: &[&'static str],
+    ) -> (TuiClap, Rc<RefCell<Vec<&'static str>>>) {
+        let mut tui = TuiClap::from_app(app);
+        let ran = Rc::new(RefCell::new(vec![]));
+        for &name in names {
+            let ran = ran.clone();
+            tui.on(name, move |_, _| {
+                ran.borrow_mut().push(name);
+                Ok(vec![])
+            });
+        }
+        (tui, ran)
+    }
+
+    fn enter(tui: &mut TuiClap, line: &str) {
+        tui.state().insert_str(line);
+        tui.execute();
+    }
+
+    #[test]
+    fn app_subcommand_wins_over_builtin() {
+        let app = Command::new("app").subcommand(Command::new("clear"));
+        let mut tui = TuiClap::from_app(app);
+        let matches = tui.parse_line("clear").expect("parsed by clap");
+        assert_eq!(matches.subcommand_name(), Some("clear"));
+        assert!(!tui.is_builtin("clear"));
+        // the other built-in commands still run
+        assert!(tui.parse_line("jobs").is_err());
+        assert!(tui.is_builtin("jobs"));
+    }
+
+    #[test]
+    fn app_subcommand_wins_within_scope() {
+        let app = Command::new("app")
+            .subcommand(Command::new("config").subcommand(Command::new("clear")));
+        let mut tui = TuiClap::from_app(app);
+        tui.set_scopes(true);
+        assert!(tui.is_builtin("clear"));
+        assert!(tui.enter_scope("config"));
+        let matches = tui.parse_line("clear").expect("parsed by clap");
+        let (name, config) = matches.subcommand().expect("config subcommand");
+        assert_eq!(name, "config");
+        assert_eq!(config.subcommand_name(), Some("clear"));
+    }
+
+    #[test]
+    fn app_set_and_unset_run_their_handlers() {
+        let app = Command::new("app")
+            .subcommand(Command::new("set").arg(Arg::new("assignment")))
+            .subcommand(Command::new("unset"));
+        let (mut tui, ran) = with_handlers(app, &["set", "unset"]);
+        enter(&mut tui, "set x=1");
+        enter(&mut tui, "unset");
+        assert_eq!(*ran.borrow(), ["set", "unset"]);
+        assert_eq!(tui.variable("x"), None);
+    }
+
+    #[test]
+    fn app_history_runs_its_handler() {
+        let app = Command::new("app").subcommand(Command::new("history").arg(Arg::new("count")));
+        let (mut tui, ran) = with_handlers(app, &["history"]);
+        enter(&mut tui, "history 5");
+        assert_eq!(*ran.borrow(), ["history"]);
+    }
+
+    #[test]
+    fn app_save_runs_its_handler() {
+        let app = Command::new("app").subcommand(Command::new("save").arg(Arg::new("file")));
+        let (mut tui, ran) = with_handlers(app, &["save"]);
+        enter(&mut tui, "save out.txt");
+        assert_eq!(*ran.borrow(), ["save"]);
+        assert!(!std::path::Path::new("out.txt").exists());
+    }
+}
@@ -0,0 +1,45 @@
+//! Cancelling running handlers with `Ctrl+C`, which they notice through a `CancellationToken`
+
+use crate::TuiClap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Tells a handler that the user cancelled it with `ReplAction::Interrupt`, see `TuiClap::on_async`.
+/// Long-running handlers check `CancellationToken::is_cancelled` regularly and return early once it is set.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels the handlers holding a clone of this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl TuiClap {
+    /// The token cancelled by the next `ReplAction::Interrupt`, e.g. for a handler passing it on to the threads it starts
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    /// Cancels the running handlers through their `CancellationToken`, discards the input and the questions asked with
+    /// `TuiClap::ask`, echoing `^C` to the output. Done on `ReplAction::Interrupt`, bound to `Ctrl+C`, even while the input is disabled.
+    pub fn interrupt(&mut self) {
+        self.cancellation_token.cancel();
+        self.cancellation_token = CancellationToken::new();
+        let content = self.command_input_state.content().to_string();
+        self.command_input_state.reset();
+        self.cancel_questions();
+        self.write_to_output(format!("{}^C", content));
+    }
+}
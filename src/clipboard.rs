@@ -0,0 +1,26 @@
+//! Access to the system clipboard, available with the `clipboard` feature
+
+/// Lazily connected handle to the system clipboard.
+/// The connection is kept open since on some platforms copied text is only served as long as it is alive.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn connect(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        let clipboard = match self.inner.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.inner.insert(clipboard))
+    }
+
+    pub(crate) fn get_text(&mut self) -> Result<String, arboard::Error> {
+        self.connect()?.get_text()
+    }
+
+    pub(crate) fn set_text(&mut self, text: &str) -> Result<(), arboard::Error> {
+        self.connect()?.set_text(text)
+    }
+}
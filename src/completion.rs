@@ -0,0 +1,449 @@
+//! Tab completion of the input according to the grammar of the clap app and registered completers
+
+use crate::fuzzy::{fuzzy_match, prefix_matches};
+use crate::grammar::{find_long, Walker};
+use crate::tokenizer::{quote, words, Word};
+use crate::{CommandInputState, CompletionMenuState, TuiClap};
+use clap::builder::PossibleValue;
+use clap::{Arg, Command};
+
+/// A word the word before the cursor can be completed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub value: String,
+    /// Shown next to the value in the completion menu
+    pub description: Option<String>,
+}
+
+impl Candidate {
+    pub fn new(value: impl Into<String>) -> Candidate {
+        Candidate {
+            value: value.into(),
+            description: None,
+        }
+    }
+
+    pub fn with_description(value: impl Into<String>, description: impl Into<String>) -> Candidate {
+        Candidate {
+            value: value.into(),
+            description: Some(description.into()),
+        }
+    }
+}
+
+/// Provides completion candidates for domain-specific values, like table names or hostnames,
+/// registered with `TuiClap::add_completer` or `TuiClap::set_arg_completer`.
+/// Implemented for closures taking the same arguments as `Completer::complete`.
+pub trait Completer {
+    /// Candidates for the word before the byte offset `cursor` in `line`.
+    /// Candidates that do not start with the word are ignored, so all values can be returned.
+    fn complete(&self, line: &str, cursor: usize) -> Vec<Candidate>;
+}
+
+impl<F: Fn(&str, usize) -> Vec<Candidate>> Completer for F {
+    fn complete(&self, line: &str, cursor: usize) -> Vec<Candidate> {
+        self(line, cursor)
+    }
+}
+
+/// How `TuiClap::complete` presents several candidates that have no common prefix extending the word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompletionMode {
+    /// List the candidates in the completion menu
+    #[default]
+    Menu,
+    /// Insert the first candidate in place of the word, pressing `Tab` again replaces it with the next one
+    /// and `BackTab` with the previous one, like `menu-complete` of readline
+    Cycle,
+}
+
+/// The word of `line` ending at the byte offset `cursor`, split like `tokenize` does, so that a quoted word may
+/// contain whitespace. `None` if the cursor follows whitespace separating words.
+fn word_before(line: &str, cursor: usize) -> Option<Word> {
+    words(&line[..cursor])
+        .0
+        .pop()
+        .filter(|word| word.end == cursor)
+}
+
+/// Start of the word under the cursor in `line`, including an opening quote
+pub(crate) fn word_start(line: &str, cursor: usize) -> usize {
+    word_before(line, cursor).map_or(cursor, |word| word.start)
+}
+
+/// End of the word under the cursor in `line`, including a closing quote
+pub(crate) fn word_end(line: &str, cursor: usize) -> usize {
+    words(line)
+        .0
+        .into_iter()
+        .find(|word| (word.start..=word.end).contains(&cursor))
+        .map_or(cursor, |word| word.end)
+}
+
+/// The part of the word under the cursor in `line` before the cursor, without quotes and escapes,
+/// e.g. `my f` for `echo "my f`
+pub(crate) fn typed_word(line: &str, cursor: usize) -> String {
+    word_before(line, cursor).map_or_else(String::new, |word| word.text)
+}
+
+/// Candidates from the grammar of `app` for the word before the cursor in `line`, which starts with the binary name
+/// if `binary_name` is set, and the argument the word is the value of, if any
+pub(crate) fn candidates<'a>(
+    app: &'a Command,
+    line: &str,
+    cursor: usize,
+    binary_name: bool,
+) -> (Vec<Candidate>, Option<&'a Arg>) {
+    let start = word_start(line, cursor);
+    let word = typed_word(line, cursor);
+    let word = word.as_str();
+
+    let mut walker = Walker::new(app, binary_name);
+    for word in words(&line[..start]).0 {
+        walker.next(&word.text);
+    }
+
+    if !walker.started() {
+        (vec![Candidate::new(app.get_name())], None)
+    } else if let Some(option) = walker.pending_option {
+        (possible_values(option, ""), Some(option))
+    } else if word.starts_with("--") && word.contains('=') && !walker.only_positionals {
+        let name = &word[2..word.find('=').unwrap_or(word.len())];
+        match find_long(walker.command, name) {
+            Some(option) => {
+                let prefix = format!("--{}=", name);
+                (possible_values(option, &prefix), Some(option))
+            }
+            None => (vec![], None),
+        }
+    } else if word.starts_with('-') && !walker.only_positionals {
+        let arguments = walker
+            .command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set());
+        let mut flags = vec![];
+        for arg in arguments {
+            let description = arg.get_help().map(ToString::to_string);
+            if let Some(long) = arg.get_long() {
+                flags.push(Candidate {
+                    value: format!("--{}", long),
+                    description: description.clone(),
+                });
+            }
+            if let (Some(short), "-") = (arg.get_short(), word) {
+                flags.push(Candidate {
+                    value: format!("-{}", short),
+                    description,
+                });
+            }
+        }
+        (flags, None)
+    } else {
+        let positional = walker.next_positional();
+        let values = positional.map(|arg| possible_values(arg, ""));
+        let candidates = walker
+            .command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .map(|subcommand| Candidate {
+                value: subcommand.get_name().to_string(),
+                description: subcommand.get_about().map(ToString::to_string),
+            })
+            .chain(values.into_iter().flatten())
+            .collect();
+        (candidates, positional)
+    }
+}
+
+/// The visible values declared for `arg` by its value parser, with `prefix` prepended
+fn possible_values(arg: &Arg, prefix: &str) -> Vec<Candidate> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value: &PossibleValue| Candidate {
+            value: format!("{}{}", prefix, value.get_name()),
+            description: value.get_help().map(ToString::to_string),
+        })
+        .collect()
+}
+
+/// The `--option=` of a word `--option=value`, which is prepended to the candidates for the value
+pub(crate) fn value_prefix(word: &str) -> &str {
+    match word.find('=') {
+        Some(i) if word.starts_with("--") => &word[..=i],
+        _ => "",
+    }
+}
+
+pub(crate) fn with_value_prefix(prefix: &str, candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates
+        .into_iter()
+        .map(|candidate| Candidate {
+            value: format!("{}{}", prefix, candidate.value),
+            ..candidate
+        })
+        .collect()
+}
+
+/// Longest common prefix of the values of `candidates`
+fn common_prefix(candidates: &[Candidate]) -> &str {
+    let first = match candidates.first() {
+        Some(first) => first.value.as_str(),
+        None => return "",
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.value.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, c), _)| i + c.len_utf8())
+            .min(len);
+    }
+    &first[..len]
+}
+
+impl CommandInputState {
+    /// Replaces the byte range `start..end` of the content with `text` and moves the cursor behind it
+    pub(crate) fn replace_word(&mut self, start: usize, end: usize, text: &str) {
+        self.selection_anchor = None;
+        self.content.replace_range(start..end, text);
+        self.cursor = start + text.len();
+    }
+
+    /// Moves the cursor behind the whitespace following a completed word, inserting a space if there is none
+    pub(crate) fn finish_word(&mut self) {
+        match self.content[self.cursor..].chars().next() {
+            Some(c) if c.is_whitespace() => self.cursor += c.len_utf8(),
+            _ => self.insert_str(" "),
+        }
+    }
+}
+
+impl TuiClap {
+    /// Registers `completer` to provide candidates for every word, in addition to the clap app
+    pub fn add_completer(&mut self, completer: impl Completer + 'static) {
+        self.completers.push(Box::new(completer));
+    }
+
+    /// Registers `completer` to provide candidates for values of the argument with the id `arg`,
+    /// replacing a previously registered completer for it. Values given as `--option=value` are completed
+    /// with the candidates prefixed by `--option=`.
+    pub fn set_arg_completer(&mut self, arg: &str, completer: impl Completer + 'static) {
+        self.arg_completers
+            .insert(arg.to_string(), Box::new(completer));
+    }
+
+    /// Sets whether the word before the cursor is matched fuzzily against the candidates of a completion,
+    /// so that `gst` completes to `git-status`. Disabled by default, completing candidates starting with the word.
+    pub fn set_fuzzy_completion(&mut self, fuzzy: bool) {
+        self.fuzzy_completion = fuzzy;
+    }
+
+    /// Sets how several candidates are presented, in the completion menu by default
+    pub fn set_completion_mode(&mut self, mode: CompletionMode) {
+        self.completion_mode = mode;
+    }
+
+    /// Candidates for the word before the cursor from the clap app and the registered completers,
+    /// and the id of the argument the word is the value of, if any
+    fn completion_candidates(&mut self) -> (Vec<Candidate>, Option<String>) {
+        self.clap.build();
+        let state = &self.command_input_state;
+        // the arguments following an alias are completed according to its expansion
+        let (line, cursor) = self.expand_alias_before(&state.content, state.cursor);
+        let line = line.as_str();
+        let word = typed_word(line, cursor);
+
+        let (mut candidates, arg) =
+            candidates(self.scoped_app(), line, cursor, self.typed_binary_name());
+        if let Some(completer) = arg.and_then(|arg| self.arg_completers.get(arg.get_id().as_str()))
+        {
+            candidates.extend(with_value_prefix(
+                value_prefix(&word),
+                completer.complete(line, cursor),
+            ));
+        }
+        for completer in &self.completers {
+            candidates.extend(completer.complete(line, cursor));
+        }
+        candidates.extend(self.builtin_candidates(line, cursor));
+        candidates.extend(self.alias_candidates(line, cursor));
+        (candidates, arg.map(|arg| arg.get_id().to_string()))
+    }
+
+    /// The candidates matching the word before the cursor, together with the byte offsets of the chars matching
+    /// the word. Sorted by value, or by score first if fuzzy completion is enabled.
+    fn matching_candidates(&self, mut candidates: Vec<Candidate>) -> Vec<(Candidate, Vec<usize>)> {
+        let state = &self.command_input_state;
+        let word = typed_word(&state.content, state.cursor);
+        let word = word.as_str();
+        candidates.sort_by(|a, b| a.value.cmp(&b.value));
+        candidates.dedup_by(|a, b| a.value == b.value);
+
+        if !self.fuzzy_completion {
+            return candidates
+                .into_iter()
+                .filter(|candidate| candidate.value.starts_with(word))
+                .map(|candidate| (candidate, prefix_matches(word)))
+                .collect();
+        }
+        let mut matched: Vec<(i64, Candidate, Vec<usize>)> = candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let (score, matches) = fuzzy_match(word, &candidate.value)?;
+                Some((score, candidate, matches))
+            })
+            .collect();
+        // stable, so that candidates with the same score stay sorted by value
+        matched.sort_by_key(|(score, _, _)| -score);
+        matched
+            .into_iter()
+            .map(|(_, candidate, matches)| (candidate, matches))
+            .collect()
+    }
+
+    /// Completes the word before the cursor to a subcommand, flag or option of the clap app,
+    /// to a possible value if the word is the value of an option or positional argument, or to a candidate of a
+    /// registered `Completer`.
+    /// Candidates are matched against the part of the word before the cursor, and replace the whole word.
+    /// A single candidate is inserted followed by a space, unless it is a directory ending with a `/`.
+    /// Candidates containing whitespace or quotes are inserted quoted, e.g. `'my file.txt'`, see `tokenize`.
+    /// Several candidates are completed to their common prefix, or presented according to the `CompletionMode`
+    /// if there is no common prefix extending the word.
+    ///
+    /// If asynchronous completers are registered, see `TuiClap::add_async_completer`, the candidates are presented
+    /// once all of them finished, while `completing…` is shown next to the word.
+    pub fn complete(&mut self) {
+        // answers to questions are no commands
+        if self.is_asking() {
+            return;
+        }
+        let (candidates, arg) = self.completion_candidates();
+        if self.has_async_completers(arg.as_deref()) {
+            self.start_async_completion(candidates, arg.as_deref());
+        } else {
+            self.present_completion(candidates);
+        }
+    }
+
+    /// Inserts the candidates matching the word before the cursor or lists them in the completion menu
+    pub(crate) fn present_completion(&mut self, candidates: Vec<Candidate>) {
+        let candidates = self.matching_candidates(candidates);
+        let state = &mut self.command_input_state;
+        let start = word_start(&state.content, state.cursor);
+        let end = word_end(&state.content, state.cursor);
+        let typed = typed_word(&state.content, state.cursor);
+        match candidates.len() {
+            0 => {}
+            1 => {
+                let value = &candidates[0].0.value;
+                // the quote of a directory is left open, so that a path within it can be completed next
+                let directory = value.ends_with('/');
+                state.replace_word(start, end, &quote(value, !directory));
+                if !directory {
+                    state.finish_word();
+                }
+            }
+            _ => {
+                let (candidates, matches): (Vec<Candidate>, Vec<Vec<usize>>) =
+                    candidates.into_iter().unzip();
+                let prefix = common_prefix(&candidates);
+                if prefix.len() > typed.len() && prefix.starts_with(&typed) {
+                    // the rest of the word is kept, since the word is not complete yet
+                    let cursor = state.cursor;
+                    state.replace_word(start, cursor, &quote(prefix, false));
+                } else {
+                    let menu = CompletionMenuState::new(candidates, matches, state, start, end);
+                    self.completion_menu_state = Some(menu);
+                    if self.completion_mode == CompletionMode::Cycle {
+                        self.select_completion(0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{typed_word, word_end, word_start};
+    use crate::{Candidate, TuiClap};
+    use clap::{Arg, Command};
+
+    #[test]
+    fn word_under_cursor_follows_quotes_and_escapes() {
+        let line = "echo \"my f";
+        assert_eq!(word_start(line, line.len()), 5);
+        assert_eq!(typed_word(line, line.len()), "my f");
+
+        let line = "echo my\\ f";
+        assert_eq!(word_start(line, line.len()), 5);
+        assert_eq!(typed_word(line, line.len()), "my f");
+
+        let line = "echo 'a b'c";
+        assert_eq!(word_start(line, line.len()), 5);
+        assert_eq!(typed_word(line, line.len()), "a bc");
+    }
+
+    #[test]
+    fn no_word_after_whitespace() {
+        let line = "echo ";
+        assert_eq!(word_start(line, line.len()), line.len());
+        assert_eq!(typed_word(line, line.len()), "");
+        assert_eq!(word_end(line, line.len()), line.len());
+    }
+
+    #[test]
+    fn word_end_includes_closing_quote() {
+        let line = "echo \"my file\" x";
+        assert_eq!(word_start(line, 9), 5);
+        assert_eq!(word_end(line, 9), 14);
+        assert_eq!(word_end(line, 5), 14);
+        assert_eq!(word_end(line, 16), 16);
+    }
+
+    #[test]
+    fn completes_quoted_word_with_whitespace() {
+        let app = Command::new("app").subcommand(Command::new("echo").arg(Arg::new("file")));
+        let mut tui = TuiClap::from_app(app);
+        tui.add_completer(|_: &str, _: usize| {
+            vec![Candidate::new("my file.txt"), Candidate::new("other")]
+        });
+        tui.state().insert_str("echo \"my f");
+        tui.complete();
+        assert_eq!(tui.state().content(), "echo 'my file.txt' ");
+    }
+
+    #[test]
+    fn common_prefix_with_whitespace_is_left_open() {
+        let app = Command::new("app").subcommand(Command::new("echo").arg(Arg::new("file")));
+        let mut tui = TuiClap::from_app(app);
+        tui.add_completer(|_: &str, _: usize| {
+            vec![
+                Candidate::new("my file.txt"),
+                Candidate::new("my files.txt"),
+            ]
+        });
+        tui.state().insert_str("echo my");
+        tui.complete();
+        assert_eq!(tui.state().content(), "echo 'my file");
+        tui.state().insert_str("s");
+        tui.complete();
+        assert_eq!(tui.state().content(), "echo 'my files.txt' ");
+    }
+
+    #[test]
+    fn menu_inserts_quoted_candidates() {
+        let app = Command::new("app").subcommand(Command::new("echo").arg(Arg::new("file")));
+        let mut tui = TuiClap::from_app(app);
+        tui.add_completer(|_: &str, _: usize| vec![Candidate::new("a b"), Candidate::new("it's")]);
+        tui.state().insert_str("echo ");
+        tui.complete();
+        tui.select_completion(0);
+        assert_eq!(tui.state().content(), "echo 'a b'");
+        tui.select_completion(1);
+        assert_eq!(tui.state().content(), "echo 'it'\\''s'");
+    }
+}
@@ -0,0 +1,340 @@
+//! Popup menu listing the candidates of a completion
+
+use crate::completion::word_start;
+use crate::tokenizer::quote;
+use crate::{Candidate, CommandInputState, CompletionMode, TuiClap};
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::backend::Backend;
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Clear, Paragraph, StatefulWidget, Widget};
+use tui::Frame;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Widget listing completion candidates, rendered by `TuiClap::render_input` next to the completed word
+#[derive(Debug, Clone)]
+pub struct CompletionMenu {
+    style: Style,
+    selected_style: Style,
+    description_style: Style,
+    match_style: Style,
+    max_height: u16,
+}
+
+impl Default for CompletionMenu {
+    fn default() -> Self {
+        CompletionMenu {
+            style: Style::default().bg(Color::DarkGray),
+            selected_style: Style::default().add_modifier(Modifier::REVERSED),
+            description_style: Style::default().fg(Color::Gray),
+            match_style: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            max_height: 8,
+        }
+    }
+}
+
+impl CompletionMenu {
+    /// Sets the style of the candidates, dark gray background by default
+    pub fn style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Sets the style of the selected candidate, reversed by default
+    pub fn selected_style(&mut self, style: Style) {
+        self.selected_style = style;
+    }
+
+    /// Sets the style of the descriptions shown next to the candidates, gray by default
+    pub fn description_style(&mut self, style: Style) {
+        self.description_style = style;
+    }
+
+    /// Sets the style of the chars of the candidates matching the typed word, bold yellow by default
+    pub fn match_style(&mut self, style: Style) {
+        self.match_style = style;
+    }
+
+    /// Sets the maximum number of candidates shown at once, 8 by default
+    pub fn max_height(&mut self, height: u16) {
+        self.max_height = height;
+    }
+}
+
+/// The candidates of a completion and which one is selected
+#[derive(Debug, Clone, Default)]
+pub struct CompletionMenuState {
+    candidates: Vec<Candidate>,
+    /// Byte offsets of the chars of each candidate that match the typed word
+    matches: Vec<Vec<usize>>,
+    selected: Option<usize>,
+    /// Byte offset of the completed word in the content of the input
+    start: usize,
+    /// Byte offset behind the completed word, which changes with the previewed candidate
+    end: usize,
+    /// The completed word as it was typed and the offset of the cursor in it, restored when the menu is cancelled
+    typed: (String, usize),
+    /// Index of the first visible candidate
+    offset: usize,
+}
+
+impl CompletionMenuState {
+    pub(crate) fn new(
+        candidates: Vec<Candidate>,
+        matches: Vec<Vec<usize>>,
+        input: &CommandInputState,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        CompletionMenuState {
+            candidates,
+            matches,
+            end,
+            typed: (input.content[start..end].to_string(), input.cursor - start),
+            selected: None,
+            start,
+            offset: 0,
+        }
+    }
+
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// Index of the selected candidate, `None` before the first one is selected
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Width of the widest value
+    fn value_width(&self) -> usize {
+        self.candidates
+            .iter()
+            .map(|candidate| candidate.value.width())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Width of the widest candidate with description
+    fn width(&self) -> u16 {
+        let description_width = self
+            .candidates
+            .iter()
+            .filter_map(|candidate| candidate.description.as_ref())
+            .map(|description| description.width() + 2)
+            .max()
+            .unwrap_or(0);
+        (self.value_width() + description_width) as u16
+    }
+}
+
+impl StatefulWidget for CompletionMenu {
+    type State = CompletionMenuState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let height = area.height as usize;
+        if let Some(selected) = state.selected {
+            if selected < state.offset {
+                state.offset = selected;
+            } else if selected >= state.offset + height {
+                state.offset = selected + 1 - height;
+            }
+        }
+
+        Clear.render(area, buf);
+        buf.set_style(area, self.style);
+        let description_x = area.left() as usize + state.value_width() + 2;
+        for (row, (index, candidate)) in state
+            .candidates
+            .iter()
+            .enumerate()
+            .skip(state.offset)
+            .take(height)
+            .enumerate()
+        {
+            let y = area.top() + row as u16;
+            buf.set_stringn(
+                area.left(),
+                y,
+                &candidate.value,
+                area.width as usize,
+                self.style,
+            );
+            for &i in state.matches.get(index).into_iter().flatten() {
+                let x = area.left() as usize + candidate.value[..i].width();
+                let width = candidate.value[i..]
+                    .chars()
+                    .next()
+                    .map_or(0, |c| c.width().unwrap_or(0));
+                if x + width <= area.right() as usize && width > 0 {
+                    buf.set_style(Rect::new(x as u16, y, width as u16, 1), self.match_style);
+                }
+            }
+            if let Some(description) = &candidate.description {
+                if description_x < area.right() as usize {
+                    buf.set_stringn(
+                        description_x as u16,
+                        y,
+                        description,
+                        area.right() as usize - description_x,
+                        self.style.patch(self.description_style),
+                    );
+                }
+            }
+            if state.selected == Some(index) {
+                buf.set_style(
+                    Rect::new(area.left(), y, area.width, 1),
+                    self.selected_style,
+                );
+            }
+        }
+    }
+}
+
+impl TuiClap {
+    /// Access the completion menu widget
+    pub fn completion_menu(&mut self) -> &mut CompletionMenu {
+        &mut self.completion_menu_widget
+    }
+
+    pub fn is_completion_menu_open(&self) -> bool {
+        self.completion_menu_state.is_some()
+    }
+
+    /// Handles `key` while the completion menu is open. `Tab` and `Down` select the next candidate, `BackTab` and `Up`
+    /// the previous one, and the selected candidate is previewed in the input. `Enter` accepts the candidate and `Esc`
+    /// restores the typed word. Any other key keeps the previewed candidate and returns `false`, so that it is processed
+    /// as usual. Also returns `false` if the menu is not open.
+    ///
+    /// When cycling through the candidates, see `CompletionMode::Cycle`, only `Tab`, `BackTab` and `Esc` are handled.
+    pub(crate) fn handle_completion_menu_key(&mut self, key: KeyEvent) -> bool {
+        self.close_stale_completion_menu();
+        let cycle = self.completion_mode == CompletionMode::Cycle;
+        let menu = match &self.completion_menu_state {
+            Some(_)
+                if cycle && !matches!(key.code, KeyCode::Tab | KeyCode::BackTab | KeyCode::Esc) =>
+            {
+                self.completion_menu_state = None;
+                return false;
+            }
+            Some(menu) => menu,
+            None => return false,
+        };
+        let len = menu.candidates.len();
+        let selected = match (key.code, menu.selected) {
+            (KeyCode::Tab, None) | (KeyCode::Down, None) => 0,
+            (KeyCode::Tab, Some(selected)) | (KeyCode::Down, Some(selected)) => {
+                (selected + 1) % len
+            }
+            (KeyCode::BackTab, None) | (KeyCode::Up, None) => len - 1,
+            (KeyCode::BackTab, Some(selected)) | (KeyCode::Up, Some(selected)) => {
+                (selected + len - 1) % len
+            }
+            (KeyCode::Enter, Some(selected)) => {
+                if !menu.candidates[selected].value.ends_with('/') {
+                    self.command_input_state.finish_word();
+                }
+                self.completion_menu_state = None;
+                return true;
+            }
+            (KeyCode::Esc, _) => {
+                let (typed, cursor) = &menu.typed;
+                self.command_input_state
+                    .replace_word(menu.start, menu.end, typed);
+                self.command_input_state.cursor = menu.start + cursor;
+                self.completion_menu_state = None;
+                return true;
+            }
+            _ => {
+                self.completion_menu_state = None;
+                return false;
+            }
+        };
+        self.select_completion(selected);
+        true
+    }
+
+    /// Selects the candidate at `index` in the completion menu and previews it in the input
+    pub(crate) fn select_completion(&mut self, index: usize) {
+        if let Some(menu) = &mut self.completion_menu_state {
+            menu.selected = Some(index);
+            let value = &menu.candidates[index].value;
+            let value = quote(value, !value.ends_with('/'));
+            self.command_input_state
+                .replace_word(menu.start, menu.end, &value);
+            menu.end = menu.start + value.len();
+        }
+    }
+
+    /// Closes the completion menu if the cursor was moved away from the completed word, e.g. by a paste
+    fn close_stale_completion_menu(&mut self) {
+        let state = &self.command_input_state;
+        if let Some(menu) = &self.completion_menu_state {
+            let cursor = if menu.selected.is_some() {
+                menu.end
+            } else {
+                menu.start + menu.typed.1
+            };
+            if state.cursor != cursor || menu.end > state.content.len() {
+                self.completion_menu_state = None;
+            }
+        }
+    }
+
+    /// Renders the completion menu above the completed word, or below it if there is no room above.
+    /// While asynchronous completers are running, `completing…` is rendered in its place.
+    pub(crate) fn render_completion_menu<B: Backend>(&mut self, frame: &mut Frame<B>) {
+        self.close_stale_completion_menu();
+        if self.is_completing() {
+            self.render_completing_indicator(frame);
+            return;
+        }
+        if self.completion_mode == CompletionMode::Cycle {
+            return;
+        }
+        let (menu, (cursor_x, cursor_y)) = match (
+            &mut self.completion_menu_state,
+            self.command_input_state.cursor_position(),
+        ) {
+            (Some(menu), Some(position)) => (menu, position),
+            _ => return,
+        };
+        let size = frame.size();
+        let state = &self.command_input_state;
+        let word_width = state.content[menu.start..state.cursor].width() as u16;
+        let x = cursor_x.saturating_sub(word_width);
+        let width = menu.width().min(size.right().saturating_sub(x));
+        let height = (menu.candidates.len() as u16).min(self.completion_menu_widget.max_height);
+        let y = if cursor_y >= size.top() + height {
+            cursor_y - height
+        } else {
+            cursor_y + 1
+        };
+        let area = Rect::new(x, y, width, height).intersection(size);
+
+        frame.render_stateful_widget(self.completion_menu_widget.clone(), area, menu);
+    }
+
+    fn render_completing_indicator<B: Backend>(&mut self, frame: &mut Frame<B>) {
+        let (cursor_x, cursor_y) = match self.command_input_state.cursor_position() {
+            Some(position) => position,
+            None => return,
+        };
+        let size = frame.size();
+        let state = &self.command_input_state;
+        let start = word_start(&state.content, state.cursor);
+        let x = cursor_x.saturating_sub(state.content[start..state.cursor].width() as u16);
+        let y = if cursor_y > size.top() {
+            cursor_y - 1
+        } else {
+            cursor_y + 1
+        };
+        let text = "completing…";
+        let area = Rect::new(x, y, text.width() as u16, 1).intersection(size);
+        let style = self.completion_menu_widget.style;
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(text).style(style), area);
+    }
+}
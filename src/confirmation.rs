@@ -0,0 +1,57 @@
+//! Confirmation of destructive subcommands before their handler is called
+
+use crate::handlers::deepest_handled;
+use crate::{CommandResult, TuiClap};
+use clap::ArgMatches;
+
+/// Words the question of a destructive subcommand is confirmed with, ignoring case
+const CONFIRMATIONS: [&str; 2] = ["y", "yes"];
+
+/// Builds the question confirming a destructive subcommand from its matches, see `TuiClap::set_confirmation`
+pub(crate) type Confirmation = Box<dyn Fn(&ArgMatches) -> Option<String>>;
+
+impl TuiClap {
+    /// Marks the subcommand `name`, named by its path like in `TuiClap::on`, as destructive, so that its handler is
+    /// called only once the user confirmed the question `question` builds from its matches, e.g.
+    /// `really delete 14 records? [y/N] `. Anything but `y` or `yes` cancels the command with the exit status 1.
+    /// `question` returns `None` to run the command without asking, e.g. if `--force` is given.
+    pub fn set_confirmation(
+        &mut self,
+        name: &str,
+        question: impl Fn(&ArgMatches) -> Option<String> + 'static,
+    ) {
+        let path = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.confirmations.insert(path, Box::new(question));
+    }
+
+    /// Removes the confirmation of the subcommand `name`, see `TuiClap::set_confirmation`
+    pub fn remove_confirmation(&mut self, name: &str) {
+        let path = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.confirmations.remove(&path);
+    }
+
+    /// Asks to confirm `matches` if the most deeply nested subcommand with a confirmation is destructive,
+    /// dispatching them once confirmed. Returns `false` if there is nothing to confirm.
+    pub(crate) fn confirm(&mut self, matches: &ArgMatches) -> bool {
+        let question = deepest_handled(matches, |key| self.confirmations.contains_key(key))
+            .and_then(|(key, sub_matches)| self.confirmations[&key](sub_matches));
+        let question = match question {
+            Some(question) => question,
+            None => return false,
+        };
+        let matches = matches.clone();
+        self.ask_with(
+            &question,
+            Box::new(move |answer, tui| {
+                let answer = answer.trim().to_lowercase();
+                if CONFIRMATIONS.contains(&answer.as_str()) {
+                    tui.run_handler(&matches);
+                    None
+                } else {
+                    Some(CommandResult::failure(1, "cancelled"))
+                }
+            }),
+        );
+        true
+    }
+}
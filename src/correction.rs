@@ -0,0 +1,80 @@
+//! Corrections of mistyped subcommands, flags and values suggested by clap, which can be put into the input widget
+
+use crate::tokenizer::words;
+use crate::TuiClap;
+use clap::error::{ContextKind, ContextValue, Error, ErrorKind};
+
+/// The mistyped word of the error `err` and the one clap suggests instead, if any
+fn suggestion(err: &Error) -> Option<(&str, &str)> {
+    let context = |kind| {
+        err.context().find_map(|(found, value)| match value {
+            ContextValue::String(value) if found == kind => Some(value.as_str()),
+            // the closest of several suggestions comes last
+            ContextValue::Strings(values) if found == kind => values.last().map(String::as_str),
+            _ => None,
+        })
+    };
+    match err.kind() {
+        ErrorKind::InvalidSubcommand => Some((
+            context(ContextKind::InvalidSubcommand)?,
+            context(ContextKind::SuggestedSubcommand)?,
+        )),
+        // a flag belonging behind a suggested subcommand is not suggested as argument, since it is not mistyped
+        ErrorKind::UnknownArgument => Some((
+            context(ContextKind::InvalidArg)?,
+            context(ContextKind::SuggestedArg)?,
+        )),
+        ErrorKind::InvalidValue => Some((
+            context(ContextKind::InvalidValue)?,
+            context(ContextKind::SuggestedValue)?,
+        )),
+        _ => None,
+    }
+}
+
+/// `line` with the first word `mistyped` replaced by `suggested`, also as unquoted part of `--flag=value`
+fn correct(line: &str, mistyped: &str, suggested: &str) -> Option<String> {
+    let (words, _) = words(line);
+    let (start, end) = words.iter().find_map(|word| {
+        let unquoted = line[word.start..word.end] == word.text;
+        if word.text == mistyped {
+            Some((word.start, word.end))
+        } else if !unquoted {
+            None
+        } else if word.text.starts_with(&format!("{}=", mistyped)) {
+            Some((word.start, word.start + mistyped.len()))
+        } else if word.text.ends_with(&format!("={}", mistyped)) {
+            Some((word.end - mistyped.len(), word.end))
+        } else {
+            None
+        }
+    })?;
+    Some(format!("{}{}{}", &line[..start], suggested, &line[end..]))
+}
+
+impl TuiClap {
+    /// The last entered command with the mistyped subcommand, flag or value replaced by the one clap suggested,
+    /// e.g. `status` for `stats`. `None` if the last command had no such error.
+    pub fn correction(&self) -> Option<&str> {
+        self.correction.as_deref()
+    }
+
+    /// Puts the suggested correction of the last entered command into the input widget, see `TuiClap::correction`.
+    /// Bound to `Alt+C` by default. Returns `false` if there is no correction.
+    pub fn accept_correction(&mut self) -> bool {
+        match self.correction.take() {
+            Some(correction) => {
+                self.command_input_state.reset();
+                self.command_input_state.insert_str(&correction);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remembers the correction of the command `line` suggested by the clap error `err`
+    pub(crate) fn suggest_correction(&mut self, line: &str, err: &Error) {
+        self.correction =
+            suggestion(err).and_then(|(mistyped, suggested)| correct(line, mistyped, suggested));
+    }
+}
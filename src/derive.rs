@@ -0,0 +1,24 @@
+//! Support for apps defined with the derive API of clap
+
+use crate::status::USAGE_ERROR;
+use crate::TuiClap;
+use clap::{FromArgMatches, Parser};
+
+impl TuiClap {
+    /// Creates a `TuiClap` struct from a type deriving `clap::Parser`, whose values `TuiClap::parse_into` returns
+    pub fn from_parser<T: Parser>() -> TuiClap {
+        TuiClap::from_app(T::command())
+    }
+
+    /// Parses the current content of the input widget like `TuiClap::parse`, converting the matches into `T`,
+    /// e.g. the type the `TuiClap` was created from with `TuiClap::from_parser`.
+    /// If the matches cannot be converted, the error is written to the output widget and a `Result::Err` is returned.
+    #[allow(clippy::result_unit_err)]
+    pub fn parse_into<T: FromArgMatches>(&mut self) -> Result<T, ()> {
+        let matches = self.parse()?;
+        T::from_arg_matches(&matches).map_err(|err| {
+            self.write_clap_error(&err.to_string());
+            self.command_input_state.report_status(USAGE_ERROR);
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//! One-line diagnostics of the command being typed, like `missing required <url>`, shown before it is entered
+
+use crate::preview::DryRun;
+use crate::tokenizer::{split_commands, split_pipeline};
+use crate::TuiClap;
+use clap::error::{ContextKind, ContextValue, Error, ErrorKind};
+use std::time::{Duration, Instant};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::text::Span;
+use tui::widgets::Paragraph;
+use tui::Frame;
+
+/// The diagnostic of the content of the input widget, computed once it was not changed for a while
+pub(crate) struct Diagnostics {
+    /// Time the content has to stay unchanged before it is parsed
+    delay: Duration,
+    style: Style,
+    /// The content the diagnostic is for
+    content: String,
+    changed_at: Instant,
+    /// Whether the content was parsed since it changed
+    checked: bool,
+    message: Option<String>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics {
+            delay: Duration::from_millis(500),
+            style: Style::default().fg(Color::Yellow),
+            content: String::new(),
+            changed_at: Instant::now(),
+            checked: true,
+            message: None,
+        }
+    }
+}
+
+/// The value of the context `kind` of `err`, several values joined by `, `
+fn context(err: &Error, kind: ContextKind) -> Option<String> {
+    err.context().find_map(|(found, value)| match value {
+        ContextValue::String(value) if found == kind => Some(value.clone()),
+        ContextValue::Strings(values) if found == kind => Some(values.join(", ")),
+        _ => None,
+    })
+}
+
+/// A short description of the clap error `err`, `None` if it is no error but e.g. the help
+pub(crate) fn describe(err: &Error) -> Option<String> {
+    let suggestion = |kind| {
+        // the closest of several suggestions comes last
+        err.context().find_map(|(found, value)| match value {
+            ContextValue::String(value) if found == kind => {
+                Some(format!(", did you mean {}?", value))
+            }
+            ContextValue::Strings(values) if found == kind => values
+                .last()
+                .map(|value| format!(", did you mean {}?", value)),
+            _ => None,
+        })
+    };
+    let message = match err.kind() {
+        ErrorKind::DisplayHelp
+        | ErrorKind::DisplayVersion
+        | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => return None,
+        ErrorKind::MissingRequiredArgument => {
+            format!(
+                "missing required {}",
+                context(err, ContextKind::InvalidArg)?
+            )
+        }
+        ErrorKind::UnknownArgument => format!(
+            "unknown flag {}{}",
+            context(err, ContextKind::InvalidArg)?,
+            suggestion(ContextKind::SuggestedArg).unwrap_or_default()
+        ),
+        ErrorKind::InvalidSubcommand => format!(
+            "unknown subcommand {}{}",
+            context(err, ContextKind::InvalidSubcommand)?,
+            suggestion(ContextKind::SuggestedSubcommand).unwrap_or_default()
+        ),
+        ErrorKind::InvalidValue => format!(
+            "invalid value '{}' for {}{}",
+            context(err, ContextKind::InvalidValue)?,
+            context(err, ContextKind::InvalidArg)?,
+            suggestion(ContextKind::SuggestedValue).unwrap_or_default()
+        ),
+        _ => {
+            let text = err.to_string();
+            let line = text.lines().next()?;
+            line.strip_prefix("error: ").unwrap_or(line).to_string()
+        }
+    };
+    Some(message)
+}
+
+impl TuiClap {
+    /// Sets how long the input has to stay unchanged before it is parsed for `TuiClap::diagnostic`, 500ms by default
+    pub fn set_diagnostics_delay(&mut self, delay: Duration) {
+        self.diagnostics.delay = delay;
+    }
+
+    /// Sets the style of the diagnostic rendered by `TuiClap::render_diagnostics`, yellow by default
+    pub fn set_diagnostics_style(&mut self, style: Style) {
+        self.diagnostics.style = style;
+    }
+
+    /// Why the command being typed would fail to parse, e.g. `missing required <url>` or
+    /// `unknown flag --forse, did you mean --force?`, once the input did not change for the delay set with
+    /// `TuiClap::set_diagnostics_delay`. Of chained commands, the last one is parsed. `None` while typing,
+    /// if the command would parse or is a built-in command.
+    pub fn diagnostic(&mut self) -> Option<&str> {
+        let diagnostics = &mut self.diagnostics;
+        if diagnostics.content != self.command_input_state.content {
+            diagnostics.content = self.command_input_state.content.clone();
+            diagnostics.changed_at = Instant::now();
+            diagnostics.checked = false;
+            diagnostics.message = None;
+        }
+        if !diagnostics.checked && diagnostics.changed_at.elapsed() >= diagnostics.delay {
+            let message = self.diagnose(&self.diagnostics.content);
+            self.diagnostics.message = message;
+            self.diagnostics.checked = true;
+        }
+        self.diagnostics.message.as_deref()
+    }
+
+    /// Renders the diagnostic of the command being typed on `area`, e.g. below the input, see `TuiClap::diagnostic`
+    pub fn render_diagnostics<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let style = self.diagnostics.style;
+        let message = self.diagnostic().unwrap_or_default().to_string();
+        frame.render_widget(Paragraph::new(Span::styled(message, style)), area);
+    }
+
+    /// Parses the last command of `line`, see `TuiClap::dry_run`
+    fn diagnose(&self, line: &str) -> Option<String> {
+        if self.is_asking() || self.is_typing_output_search() {
+            return None;
+        }
+        let (command, _, _) = split_commands(line).pop()?;
+        let command = split_pipeline(command).into_iter().next()?;
+        match self.dry_run(command) {
+            DryRun::Failed(err) => describe(&err),
+            DryRun::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Which of the input and the output widget the keys go to, switched by clicking them
+
+use crate::{Action, ReplAction, TuiClap};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
+
+/// The widget the keys go to, see `TuiClap::set_focus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Focus {
+    /// Keys edit the input, which is the default
+    #[default]
+    Input,
+    /// Keys scroll the output and the cursor of the input is hidden
+    Output,
+}
+
+#[derive(Default)]
+pub(crate) struct FocusState {
+    focus: Focus,
+    /// Area of the last render of the input widget, to locate mouse events
+    pub(crate) input_area: Rect,
+}
+
+/// Whether the position of `event` is inside `area`
+pub(crate) fn contains(area: Rect, event: &MouseEvent) -> bool {
+    (area.left()..area.right()).contains(&event.column)
+        && (area.top()..area.bottom()).contains(&event.row)
+}
+
+impl TuiClap {
+    /// The widget the keys go to
+    pub fn focus(&self) -> Focus {
+        self.focus.focus
+    }
+
+    /// Sets the widget the keys go to. With mouse capture enabled, see `Config::mouse_capture`,
+    /// clicking the input or the output focuses it.
+    pub fn set_focus(&mut self, focus: Focus) {
+        self.focus.focus = focus;
+    }
+
+    /// Focuses the widget clicked by `event`. Returns `false` if it is not a click on the input or the output.
+    pub(crate) fn handle_focus_mouse(&mut self, event: &MouseEvent) -> bool {
+        if event.kind != MouseEventKind::Down(MouseButton::Left) {
+            return false;
+        }
+        let focus = if contains(self.focus.input_area, event) {
+            Focus::Input
+        } else if contains(self.command_output_state.area, event)
+            || contains(self.command_error_state.area, event)
+        {
+            Focus::Output
+        } else {
+            return false;
+        };
+        self.focus.focus = focus;
+        true
+    }
+
+    /// Handles `key` while the output is focused. Without modifiers, `Up` and `Down`, or `k` and `j`, scroll the
+    /// output by a row, and `End` scrolls down to the newest output. `Ctrl+U` and `Ctrl+D` scroll by half a page.
+    /// `Left` and `Right` scroll it horizontally in `WrapMode::None`, like `Alt+Left` and `Alt+Right` do while the
+    /// input is focused. `Esc` and `Tab` focus the input. Keys editing the input and `Enter` focus it as well and are
+    /// handled as usual, so that typing goes on in the input, like other keys such as `PageUp`.
+    /// Returns `false` if the output is not focused or the key is to be handled as usual.
+    pub(crate) fn handle_focus_key(&mut self, key: KeyEvent) -> bool {
+        if self.focus.focus != Focus::Output {
+            return false;
+        }
+        let state = &mut self.command_output_state;
+        // keys with modifiers other than these are bound in the keymap, e.g. `Ctrl+K` to kill the rest of the input
+        let ctrl = key.modifiers == KeyModifiers::CONTROL;
+        let plain = key.modifiers.is_empty();
+        match key.code {
+            KeyCode::Char('u') if ctrl => state.half_page_up(),
+            KeyCode::Char('d') if ctrl => state.half_page_down(),
+            KeyCode::Left if plain => state.scroll_left(state.horizontal_step()),
+            KeyCode::Right if plain => state.scroll_right(state.horizontal_step()),
+            KeyCode::Up | KeyCode::Char('k') if plain => state.scroll_up(1),
+            KeyCode::Down | KeyCode::Char('j') if plain => state.scroll_down(1),
+            KeyCode::End if plain => state.scroll_to_bottom(),
+            KeyCode::Esc | KeyCode::Tab if plain => self.focus.focus = Focus::Input,
+            _ => {
+                if let Some(Action::Edit(_)) | Some(Action::Repl(ReplAction::Submit)) | None =
+                    self.keymap.action(&key)
+                {
+                    self.focus.focus = Focus::Input;
+                }
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Focus, TuiClap, WrapMode};
+    use clap::Command;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tui::backend::TestBackend;
+    use tui::Terminal;
+
+    /// A `TuiClap` with the output focused, rendered with a page of 10 rows
+    fn focused_output() -> TuiClap {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        for i in 0..100 {
+            tui.write_to_output(format!("line {}", i));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(40, 11)).unwrap();
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+        tui.set_focus(Focus::Output);
+        tui
+    }
+
+    fn press(tui: &mut TuiClap, code: KeyCode, modifiers: KeyModifiers) {
+        tui.handle_key_event(KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn ctrl_u_and_ctrl_d_scroll_half_pages() {
+        let mut tui = focused_output();
+        press(&mut tui, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(tui.output_state().scroll_offset(), 5);
+        press(&mut tui, KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(tui.output_state().scroll_offset(), 0);
+        assert_eq!(tui.focus(), Focus::Output);
+    }
+
+    #[test]
+    fn ctrl_d_in_input_is_no_scrolling() {
+        let mut tui = focused_output();
+        tui.set_focus(Focus::Input);
+        tui.state().insert_str("ab");
+        tui.state().move_cursor_to_start();
+        press(&mut tui, KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(tui.state().content(), "b");
+        assert_eq!(tui.output_state().scroll_offset(), 0);
+    }
+
+    #[test]
+    fn left_and_right_scroll_horizontally() {
+        let mut tui = focused_output();
+        tui.output_widget().wrap_mode(WrapMode::None);
+        tui.write_to_output("x".repeat(200));
+        let mut terminal = Terminal::new(TestBackend::new(41, 11)).unwrap();
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+        press(&mut tui, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(tui.output_state().horizontal_scroll_offset(), 10);
+        press(&mut tui, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(tui.output_state().horizontal_scroll_offset(), 0);
+        assert_eq!(tui.state().cursor(), 0);
+    }
+
+    #[test]
+    fn plain_keys_scroll() {
+        let mut tui = focused_output();
+        press(&mut tui, KeyCode::Char('k'), KeyModifiers::NONE);
+        press(&mut tui, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(tui.output_state().scroll_offset(), 2);
+        press(&mut tui, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(tui.output_state().scroll_offset(), 1);
+        assert_eq!(tui.state().content(), "");
+        assert_eq!(tui.focus(), Focus::Output);
+    }
+
+    #[test]
+    fn modified_keys_go_to_the_keymap() {
+        let mut tui = focused_output();
+        tui.state().insert_str("abc");
+        tui.state().move_cursor_to_start();
+        // kills the input instead of scrolling up
+        press(&mut tui, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(tui.output_state().scroll_offset(), 0);
+        assert_eq!(tui.state().content(), "");
+        assert_eq!(tui.focus(), Focus::Input);
+
+        // an upper case char is typed into the input
+        tui.set_focus(Focus::Output);
+        press(&mut tui, KeyCode::Char('J'), KeyModifiers::SHIFT);
+        assert_eq!(tui.output_state().scroll_offset(), 0);
+        assert_eq!(tui.state().content(), "J");
+        assert_eq!(tui.focus(), Focus::Input);
+    }
+}
@@ -0,0 +1,90 @@
+//! Fuzzy matching of completion candidates and history entries
+
+/// Matches the chars of `pattern` as a subsequence of `text`, ignoring case.
+/// Returns a score, higher for better matches, and the byte offsets of the matched chars in `text`,
+/// or `None` if `pattern` is no subsequence of `text`.
+///
+/// Consecutive matches and matches at the start of words, like the `s` of `git-status`, score higher.
+pub(crate) fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let mut pattern = pattern.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut matches = vec![];
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+
+    for (i, c) in text.char_indices() {
+        let expected = match pattern.peek() {
+            Some(&expected) => expected,
+            None => break,
+        };
+        let matched = c.to_lowercase().next() == Some(expected);
+        if matched {
+            pattern.next();
+            matches.push(i);
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += 8;
+            }
+        } else if !matches.is_empty() {
+            // gaps between matches
+            score -= 1;
+        }
+        previous = Some(c);
+        previous_matched = matched;
+    }
+
+    if pattern.peek().is_some() {
+        return None;
+    }
+    Some((score, matches))
+}
+
+/// The byte offsets of the chars of `prefix` in candidates starting with it
+pub(crate) fn prefix_matches(prefix: &str) -> Vec<usize> {
+    prefix.char_indices().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_match, prefix_matches};
+
+    #[test]
+    fn matches_subsequence() {
+        let (_, matches) = fuzzy_match("gst", "git-status").unwrap();
+        assert_eq!(matches, [0, 4, 5]);
+        assert_eq!(fuzzy_match("sg", "git-status"), None);
+        assert_eq!(fuzzy_match("gits", "git"), None);
+    }
+
+    #[test]
+    fn ignores_case() {
+        let (_, matches) = fuzzy_match("GS", "git-Status").unwrap();
+        assert_eq!(matches, [0, 4]);
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(fuzzy_match("", "status"), Some((0, vec![])));
+        assert_eq!(fuzzy_match("", ""), Some((0, vec![])));
+    }
+
+    #[test]
+    fn prefers_consecutive_matches_at_word_starts() {
+        let score = |text| fuzzy_match("st", text).unwrap().0;
+        assert!(score("status") > score("fast"));
+        assert!(score("git-status") > score("sort"));
+    }
+
+    #[test]
+    fn returns_byte_offsets() {
+        let (_, matches) = fuzzy_match("é", "café").unwrap();
+        assert_eq!(matches, [3]);
+        let (_, matches) = fuzzy_match("çb", "ça-b").unwrap();
+        assert_eq!(matches, [0, 4]);
+        assert_eq!(prefix_matches("ab"), [0, 1]);
+        assert_eq!(prefix_matches("éa"), [0, 2]);
+    }
+}
@@ -0,0 +1,192 @@
+//! Classification of the tokens of a command line against the grammar of a `clap::Command`
+
+use crate::tokenizer::words;
+use clap::{Arg, ArgAction, Command};
+
+/// What a token of a command line is according to the grammar of the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// The binary name or a subcommand
+    Command,
+    /// A known flag or option, or `--`
+    Flag,
+    /// The value of an option or a positional argument
+    Value,
+    /// A flag, subcommand or value the app does not know
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token {
+    /// Byte offset of the token in the command line
+    pub(crate) start: usize,
+    /// Byte offset behind the token in the command line
+    pub(crate) end: usize,
+    pub(crate) kind: TokenKind,
+}
+
+/// Splits `line` into words like a shell, see `tokenize`, and classifies them, starting with the binary name
+/// if `binary_name` is set. The app has to be built, see `Command::build`, so that generated arguments like `--help`
+/// are known.
+pub(crate) fn classify(app: &Command, line: &str, binary_name: bool) -> Vec<Token> {
+    let mut walker = Walker::new(app, binary_name);
+    words(line)
+        .0
+        .into_iter()
+        .map(|word| Token {
+            start: word.start,
+            end: word.end,
+            kind: walker.next(&word.text),
+        })
+        .collect()
+}
+
+/// State of walking the tokens of a command line through the grammar of an app, starting with the binary name if given
+pub(crate) struct Walker<'a> {
+    /// The app or subcommand the following tokens belong to
+    pub(crate) command: &'a Command,
+    /// The option expecting the following token as its value
+    pub(crate) pending_option: Option<&'a Arg>,
+    /// Whether `--` was given, so that the following tokens are positional arguments
+    pub(crate) only_positionals: bool,
+    /// Number of positional arguments given to `command`
+    pub(crate) positionals: usize,
+    /// Flags and options given to `command`
+    pub(crate) given: Vec<&'a Arg>,
+    /// Whether the binary name was given
+    started: bool,
+}
+
+impl<'a> Walker<'a> {
+    /// Creates a walker expecting the binary name as the first token if `binary_name` is set
+    pub(crate) fn new(app: &'a Command, binary_name: bool) -> Self {
+        Walker {
+            command: app,
+            pending_option: None,
+            only_positionals: false,
+            positionals: 0,
+            given: vec![],
+            started: !binary_name,
+        }
+    }
+
+    /// Whether the binary name was given
+    pub(crate) fn started(&self) -> bool {
+        self.started
+    }
+
+    /// The positional argument the next token would be given to, if any
+    pub(crate) fn next_positional(&self) -> Option<&'a Arg> {
+        self.remaining_positionals().first().copied()
+    }
+
+    /// The positional arguments of `command` not given yet, in order. The last one is kept if it takes multiple values.
+    pub(crate) fn remaining_positionals(&self) -> Vec<&'a Arg> {
+        let mut positionals: Vec<&Arg> = self.command.get_positionals().collect();
+        positionals.sort_by_key(|arg| arg.get_index());
+        let multiple = positionals
+            .last()
+            .is_some_and(|arg| takes_multiple_values(arg));
+        let given = if multiple {
+            self.positionals.min(positionals.len() - 1)
+        } else {
+            self.positionals.min(positionals.len())
+        };
+        positionals.split_off(given)
+    }
+
+    /// Classifies the next token `text` and advances the state
+    pub(crate) fn next(&mut self, text: &str) -> TokenKind {
+        let command = self.command;
+        if !self.started {
+            self.started = true;
+            TokenKind::Command
+        } else if self.pending_option.take().is_some() {
+            TokenKind::Value
+        } else if !self.only_positionals && text == "--" {
+            self.only_positionals = true;
+            TokenKind::Flag
+        } else if !self.only_positionals && text.starts_with("--") {
+            let (name, value) = match text[2..].find('=') {
+                Some(i) => (&text[2..2 + i], Some(&text[3 + i..])),
+                None => (&text[2..], None),
+            };
+            match find_long(command, name) {
+                Some(arg) => {
+                    self.given.push(arg);
+                    if arg.get_action().takes_values() && value.is_none() {
+                        self.pending_option = Some(arg);
+                    }
+                    TokenKind::Flag
+                }
+                None => TokenKind::Unknown,
+            }
+        } else if !self.only_positionals && text.starts_with('-') && text.len() > 1 {
+            let mut kind = TokenKind::Flag;
+            for (i, c) in text[1..].char_indices() {
+                let arg = find_short(command, c);
+                self.given.extend(arg);
+                match arg {
+                    Some(arg) if arg.get_action().takes_values() => {
+                        // the rest of the token is the value of the option, if any
+                        if 1 + i + c.len_utf8() == text.len() {
+                            self.pending_option = Some(arg);
+                        }
+                        break;
+                    }
+                    Some(_) => {}
+                    None => {
+                        kind = TokenKind::Unknown;
+                        break;
+                    }
+                }
+            }
+            kind
+        } else if let Some(subcommand) = command.find_subcommand(text) {
+            self.command = subcommand;
+            self.positionals = 0;
+            self.given.clear();
+            TokenKind::Command
+        } else if accepts_positional(command, self.positionals) {
+            self.positionals += 1;
+            TokenKind::Value
+        } else {
+            TokenKind::Unknown
+        }
+    }
+}
+
+pub(crate) fn find_long<'a>(command: &'a Command, name: &str) -> Option<&'a Arg> {
+    command.get_arguments().find(|arg| {
+        arg.get_long() == Some(name)
+            || arg
+                .get_all_aliases()
+                .is_some_and(|aliases| aliases.contains(&name))
+    })
+}
+
+fn find_short(command: &Command, c: char) -> Option<&Arg> {
+    command.get_arguments().find(|arg| {
+        arg.get_short() == Some(c)
+            || arg
+                .get_all_short_aliases()
+                .is_some_and(|aliases| aliases.contains(&c))
+    })
+}
+
+/// Whether `command` accepts another positional argument after `given` ones
+fn accepts_positional(command: &Command, given: usize) -> bool {
+    let positionals: Vec<&Arg> = command.get_positionals().collect();
+    given < positionals.len()
+        || positionals
+            .last()
+            .is_some_and(|arg| takes_multiple_values(arg))
+}
+
+/// Whether the positional argument `arg` takes several values
+pub(crate) fn takes_multiple_values(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::Append)
+        || arg
+            .get_num_args()
+            .is_some_and(|range| range.max_values() > 1)
+}
@@ -0,0 +1,193 @@
+//! Handlers of subcommands, which `TuiClap::dispatch` calls with the matches of their subcommand
+
+use crate::tokenizer::{split_commands, Chain};
+use crate::{CommandResult, TuiClap};
+use clap::{ArgMatches, Command};
+use std::time::Instant;
+
+/// A handler of a subcommand, see `TuiClap::on`. Returns `None` if it runs asynchronously and delivers its result later.
+pub(crate) type Handler = Box<dyn FnMut(&ArgMatches, &mut TuiClap) -> Option<CommandResult>>;
+
+/// The path of the most deeply nested subcommand of `matches` which is `handled`, with its matches.
+/// The empty path stands for the app itself.
+pub(crate) fn deepest_handled(
+    matches: &ArgMatches,
+    handled: impl Fn(&str) -> bool,
+) -> Option<(String, &ArgMatches)> {
+    let mut path = vec![];
+    let mut found = handled("").then(|| (String::new(), matches));
+    let mut current = matches;
+    while let Some((name, sub_matches)) = current.subcommand() {
+        path.push(name);
+        let key = path.join(" ");
+        if handled(&key) {
+            found = Some((key, sub_matches));
+        }
+        current = sub_matches;
+    }
+    found
+}
+
+impl TuiClap {
+    /// Creates a `TuiClap` struct from a `clap::Command` and a function handling its matches, which `TuiClap::execute`
+    /// calls unless a handler is registered for the subcommand, see `TuiClap::on`.
+    /// This is a constructor of its own rather than a second argument of `TuiClap::from_app`, so that existing callers
+    /// of `from_app(app)`, which handle the matches of `TuiClap::parse` themselves, keep compiling.
+    pub fn with_handler(
+        app: Command,
+        mut handler: impl FnMut(ArgMatches) -> Result<Vec<String>, String> + 'static,
+    ) -> TuiClap {
+        let mut tui = TuiClap::from_app(app);
+        tui.on("", move |matches, _| handler(matches.clone()));
+        tui
+    }
+}
+
+impl TuiClap {
+    /// Registers `handler` for the subcommand `name`, replacing any previous handler of it.
+    /// Nested subcommands are named by their path, e.g. `remote add`, and the empty name stands for the app itself.
+    /// The handler is called by `TuiClap::dispatch` with the matches of the subcommand and the `TuiClap`,
+    /// e.g. to write further output. The returned lines are written to the output, an error to the error stream.
+    pub fn on(
+        &mut self,
+        name: &str,
+        mut handler: impl FnMut(&ArgMatches, &mut TuiClap) -> Result<Vec<String>, String> + 'static,
+    ) {
+        self.register_handler(
+            name,
+            Box::new(move |matches, tui| Some(handler(matches, tui).into())),
+        );
+    }
+
+    /// Registers `handler` for the subcommand `name` like `TuiClap::on`, but the handler returns a `CommandResult`
+    /// with the exit status of the command, which is recorded as `TuiClap::last_status`
+    pub fn on_result(
+        &mut self,
+        name: &str,
+        mut handler: impl FnMut(&ArgMatches, &mut TuiClap) -> CommandResult + 'static,
+    ) {
+        self.register_handler(
+            name,
+            Box::new(move |matches, tui| Some(handler(matches, tui))),
+        );
+    }
+
+    pub(crate) fn register_handler(&mut self, name: &str, handler: Handler) {
+        let path = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.jobs.remove_worker(&path);
+        self.handlers.insert(path, handler);
+    }
+
+    /// Calls the handler registered for the most deeply nested subcommand of `matches` which has one, see `TuiClap::on`,
+    /// writes its output and reports its success to the history. Destructive subcommands, see
+    /// `TuiClap::set_confirmation`, are run once the user confirmed them.
+    /// Returns `false` if no handler is registered for any of the subcommands.
+    pub fn dispatch(&mut self, matches: &ArgMatches) -> bool {
+        if !self.has_handler(matches) {
+            return false;
+        }
+        if !self.confirm(matches) {
+            self.run_handler(matches);
+        }
+        true
+    }
+
+    /// Whether a handler is registered for any of the subcommands of `matches`
+    pub(crate) fn has_handler(&self, matches: &ArgMatches) -> bool {
+        deepest_handled(matches, |key| self.handlers.contains_key(key)).is_some()
+    }
+
+    /// Calls the handler of `matches` like `TuiClap::dispatch`, without asking for a confirmation
+    pub(crate) fn run_handler(&mut self, matches: &ArgMatches) -> bool {
+        let found = deepest_handled(matches, |key| self.handlers.contains_key(key));
+        let (key, sub_matches) = match found {
+            Some(found) => found,
+            None => return false,
+        };
+
+        // the handler is taken out while it runs, since it can access the `TuiClap`
+        let mut handler = match self.handlers.remove(&key) {
+            Some(handler) => handler,
+            None => return false,
+        };
+        let mode = self.mode.clone();
+        let started = Instant::now();
+        let result = handler(sub_matches, self);
+        let duration = started.elapsed();
+        self.restore_handler(&mode, key, handler);
+        if let Some(result) = result {
+            self.record_duration(duration, self.echo.clone());
+            self.command_input_state.report_status(result.status);
+            self.run_after_execute_hooks(&result);
+            self.write_result(result);
+        }
+        true
+    }
+
+    /// Writes the lines returned by a handler to the output, styled as failed unless it succeeded,
+    /// and its error to the error stream
+    pub(crate) fn write_result(&mut self, result: impl Into<CommandResult>) {
+        let result = result.into();
+        for line in result.output {
+            if result.status == 0 {
+                self.write_to_output(line);
+            } else {
+                self.write_styled(line, self.failure_style);
+            }
+        }
+        if let Some(err) = result.error {
+            self.write_to_error(err);
+        }
+    }
+
+    /// Runs `command` and dispatches its matches, returning them if no handler is registered for them
+    pub(crate) fn run_and_dispatch(&mut self, command: &str) -> Option<ArgMatches> {
+        match self.run_command(command) {
+            Ok(matches) if !self.dispatch(&matches) => Some(matches),
+            _ => None,
+        }
+    }
+
+    /// Parses the current content of the input widget like `TuiClap::parse` and dispatches the matches to their handler
+    /// like `TuiClap::dispatch`. Returns the matches if no handler is registered for them.
+    /// Several commands can be chained with `;`, which runs the next command in any case, and `&&`, which runs it only
+    /// if the previous one succeeded. They are run in order, each echoed in front of its output if enabled, see
+    /// `TuiClap::set_echo_commands`. Commands followed by `&` run in the background, see `TuiClap::on_worker`.
+    /// Only the matches of the last command are returned if it has no handler, the other
+    /// commands need one. Commands whose handler runs asynchronously count as succeeded.
+    /// The output of a command can be piped through filters, see `TuiClap::add_filter`.
+    pub fn execute(&mut self) -> Option<ArgMatches> {
+        let content = self.enter_input()?;
+        self.run_line(&content, self.echo_commands).0
+    }
+
+    /// Runs the commands chained in `line` like `TuiClap::execute`, echoing each of them if `echo` is set.
+    /// Returns the matches of the last command if it has no handler, and whether the last command run succeeded.
+    pub(crate) fn run_line(&mut self, line: &str, echo: bool) -> (Option<ArgMatches>, bool) {
+        let commands = split_commands(line);
+        let chained = commands.len() > 1;
+        let mut succeeded = true;
+        let mut unhandled = None;
+        for (command, chain, background) in commands {
+            if (chained && command.trim().is_empty()) || (chain == Chain::OnSuccess && !succeeded) {
+                continue;
+            }
+            if echo && background {
+                self.echo_command(&format!("{} &", command.trim()));
+            } else if echo {
+                self.echo_command(if chained { command.trim() } else { line });
+            }
+            if background {
+                self.start_job(command);
+                unhandled = None;
+            } else {
+                unhandled = self.run_pipeline(command);
+            }
+            succeeded = self
+                .command_input_state
+                .last_status
+                .is_none_or(|status| status == 0);
+        }
+        (unhandled, succeeded)
+    }
+}
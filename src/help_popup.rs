@@ -0,0 +1,120 @@
+//! Popup showing help texts of the clap app above the other widgets, instead of writing them to the output
+
+use crate::scroll::WHEEL_ROWS;
+use crate::TuiClap;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::text::{Spans, Text};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
+use tui::Frame;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HelpPopup {
+    lines: Vec<Spans<'static>>,
+    /// Number of lines scrolled out of the top of the popup
+    scroll: usize,
+    /// Number of lines visible at once, as computed by the last render
+    page_size: usize,
+}
+
+impl HelpPopup {
+    fn scroll_to(&mut self, scroll: usize) {
+        let last = self.lines.len().saturating_sub(self.page_size.max(1));
+        self.scroll = scroll.min(last);
+    }
+}
+
+impl TuiClap {
+    /// Sets whether help texts requested with `--help` or the `help` subcommand are shown in a popup
+    /// rendered by `TuiClap::render_help_popup` instead of being written to the output widget, disabled by default
+    pub fn set_help_popup(&mut self, enabled: bool) {
+        self.help_popup_enabled = enabled;
+    }
+
+    /// Opens the help popup showing the help text of the clap app
+    pub fn open_help_popup(&mut self) {
+        let mut buf = Vec::new();
+        self.clap
+            .write_help(&mut buf)
+            .expect("Could not write help");
+        self.show_help_popup(&String::from_utf8_lossy(&buf));
+    }
+
+    pub fn close_help_popup(&mut self) {
+        self.help_popup = None;
+    }
+
+    pub fn is_help_popup_open(&self) -> bool {
+        self.help_popup.is_some()
+    }
+
+    /// Opens the help popup showing the help text `text`, styled like the help texts written to the output widget
+    pub(crate) fn show_help_popup(&mut self, text: &str) {
+        self.help_popup = Some(HelpPopup {
+            lines: self.help_lines(text),
+            ..HelpPopup::default()
+        });
+    }
+
+    /// Handles `key` while the help popup is open. The arrow keys, `j`, `k`, `PageUp`, `PageDown`, `Home` and `End`
+    /// scroll the help text, `Esc` and `q` close the popup. Returns `false` if the popup is not open.
+    pub(crate) fn handle_help_popup_key(&mut self, key: KeyEvent) -> bool {
+        let popup = match &mut self.help_popup {
+            Some(popup) => popup,
+            None => return false,
+        };
+        let page_size = popup.page_size.max(1);
+        let scroll = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => popup.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => popup.scroll + 1,
+            KeyCode::PageUp => popup.scroll.saturating_sub(page_size),
+            KeyCode::PageDown => popup.scroll + page_size,
+            KeyCode::Home => 0,
+            KeyCode::End => usize::MAX,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.help_popup = None;
+                return true;
+            }
+            _ => return true,
+        };
+        popup.scroll_to(scroll);
+        true
+    }
+
+    /// Handles `event` while the help popup is open, the mouse wheel scrolls the help text.
+    /// Returns `false` if the popup is not open.
+    pub(crate) fn handle_help_popup_mouse(&mut self, event: MouseEvent) -> bool {
+        let popup = match &mut self.help_popup {
+            Some(popup) => popup,
+            None => return false,
+        };
+        match event.kind {
+            MouseEventKind::ScrollUp => popup.scroll_to(popup.scroll.saturating_sub(WHEEL_ROWS)),
+            MouseEventKind::ScrollDown => popup.scroll_to(popup.scroll + WHEEL_ROWS),
+            _ => {}
+        }
+        true
+    }
+
+    /// Renders the help popup on `area` of `tui::Frame`, if it is open.
+    /// The area is cleared first, so the popup can be drawn above other widgets.
+    pub fn render_help_popup<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let popup = match &mut self.help_popup {
+            Some(popup) => popup,
+            None => return,
+        };
+        popup.page_size = area.height.saturating_sub(2) as usize;
+        popup.scroll_to(popup.scroll);
+        let paragraph = Paragraph::new(Text::from(popup.lines.clone()))
+            .block(
+                Block::default()
+                    .title("Help (Esc to close)")
+                    .borders(Borders::ALL),
+            )
+            .scroll((popup.scroll.min(u16::MAX as usize) as u16, 0));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,174 @@
+//! Styling the help texts and error messages of clap, which are rendered without colors when written to a buffer,
+//! with the emphasis clap uses on a terminal
+
+use crate::output::Level;
+use crate::{CommandOutput, TuiClap};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Styles of the help texts and error messages of clap written to the output widget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelpStyles {
+    /// Style of section headings like `Usage:` or `Options:`
+    pub heading: Style,
+    /// Style of the flags, arguments and subcommands listed in a section, and of suggestions in error messages
+    pub entry: Style,
+    /// Style of quoted values in error messages
+    pub value: Style,
+    /// Style of the `error:` in front of error messages
+    pub error: Style,
+}
+
+impl Default for HelpStyles {
+    fn default() -> Self {
+        HelpStyles {
+            heading: Style::default().fg(Color::Yellow),
+            entry: Style::default().fg(Color::Green),
+            value: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+impl CommandOutput {
+    /// Sets the styles of the help texts and error messages of clap
+    pub fn help_styles(&mut self, styles: HelpStyles) {
+        self.help_styles = styles;
+    }
+}
+
+/// Whether `line` is a heading like `Options:`, which follows an empty line unless it is the first one
+fn is_heading(line: &str, previous: Option<&str>) -> bool {
+    !line.starts_with(char::is_whitespace)
+        && !line.starts_with("error:")
+        && line.ends_with(':')
+        && previous.is_none_or(|previous| previous.trim().is_empty())
+}
+
+/// The `Usage:` heading followed by the usage in the same line split off `line`
+fn split_usage(line: &str) -> Option<(&str, &str)> {
+    line.strip_prefix("Usage:").map(|usage| ("Usage:", usage))
+}
+
+/// Splits the help text `text` into lines, styling the headings and the first column of the entries of a section
+pub(crate) fn style_help(text: &str, styles: &HelpStyles) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    let mut previous = None;
+    let mut section = None;
+    for line in text.lines() {
+        if let Some((heading, usage)) = split_usage(line) {
+            section = Some(heading);
+            lines.push(Spans::from(vec![
+                Span::styled(heading.to_string(), styles.heading),
+                Span::raw(usage.to_string()),
+            ]));
+            previous = Some(line);
+            continue;
+        }
+        if is_heading(line, previous) {
+            section = Some(line);
+            lines.push(Spans::from(Span::styled(line.to_string(), styles.heading)));
+            previous = Some(line);
+            continue;
+        }
+        previous = Some(line);
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        // options without a short flag are aligned with the long flags of the others
+        let is_entry = (indent == 2 || (indent > 0 && trimmed.starts_with("--")))
+            && section.is_some_and(|section| section != "Usage:");
+        if !is_entry {
+            lines.push(Spans::from(line.to_string()));
+            continue;
+        }
+        // the columns are separated by at least two spaces
+        let end = indent + trimmed.find("  ").unwrap_or(trimmed.len());
+        lines.push(Spans::from(vec![
+            Span::raw(line[..indent].to_string()),
+            Span::styled(line[indent..end].to_string(), styles.entry),
+            Span::raw(line[end..].to_string()),
+        ]));
+    }
+    lines
+}
+
+/// Splits the error message `text` into lines, styling the `error:`, quoted values and suggestions,
+/// the `Usage:` heading and the `--help` flag
+pub(crate) fn style_error(text: &str, styles: &HelpStyles) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    let mut previous = None;
+    for line in text.lines() {
+        if let Some((heading, usage)) = split_usage(line) {
+            lines.push(Spans::from(vec![
+                Span::styled(heading.to_string(), styles.heading),
+                Span::raw(usage.to_string()),
+            ]));
+            previous = Some(line);
+            continue;
+        }
+        if is_heading(line, previous) {
+            lines.push(Spans::from(Span::styled(line.to_string(), styles.heading)));
+            previous = Some(line);
+            continue;
+        }
+        previous = Some(line);
+        let mut spans = vec![];
+        let mut rest = line;
+        if let Some(message) = rest.strip_prefix("error:") {
+            spans.push(Span::styled("error:", styles.error));
+            rest = message;
+        }
+        let quoted = if rest.contains("tip:") || rest.contains("try '--help'") {
+            styles.entry
+        } else {
+            styles.value
+        };
+        while let Some((start, end)) = quoted_value(rest) {
+            spans.push(Span::raw(rest[..start].to_string()));
+            spans.push(Span::styled(rest[start..end].to_string(), quoted));
+            spans.push(Span::raw("'"));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            spans.push(Span::raw(rest.to_string()));
+        }
+        lines.push(Spans::from(spans));
+    }
+    lines
+}
+
+/// The byte range of the first value between single quotes in `text`, skipping apostrophes like in `wasn't`
+fn quoted_value(text: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while let Some(quote) = text[offset..].find('\'') {
+        let start = offset + quote + 1;
+        let opens = text[..start - 1]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        if opens {
+            if let Some(length) = text[start..].find('\'') {
+                return Some((start, start + length));
+            }
+            return None;
+        }
+        offset = start;
+    }
+    None
+}
+
+impl TuiClap {
+    /// Writes the error message `text` of clap to the error stream, see `CommandOutput::help_styles`
+    pub(crate) fn write_clap_error(&mut self, text: &str) {
+        let lines = style_error(text, &self.command_output_widget.help_styles)
+            .into_iter()
+            .map(|line| (line, vec![]))
+            .collect();
+        let state = if self.separate_errors {
+            &mut self.command_error_state
+        } else {
+            &mut self.command_output_state
+        };
+        state.push_block(lines, Level::Error);
+    }
+}
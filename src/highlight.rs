@@ -0,0 +1,54 @@
+//! Syntax highlighting of the input according to the grammar of the clap app
+
+use crate::grammar::{classify, TokenKind};
+use clap::Command;
+use tui::style::{Color, Modifier, Style};
+
+/// Styles of the tokens of the input when syntax highlighting is enabled, see `TuiClap::set_highlighting`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightStyles {
+    /// Style of the binary name and subcommands
+    pub command: Style,
+    /// Style of known flags and options
+    pub flag: Style,
+    /// Style of option values and positional arguments
+    pub value: Style,
+    /// Style of tokens the app does not know
+    pub unknown: Style,
+}
+
+impl Default for HighlightStyles {
+    fn default() -> Self {
+        HighlightStyles {
+            command: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            flag: Style::default().fg(Color::Yellow),
+            value: Style::default(),
+            unknown: Style::default().fg(Color::Red),
+        }
+    }
+}
+
+impl HighlightStyles {
+    /// Styled byte ranges of `line` according to the grammar of `app`, see `classify`
+    pub(crate) fn highlight(
+        &self,
+        app: &Command,
+        line: &str,
+        binary_name: bool,
+    ) -> Vec<(usize, usize, Style)> {
+        classify(app, line, binary_name)
+            .into_iter()
+            .map(|token| {
+                let style = match token.kind {
+                    TokenKind::Command => self.command,
+                    TokenKind::Flag => self.flag,
+                    TokenKind::Value => self.value,
+                    TokenKind::Unknown => self.unknown,
+                };
+                (token.start, token.end, style)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,440 @@
+//! Persistence and limits of the command history of `CommandInputState`
+
+use crate::CommandInputState;
+use fs2::FileExt;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A command in the history of `CommandInputState`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// When the command was entered
+    pub timestamp: SystemTime,
+    /// Whether the command succeeded, `None` if not reported
+    pub success: Option<bool>,
+}
+
+impl HistoryEntry {
+    /// Creates an entry for `command` entered now
+    pub fn new(command: impl Into<String>) -> HistoryEntry {
+        HistoryEntry {
+            command: command.into(),
+            timestamp: SystemTime::now(),
+            success: None,
+        }
+    }
+}
+
+/// How `CommandInputState` records a command that is already in the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryDuplicates {
+    /// Record every command
+    #[default]
+    Keep,
+    /// Skip a command if it equals the most recent entry
+    IgnoreConsecutive,
+    /// Remove older entries equal to the command, so that it only appears as the most recent entry
+    MoveToFront,
+}
+
+impl CommandInputState {
+    /// Sets the maximum number of history entries, dropping the oldest ones beyond it. `None`, the default, keeps all entries.
+    /// The limit applies to the history in memory, entries already appended to a history file stay there.
+    pub fn history_limit(&mut self, limit: Option<usize>) {
+        self.history_limit = limit;
+        self.trim_history();
+    }
+
+    /// Sets how commands that are already in the history are recorded
+    pub fn history_duplicates(&mut self, duplicates: HistoryDuplicates) {
+        self.history_duplicates = duplicates;
+        self.trim_history();
+    }
+
+    /// Removes duplicates and the entries beyond the limit from the history
+    pub(crate) fn trim_history(&mut self) {
+        let keep: Vec<bool> = match self.history_duplicates {
+            HistoryDuplicates::Keep => vec![true; self.history.len()],
+            HistoryDuplicates::IgnoreConsecutive => (0..self.history.len())
+                .map(|i| i == 0 || self.history[i].command != self.history[i - 1].command)
+                .collect(),
+            HistoryDuplicates::MoveToFront => {
+                let mut seen = HashSet::new();
+                let mut keep: Vec<bool> = self
+                    .history
+                    .iter()
+                    .rev()
+                    .map(|entry| seen.insert(entry.command.as_str()))
+                    .collect();
+                keep.reverse();
+                keep
+            }
+        };
+        let mut kept = keep.iter().filter(|&&keep| keep).count();
+
+        let mut index = 0;
+        let saved_history = self.saved_history;
+        let history_limit = self.history_limit;
+        let mut removed_saved = 0;
+        self.history.retain(|_| {
+            let mut retain = keep[index];
+            if retain && history_limit.is_some_and(|limit| kept > limit) {
+                kept -= 1;
+                retain = false;
+            }
+            if !retain && index < saved_history {
+                removed_saved += 1;
+            }
+            index += 1;
+            retain
+        });
+        self.saved_history -= removed_saved;
+        if self.history.len() < keep.len() {
+            self.index_of_history = None;
+        }
+    }
+
+    /// Sets whether commands starting with a space are kept out of the history
+    pub fn history_ignore_space(&mut self, ignore: bool) {
+        self.history_ignore_space = ignore;
+    }
+
+    /// Keeps commands matching the regular expression `pattern` out of the history, in addition to already added patterns
+    pub fn history_ignore_pattern(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        self.history_ignore_patterns.push(Regex::new(pattern)?);
+        Ok(())
+    }
+
+    /// Keeps commands for which `filter` returns `true` out of the history, e.g. commands containing credentials
+    pub fn history_ignore_filter(&mut self, filter: impl Fn(&str) -> bool + 'static) {
+        self.history_ignore_filter = Some(Box::new(filter));
+    }
+
+    /// Whether `command` is kept out of the history by the ignore rules
+    pub(crate) fn is_ignored_in_history(&self, command: &str) -> bool {
+        (self.history_ignore_space && command.starts_with(' '))
+            || self
+                .history_ignore_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(command))
+            || self
+                .history_ignore_filter
+                .as_ref()
+                .is_some_and(|filter| filter(command))
+    }
+
+    /// Sets whether `TuiClap::parse` expands history references, enabled by default
+    pub fn history_expansion(&mut self, enabled: bool) {
+        self.history_expansion_disabled = !enabled;
+    }
+
+    /// Expands the history references in `line`: `!!` to the last command, `!n` to entry `n`, numbered from 1 like in the
+    /// `history` builtin, and `!prefix` to the last command starting with `prefix`. Like in bash, a `!` is kept within
+    /// single quotes, after a backslash, and if followed by whitespace, a quote, `=`, `(` or the end of the line.
+    /// Returns `Ok(None)` if `line` contains no reference or history expansion is disabled,
+    /// and an error message if a reference matches no entry.
+    pub fn expand_history(&self, line: &str) -> Result<Option<String>, String> {
+        if self.history_expansion_disabled || !line.contains('!') {
+            return Ok(None);
+        }
+        let mut expanded = String::with_capacity(line.len());
+        let mut found = false;
+        let mut single_quoted = false;
+        let mut double_quoted = false;
+        let mut index = 0;
+        while let Some(c) = line[index..].chars().next() {
+            match c {
+                '\\' if !single_quoted => {
+                    // the escaped char is kept together with the backslash, which the tokenizer removes
+                    let escaped = line[index + 1..].chars().next().map_or(0, char::len_utf8);
+                    expanded.push_str(&line[index..index + 1 + escaped]);
+                    index += 1 + escaped;
+                    continue;
+                }
+                '\'' if !double_quoted => single_quoted = !single_quoted,
+                '"' if !single_quoted => double_quoted = !double_quoted,
+                '!' if !single_quoted => {
+                    let reference = &line[index + 1..];
+                    let len = reference_len(reference);
+                    if len > 0 {
+                        let reference = &reference[..len];
+                        match self.history_reference(reference) {
+                            Some(entry) => expanded.push_str(&entry.command),
+                            None => return Err(format!("!{}: event not found", reference)),
+                        }
+                        found = true;
+                        index += 1 + len;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            expanded.push(c);
+            index += c.len_utf8();
+        }
+
+        Ok(if found { Some(expanded) } else { None })
+    }
+
+    /// The entry `reference` refers to, the text behind the `!` of a history reference
+    fn history_reference(&self, reference: &str) -> Option<&HistoryEntry> {
+        match reference {
+            "!" => self.history.last(),
+            number if number.starts_with(|c: char| c.is_ascii_digit()) => number
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| self.history.get(number.checked_sub(1)?)),
+            prefix => self
+                .history
+                .iter()
+                .rev()
+                .find(|entry| entry.command.starts_with(prefix)),
+        }
+    }
+
+    /// Sets whether the most recently entered command succeeded, if it was recorded in the history,
+    /// like `CommandInputState::report_status` with the exit status 0 or 1
+    pub fn report_success(&mut self, success: bool) {
+        self.report_status(if success { 0 } else { 1 });
+    }
+
+    /// Sets the exit status of the most recently entered command, which succeeded if it is 0,
+    /// see `TuiClap::last_status`. Its success is recorded in the history if the command was.
+    pub fn report_status(&mut self, status: i32) {
+        self.last_status = Some(status);
+        if !self.last_recorded {
+            return;
+        }
+        if let Some(entry) = self.history.last_mut() {
+            entry.success = Some(status == 0);
+        }
+    }
+
+    /// Loads the history from the file at `path` and puts it in front of the entries entered since the last load or save.
+    /// A missing file is treated like an empty history.
+    pub fn load_history(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut entries = read_history_file(path.as_ref())?;
+        let saved = entries.len();
+        entries.extend(self.history.drain(self.saved_history..));
+        self.history = entries;
+        self.saved_history = saved;
+        self.trim_history();
+        Ok(())
+    }
+
+    /// Appends the entries entered since the last load or save to the file at `path`.
+    /// The file is locked while writing, so that several instances can share one history file.
+    pub fn save_history(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        append_history_file(path.as_ref(), &self.history[self.saved_history..])?;
+        self.saved_history = self.history.len();
+        Ok(())
+    }
+}
+
+/// Length of the history reference at the start of `reference`, the text behind a `!`, 0 if the `!` is no reference
+fn reference_len(reference: &str) -> usize {
+    let is_end = |c: char| c.is_whitespace() || c == '"' || c == '\'';
+    match reference.chars().next() {
+        None => 0,
+        Some(c) if is_end(c) || c == '=' || c == '(' => 0,
+        Some('!') => 1,
+        Some(c) if c.is_ascii_digit() => reference
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(reference.len()),
+        Some(_) => reference.find(is_end).unwrap_or(reference.len()),
+    }
+}
+
+/// Reads the entries of a history file, one per line as the seconds since the unix epoch, the success as `+`, `-` or `?`
+/// and the command with line breaks and backslashes escaped, separated by tabs.
+/// Lines without timestamp and success are read as command entered at the unix epoch.
+fn read_history_file(path: &Path) -> io::Result<Vec<HistoryEntry>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    file.lock_shared()?;
+    let mut content = String::new();
+    let read = file.read_to_string(&mut content);
+    file.unlock()?;
+    read?;
+
+    Ok(content.lines().map(parse_line).collect())
+}
+
+fn parse_line(line: &str) -> HistoryEntry {
+    let mut fields = line.splitn(3, '\t');
+    let parsed = match (fields.next(), fields.next(), fields.next()) {
+        (Some(secs), Some(success), Some(command)) => {
+            let success = match success {
+                "+" => Some(Some(true)),
+                "-" => Some(Some(false)),
+                "?" => Some(None),
+                _ => None,
+            };
+            secs.parse::<u64>()
+                .ok()
+                .zip(success)
+                .map(|(secs, success)| HistoryEntry {
+                    command: unescape(command),
+                    timestamp: UNIX_EPOCH + Duration::from_secs(secs),
+                    success,
+                })
+        }
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| HistoryEntry {
+        command: unescape(line),
+        timestamp: UNIX_EPOCH,
+        success: None,
+    })
+}
+
+fn append_history_file(path: &Path, entries: &[HistoryEntry]) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut content = String::new();
+    for entry in entries {
+        let secs = entry
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let success = match entry.success {
+            Some(true) => '+',
+            Some(false) => '-',
+            None => '?',
+        };
+        content.push_str(&format!(
+            "{}\t{}\t{}\n",
+            secs,
+            success,
+            escape(&entry.command)
+        ));
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.lock_exclusive()?;
+    let written = file.write_all(content.as_bytes());
+    file.unlock()?;
+    written
+}
+
+fn escape(entry: &str) -> String {
+    entry.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                entry.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                entry.push('\\');
+                chars.next();
+            }
+            _ => entry.push(c),
+        }
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CommandInputState;
+
+    fn with_history(commands: &[&str]) -> CommandInputState {
+        let mut state = CommandInputState::default();
+        for command in commands {
+            state.insert_str(command);
+            state.enter();
+        }
+        state
+    }
+
+    #[test]
+    fn expands_references() {
+        let state = with_history(&["git status", "ls -l"]);
+        let expand = |line| state.expand_history(line);
+        assert_eq!(expand("!!"), Ok(Some("ls -l".to_string())));
+        assert_eq!(expand("sudo !! -a"), Ok(Some("sudo ls -l -a".to_string())));
+        assert_eq!(expand("!1"), Ok(Some("git status".to_string())));
+        assert_eq!(expand("!gi"), Ok(Some("git status".to_string())));
+        assert_eq!(
+            expand("echo \"!!\""),
+            Ok(Some("echo \"ls -l\"".to_string()))
+        );
+        assert_eq!(
+            expand("echo !l\"x\""),
+            Ok(Some("echo ls -l\"x\"".to_string()))
+        );
+        assert_eq!(expand("ls"), Ok(None));
+    }
+
+    #[test]
+    fn unknown_references_fail() {
+        let state = with_history(&["git status"]);
+        assert_eq!(
+            state.expand_history("!nope"),
+            Err("!nope: event not found".to_string())
+        );
+        assert_eq!(
+            state.expand_history("!5"),
+            Err("!5: event not found".to_string())
+        );
+        assert_eq!(
+            state.expand_history("!0"),
+            Err("!0: event not found".to_string())
+        );
+    }
+
+    #[test]
+    fn keeps_bang_within_single_quotes() {
+        let state = with_history(&["git status"]);
+        assert_eq!(state.expand_history("echo 'hello!world'"), Ok(None));
+        assert_eq!(state.expand_history("echo '!!'"), Ok(None));
+        assert_eq!(
+            state.expand_history("echo '!!' !!"),
+            Ok(Some("echo '!!' git status".to_string()))
+        );
+        // a single quote within double quotes does not quote
+        assert_eq!(
+            state.expand_history("echo \"it's !!\""),
+            Ok(Some("echo \"it's git status\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn keeps_bang_which_is_no_reference() {
+        let state = with_history(&["git status"]);
+        for line in [
+            "echo \"hi!\"",
+            "echo hi!",
+            "echo hi! there",
+            "test a != b",
+            "echo !'x'",
+            "echo !(x)",
+            "echo \\!!",
+        ] {
+            assert_eq!(state.expand_history(line), Ok(None), "{}", line);
+        }
+    }
+
+    #[test]
+    fn disabled_expansion_keeps_line() {
+        let mut state = with_history(&["git status"]);
+        state.history_expansion(false);
+        assert_eq!(state.expand_history("!!"), Ok(None));
+    }
+}
@@ -0,0 +1,99 @@
+//! Popup listing the command history for `TuiClap`
+
+use crate::TuiClap;
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use tui::Frame;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HistoryPopup {
+    list_state: ListState,
+    /// Number of entries visible at once, as computed by the last render
+    page_size: usize,
+}
+
+impl TuiClap {
+    /// Opens the popup listing the history, with the most recent entry selected.
+    /// Has no effect if the history is empty.
+    pub fn open_history_popup(&mut self) {
+        let len = self.command_input_state.history().len();
+        if len == 0 {
+            return;
+        }
+        let mut popup = HistoryPopup::default();
+        popup.list_state.select(Some(len - 1));
+        self.history_popup = Some(popup);
+    }
+
+    pub fn close_history_popup(&mut self) {
+        self.history_popup = None;
+    }
+
+    pub fn is_history_popup_open(&self) -> bool {
+        self.history_popup.is_some()
+    }
+
+    /// Handles `key` while the history popup is open. The arrow keys, `PageUp`, `PageDown`, `Home` and `End`
+    /// move the selection, `Enter` puts the selected entry into the input widget and `Esc` closes the popup.
+    /// Returns `false` if the popup is not open.
+    pub(crate) fn handle_history_popup_key(&mut self, key: KeyEvent) -> bool {
+        let popup = match &mut self.history_popup {
+            Some(popup) => popup,
+            None => return false,
+        };
+        let last = self.command_input_state.history().len().saturating_sub(1);
+        let selected = popup.list_state.selected().unwrap_or(last);
+        let page_size = popup.page_size.max(1);
+        let selected = match key.code {
+            KeyCode::Up => selected.saturating_sub(1),
+            KeyCode::Down => selected + 1,
+            KeyCode::PageUp => selected.saturating_sub(page_size),
+            KeyCode::PageDown => selected + page_size,
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            KeyCode::Enter => {
+                self.history_popup = None;
+                self.command_input_state.recall_history(selected);
+                return true;
+            }
+            KeyCode::Esc => {
+                self.history_popup = None;
+                return true;
+            }
+            _ => return true,
+        };
+        popup.list_state.select(Some(selected.min(last)));
+        true
+    }
+
+    /// Renders the history popup on `area` of `tui::Frame`, if it is open.
+    /// The area is cleared first, so the popup can be drawn above other widgets.
+    pub fn render_history_popup<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let popup = match &mut self.history_popup {
+            Some(popup) => popup,
+            None => return,
+        };
+        let history = self.command_input_state.history();
+        let items: Vec<ListItem> = history
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                ListItem::new(format!(
+                    "{:>5}  {}",
+                    index + 1,
+                    entry.command.replace('\n', " ")
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().title("History").borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        popup.page_size = area.height.saturating_sub(2) as usize;
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut popup.list_state);
+    }
+}
@@ -0,0 +1,54 @@
+//! Hooks around parsing and running commands, e.g. for logging, rewriting the input or collecting metrics
+
+use crate::{CommandResult, TuiClap};
+use clap::ArgMatches;
+
+type BeforeParseHook = Box<dyn FnMut(&mut String)>;
+type AfterParseHook = Box<dyn FnMut(&ArgMatches)>;
+type AfterExecuteHook = Box<dyn FnMut(&CommandResult)>;
+
+/// The hooks registered with `TuiClap::before_parse`, `TuiClap::after_parse` and `TuiClap::after_execute`,
+/// each called in the order of registration
+#[derive(Default)]
+pub(crate) struct Hooks {
+    before_parse: Vec<BeforeParseHook>,
+    after_parse: Vec<AfterParseHook>,
+    after_execute: Vec<AfterExecuteHook>,
+}
+
+impl TuiClap {
+    /// Registers `hook` to be called with each command before aliases are expanded and variables substituted,
+    /// e.g. to log or rewrite it. Commands chained with `;` or `&&` are passed one by one, without their filters.
+    pub fn before_parse(&mut self, hook: impl FnMut(&mut String) + 'static) {
+        self.hooks.before_parse.push(Box::new(hook));
+    }
+
+    /// Registers `hook` to be called with the matches of each command clap parsed, before its handler is called
+    pub fn after_parse(&mut self, hook: impl FnMut(&ArgMatches) + 'static) {
+        self.hooks.after_parse.push(Box::new(hook));
+    }
+
+    /// Registers `hook` to be called with the result of each command run by a handler or built-in command,
+    /// once it completed, e.g. to collect metrics
+    pub fn after_execute(&mut self, hook: impl FnMut(&CommandResult) + 'static) {
+        self.hooks.after_execute.push(Box::new(hook));
+    }
+
+    pub(crate) fn run_before_parse_hooks(&mut self, command: &mut String) {
+        for hook in &mut self.hooks.before_parse {
+            hook(command);
+        }
+    }
+
+    pub(crate) fn run_after_parse_hooks(&mut self, matches: &ArgMatches) {
+        for hook in &mut self.hooks.after_parse {
+            hook(matches);
+        }
+    }
+
+    pub(crate) fn run_after_execute_hooks(&mut self, result: &CommandResult) {
+        for hook in &mut self.hooks.after_execute {
+            hook(result);
+        }
+    }
+}
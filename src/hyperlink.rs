@@ -0,0 +1,199 @@
+//! Hyperlinks in the output, rendered with OSC 8 escape sequences on terminals supporting them
+
+use crate::output::{Level, OutputLine};
+use crate::output_search::line_text;
+use crate::{CommandOutput, TuiClap};
+use crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use crossterm::queue;
+use crossterm::style::{
+    Attribute, Color as CColor, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use std::io::{self, Write};
+use std::ops::Range;
+use tui::buffer::{Buffer, Cell};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use unicode_width::UnicodeWidthStr;
+
+/// A hyperlink on a part of a line of the output
+#[derive(Debug, Clone)]
+pub(crate) struct Link {
+    /// Byte range of the label in the text of the line
+    pub(crate) range: Range<usize>,
+    pub(crate) url: String,
+}
+
+/// The part of a hyperlink rendered in a row, see `TuiClap::write_hyperlinks`
+#[derive(Debug, Clone)]
+pub(crate) struct RenderedLink {
+    x: u16,
+    y: u16,
+    url: String,
+    /// The cells the label was rendered to
+    cells: Vec<Cell>,
+}
+
+/// The parts of the hyperlinks of `line` in the row showing the byte range `row` of its text, which starts at
+/// column `x` of row `y` in `buf`. Labels are cut off at `right`.
+pub(crate) fn rendered_links(
+    line: &OutputLine,
+    row: Range<usize>,
+    x: u16,
+    y: u16,
+    right: u16,
+    buf: &Buffer,
+) -> Vec<RenderedLink> {
+    let text = line_text(&line.spans);
+    line.links
+        .iter()
+        .filter_map(|link| {
+            let start = link.range.start.max(row.start);
+            let end = link.range.end.min(row.end);
+            if start >= end {
+                return None;
+            }
+            let link_x = x.saturating_add(text[row.start..start].width() as u16);
+            let link_right = link_x
+                .saturating_add(text[start..end].width() as u16)
+                .min(right);
+            if link_x >= link_right {
+                return None;
+            }
+            Some(RenderedLink {
+                x: link_x,
+                y,
+                url: link.url.clone(),
+                cells: (link_x..link_right)
+                    .map(|column| buf.get(column, y).clone())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+impl CommandOutput {
+    /// Sets whether hyperlinks are rendered as OSC 8 hyperlinks by `TuiClap::write_hyperlinks`, for terminals
+    /// supporting them. Otherwise, which is the default, the URL is shown in parentheses behind the label.
+    pub fn hyperlinks(&mut self, enabled: bool) {
+        self.hyperlinks = enabled;
+    }
+
+    /// Sets the style of the labels written with `TuiClap::write_link`, blue and underlined by default
+    pub fn link_style(&mut self, style: Style) {
+        self.link_style = style;
+    }
+
+    /// Appends the URLs of the hyperlinks of `line` in parentheses behind their labels to `spans`,
+    /// if hyperlinks are not rendered as OSC 8 hyperlinks
+    pub(crate) fn show_urls<'a>(
+        &self,
+        line: &OutputLine,
+        spans: Vec<Span<'a>>,
+        style: Style,
+    ) -> Vec<Span<'a>> {
+        if self.hyperlinks || line.links.is_empty() {
+            return spans;
+        }
+        let mut offset = 0;
+        let mut with_urls = vec![];
+        for span in spans {
+            offset += span.content.len();
+            let empty = span.content.is_empty();
+            with_urls.push(span);
+            if empty {
+                continue;
+            }
+            for link in line.links.iter().filter(|link| link.range.end == offset) {
+                with_urls.push(Span::styled(format!(" ({})", link.url), style));
+            }
+        }
+        with_urls
+    }
+}
+
+impl TuiClap {
+    /// Write a line consisting of a hyperlink to `url` labeled `label` to the output widget.
+    /// Handlers can also write OSC 8 hyperlinks, which are kept if `TuiClap::set_ansi_parsing` is enabled.
+    pub fn write_link(&mut self, label: &str, url: &str) {
+        let link = Link {
+            range: 0..label.len(),
+            url: url.to_string(),
+        };
+        let spans = Spans::from(Span::styled(
+            label.to_string(),
+            self.command_output_widget.link_style,
+        ));
+        self.command_output_state
+            .push_line(spans, vec![link], Level::Info);
+    }
+
+    /// Writes the hyperlinks rendered by the last draw to `out` as OSC 8 hyperlinks, if enabled with
+    /// `CommandOutput::hyperlinks`. tui has no notion of hyperlinks, so this has to be called after each draw,
+    /// e.g. with `terminal.backend_mut()`, to overwrite their labels with the same content wrapped in OSC 8 sequences.
+    pub fn write_hyperlinks<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let links = self
+            .command_output_state
+            .rendered_links
+            .iter()
+            .chain(&self.command_error_state.rendered_links);
+        queue!(out, SavePosition)?;
+        for link in links {
+            queue!(
+                out,
+                MoveTo(link.x, link.y),
+                Print(format!("\x1b]8;;{}\x1b\\", link.url))
+            )?;
+            // the cells covered by a wide grapheme cluster are skipped like tui does
+            let mut skip = 0;
+            let mut previous: Option<&Cell> = None;
+            for cell in &link.cells {
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                if previous.is_none_or(|previous| previous.style() != cell.style()) {
+                    queue_style(out, cell)?;
+                }
+                previous = Some(cell);
+                queue!(out, Print(&cell.symbol))?;
+                skip = cell.symbol.width().saturating_sub(1);
+            }
+            queue!(
+                out,
+                Print("\x1b]8;;\x1b\\"),
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(CColor::Reset),
+                SetBackgroundColor(CColor::Reset)
+            )?;
+        }
+        queue!(out, RestorePosition)?;
+        out.flush()
+    }
+}
+
+/// Queues the style of `cell`, resetting the style of the previous cell
+fn queue_style<W: Write>(out: &mut W, cell: &Cell) -> io::Result<()> {
+    queue!(
+        out,
+        SetAttribute(Attribute::Reset),
+        SetForegroundColor(CColor::from(cell.fg)),
+        SetBackgroundColor(CColor::from(cell.bg))
+    )?;
+    let attributes = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::SLOW_BLINK, Attribute::SlowBlink),
+        (Modifier::RAPID_BLINK, Attribute::RapidBlink),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::HIDDEN, Attribute::Hidden),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+    ];
+    for (modifier, attribute) in attributes {
+        if cell.modifier.contains(modifier) {
+            queue!(out, SetAttribute(attribute))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Background jobs: commands ending with `&` run their handler on a worker thread while the prompt can be used
+
+use crate::handlers::deepest_handled;
+use crate::tokenizer::split_pipeline;
+use crate::{CancellationToken, CommandResult, TuiClap};
+use clap::ArgMatches;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tui::text::{Span, Spans};
+
+type HandlerResult = Result<Vec<String>, String>;
+
+/// A handler which can run on a worker thread, see `TuiClap::on_worker`
+pub(crate) type WorkerHandler =
+    Arc<dyn Fn(&ArgMatches, &CancellationToken) -> HandlerResult + Send + Sync>;
+
+/// A command running in the background
+struct Job {
+    id: usize,
+    /// The command as entered
+    command: String,
+    /// The filters the output is piped through once the job completed
+    filters: Vec<String>,
+    started: Instant,
+    cancellation_token: CancellationToken,
+}
+
+/// The background jobs of `TuiClap`
+pub(crate) struct Jobs {
+    /// The handlers which can run in the background by the name of their subcommand
+    workers: HashMap<String, WorkerHandler>,
+    running: Vec<Job>,
+    /// Delivers the results with the id of their job and how long it ran
+    tx: Sender<(usize, Duration, HandlerResult)>,
+    rx: Receiver<(usize, Duration, HandlerResult)>,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Jobs {
+            workers: HashMap::new(),
+            running: vec![],
+            tx,
+            rx,
+        }
+    }
+}
+
+impl Jobs {
+    /// Forgets the worker handler of the subcommand `path`, since another handler was registered for it
+    pub(crate) fn remove_worker(&mut self, path: &str) {
+        self.workers.remove(path);
+    }
+
+    /// Exchanges the worker handlers with the ones of another mode
+    pub(crate) fn swap_workers(&mut self, workers: &mut HashMap<String, WorkerHandler>) {
+        mem::swap(&mut self.workers, workers);
+    }
+}
+
+impl TuiClap {
+    /// Registers `handler` for the subcommand `name` like `TuiClap::on`. Since it only gets the matches and a
+    /// `CancellationToken`, it can run in the background on a worker thread as well, if the command ends with `&`.
+    /// The prompt can be used meanwhile and the output of the job is written once it completes, see `TuiClap::poll_jobs`.
+    pub fn on_worker(
+        &mut self,
+        name: &str,
+        handler: impl Fn(&ArgMatches, &CancellationToken) -> HandlerResult + Send + Sync + 'static,
+    ) {
+        let handler: WorkerHandler = Arc::new(handler);
+        let worker = handler.clone();
+        self.on(name, move |matches, tui| {
+            worker(matches, &tui.cancellation_token())
+        });
+        let path = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.jobs.workers.insert(path, handler);
+    }
+
+    /// The ids and commands of the jobs running in the background, e.g. to show their number in the prompt
+    pub fn running_jobs(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.jobs
+            .running
+            .iter()
+            .map(|job| (job.id, job.command.as_str()))
+    }
+
+    /// Cancels the `CancellationToken` of the job `id`. Returns `false` if there is no such job.
+    pub fn cancel_job(&mut self, id: usize) -> bool {
+        match self.jobs.running.iter().find(|job| job.id == id) {
+            Some(job) => {
+                job.cancellation_token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts `command` as background job, see `TuiClap::on_worker`
+    pub(crate) fn start_job(&mut self, command: &str) {
+        let entered = command.trim();
+        let mut stages = split_pipeline(entered).into_iter();
+        let command = stages.next().unwrap_or_default().trim();
+        let filters = stages.map(|filter| filter.to_string()).collect();
+        let matches = match self.run_command(command) {
+            Ok(matches) => matches,
+            Err(()) => return,
+        };
+        let workers = &self.jobs.workers;
+        let handler = deepest_handled(&matches, |key| self.handlers.contains_key(key))
+            .and_then(|(key, sub_matches)| Some((workers.get(&key)?.clone(), sub_matches.clone())));
+        let (handler, matches) = match handler {
+            Some(handler) => handler,
+            None => {
+                self.write_error(format!(
+                    "error: '{}' has no handler which can run in the background",
+                    command
+                ));
+                self.command_input_state.report_success(false);
+                return;
+            }
+        };
+
+        let id = self.jobs.running.last().map_or(1, |job| job.id + 1);
+        let cancellation_token = CancellationToken::new();
+        let tx = self.jobs.tx.clone();
+        let token = cancellation_token.clone();
+        thread::spawn(move || {
+            let started = Instant::now();
+            let result = handler(&matches, &token);
+            // the receiver is only gone if `TuiClap` was dropped
+            let _ = tx.send((id, started.elapsed(), result));
+        });
+        self.jobs.running.push(Job {
+            id,
+            command: entered.to_string(),
+            filters,
+            started: Instant::now(),
+            cancellation_token,
+        });
+        self.command_input_state.report_success(true);
+        self.write_to_output(format!("[{}] started", id));
+    }
+
+    /// Writes the output of the jobs which completed since the last call, each headed by its id, see
+    /// `TuiClap::on_worker`. Called by `TuiClap::fetch_event` and `TuiClap::render_output`.
+    pub fn poll_jobs(&mut self) {
+        while let Ok((id, duration, result)) = self.jobs.rx.try_recv() {
+            let index = match self.jobs.running.iter().position(|job| job.id == id) {
+                Some(index) => index,
+                None => continue,
+            };
+            let job = self.jobs.running.remove(index);
+            let result = result.and_then(|lines| {
+                job.filters
+                    .iter()
+                    .try_fold(lines, |lines, filter| self.apply_filter(filter, lines))
+            });
+            let result = CommandResult::from(result);
+            let status = if result.is_success() {
+                "done"
+            } else {
+                "failed"
+            };
+            self.record_duration(duration, None);
+            self.write_spans(Spans::from(vec![
+                Span::raw(format!("[{}] {}  {}", id, status, job.command)),
+                self.timing_span(duration),
+            ]));
+            self.run_after_execute_hooks(&result);
+            self.write_result(result);
+        }
+    }
+
+    /// `jobs` lists the jobs running in the background with the time since they started
+    pub(crate) fn builtin_jobs(&mut self) -> HandlerResult {
+        self.poll_jobs();
+        Ok(self
+            .jobs
+            .running
+            .iter()
+            .map(|job| {
+                let seconds = job.started.elapsed().as_secs();
+                format!("[{}] running {}s  {}", job.id, seconds, job.command)
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,138 @@
+//! Pretty-printing JSON values to the output with syntax coloring
+
+use crate::output::Level;
+use crate::{CommandOutput, TuiClap};
+use serde_json::Value;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+
+/// Styles of the tokens of JSON written with `TuiClap::write_json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonStyles {
+    /// Style of the keys of objects
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    /// Style of `true`, `false` and `null`
+    pub literal: Style,
+    /// Style of brackets, braces, colons and commas, and of the number of folded items
+    pub punctuation: Style,
+}
+
+impl Default for JsonStyles {
+    fn default() -> Self {
+        JsonStyles {
+            key: Style::default().fg(Color::Blue),
+            string: Style::default().fg(Color::Green),
+            number: Style::default().fg(Color::Cyan),
+            literal: Style::default().fg(Color::Magenta),
+            punctuation: Style::default(),
+        }
+    }
+}
+
+impl CommandOutput {
+    /// Sets the styles of JSON written with `TuiClap::write_json`
+    pub fn json_styles(&mut self, styles: JsonStyles) {
+        self.json_styles = styles;
+    }
+
+    /// Sets the number of items of arrays shown by `TuiClap::write_json`, folding the remaining ones
+    /// into a line stating their number. `None`, the default, shows all items.
+    pub fn json_fold(&mut self, items: Option<usize>) {
+        self.json_fold = items;
+    }
+}
+
+/// Lines of pretty-printed JSON, indented by two spaces per level
+struct JsonLines<'a> {
+    styles: &'a JsonStyles,
+    fold: Option<usize>,
+    lines: Vec<Spans<'static>>,
+    line: Vec<Span<'static>>,
+}
+
+impl JsonLines<'_> {
+    fn push(&mut self, text: String, style: Style) {
+        self.line.push(Span::styled(text, style));
+    }
+
+    fn new_line(&mut self, indent: usize) {
+        let line = std::mem::take(&mut self.line);
+        self.lines.push(Spans::from(line));
+        self.line.push(Span::raw("  ".repeat(indent)));
+    }
+
+    fn value(&mut self, value: &Value, indent: usize) {
+        let styles = self.styles;
+        match value {
+            Value::Null => self.push("null".to_string(), styles.literal),
+            Value::Bool(value) => self.push(value.to_string(), styles.literal),
+            Value::Number(value) => self.push(value.to_string(), styles.number),
+            Value::String(value) => {
+                self.push(Value::from(value.as_str()).to_string(), styles.string)
+            }
+            Value::Array(items) if items.is_empty() => {
+                self.push("[]".to_string(), styles.punctuation)
+            }
+            Value::Array(items) => {
+                self.push("[".to_string(), styles.punctuation);
+                let shown = self.fold.map_or(items.len(), |fold| fold.min(items.len()));
+                for (i, item) in items[..shown].iter().enumerate() {
+                    self.new_line(indent + 1);
+                    self.value(item, indent + 1);
+                    if i + 1 < items.len() {
+                        self.push(",".to_string(), styles.punctuation);
+                    }
+                }
+                if shown < items.len() {
+                    self.new_line(indent + 1);
+                    let folded = match items.len() - shown {
+                        1 => "… 1 more item".to_string(),
+                        n => format!("… {} more items", n),
+                    };
+                    self.push(folded, styles.punctuation);
+                }
+                self.new_line(indent);
+                self.push("]".to_string(), styles.punctuation);
+            }
+            Value::Object(entries) if entries.is_empty() => {
+                self.push("{}".to_string(), styles.punctuation)
+            }
+            Value::Object(entries) => {
+                self.push("{".to_string(), styles.punctuation);
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    self.new_line(indent + 1);
+                    self.push(Value::from(key.as_str()).to_string(), styles.key);
+                    self.push(": ".to_string(), styles.punctuation);
+                    self.value(value, indent + 1);
+                    if i + 1 < entries.len() {
+                        self.push(",".to_string(), styles.punctuation);
+                    }
+                }
+                self.new_line(indent);
+                self.push("}".to_string(), styles.punctuation);
+            }
+        }
+    }
+}
+
+impl TuiClap {
+    /// Writes `value` pretty-printed and syntax colored to the output widget, see `CommandOutput::json_styles`.
+    /// Large arrays can be folded with `CommandOutput::json_fold`.
+    pub fn write_json(&mut self, value: &Value) {
+        let output = &self.command_output_widget;
+        let mut json = JsonLines {
+            styles: &output.json_styles,
+            fold: output.json_fold,
+            lines: vec![],
+            line: vec![],
+        };
+        json.value(value, 0);
+        json.new_line(0);
+        for line in json.lines {
+            self.command_output_state
+                .push_line(line, vec![], Level::Info);
+        }
+    }
+}
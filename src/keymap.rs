@@ -0,0 +1,282 @@
+//! Configurable key bindings for `TuiClap`
+
+use crate::CommandInputState;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// An action editing the content of `CommandInputState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditAction {
+    MoveLeft,
+    MoveRight,
+    MoveWordLeft,
+    MoveWordRight,
+    MoveToStart,
+    MoveToEnd,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWordBackward,
+    DeleteWordForward,
+    KillToStart,
+    KillToEnd,
+    Yank,
+    YankPop,
+    SelectLeft,
+    SelectRight,
+    SelectWordLeft,
+    SelectWordRight,
+    SelectToStart,
+    SelectToEnd,
+    SelectAll,
+    NewLine,
+}
+
+/// An action of the REPL around the input line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplAction {
+    /// Submit the input for parsing
+    Submit,
+    HistoryPrevious,
+    HistoryNext,
+    /// Start a reverse incremental history search or search the next older match
+    HistorySearch,
+    /// Open the popup listing the history
+    HistoryPopup,
+    /// Complete the token before the cursor, see `TuiClap::complete`
+    Complete,
+    /// Scroll the output up by the height of the output widget
+    ScrollPageUp,
+    ScrollPageDown,
+    /// Scroll the output up by half the height of the output widget. Unbound by default, since `Ctrl+U` and `Ctrl+D`
+    /// are bound to `EditAction::KillToStart` and `ReplAction::ExitOrDeleteForward`, but they scroll by half a page
+    /// while the output is focused, see `TuiClap::set_focus`.
+    ScrollHalfPageUp,
+    ScrollHalfPageDown,
+    /// Scroll the output left by a quarter of the width of the output widget in `WrapMode::None`
+    ScrollLeft,
+    ScrollRight,
+    /// Start typing a pattern to search the output for, see `TuiClap::start_output_search`
+    SearchOutput,
+    /// Scroll to the next older match of the output search
+    SearchOutputNext,
+    SearchOutputPrevious,
+    /// Select lines of the output with the arrow keys, see `CommandOutputState::start_selection`
+    SelectOutput,
+    /// Show the lines hidden by the output filter again, see `CommandOutputState::set_filter`
+    ClearOutputFilter,
+    /// Fold or unfold the section of the newest command shown in the view, see `CommandOutputState::toggle_section`
+    ToggleSection,
+    /// Put the correction of the last command suggested by clap into the input, see `TuiClap::accept_correction`
+    AcceptCorrection,
+    /// Write what the input resolves to without running it, see `TuiClap::preview`
+    Preview,
+    /// Toggle following new lines of the output, see `CommandOutputState::follow`
+    ToggleFollow,
+    /// Clear the output, see `TuiClap::clear_output`
+    ClearOutput,
+    /// Cancel the running handlers and discard the input, see `TuiClap::interrupt`
+    Interrupt,
+    Exit,
+    /// Exit if the input is empty, otherwise delete the grapheme cluster behind the cursor, like `Ctrl+D` in readline.
+    /// `TuiClap::handle_key_event` returns `ReplAction::Exit` for it if the input is empty.
+    ExitOrDeleteForward,
+    #[cfg(feature = "clipboard")]
+    CopyToClipboard,
+    #[cfg(feature = "clipboard")]
+    PasteFromClipboard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Edit(EditAction),
+    Repl(ReplAction),
+}
+
+impl From<EditAction> for Action {
+    fn from(action: EditAction) -> Self {
+        Action::Edit(action)
+    }
+}
+
+impl From<ReplAction> for Action {
+    fn from(action: ReplAction) -> Self {
+        Action::Repl(action)
+    }
+}
+
+/// Maps key events to actions.
+/// The default keymap provides readline-like bindings.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl Keymap {
+    /// Creates a keymap without any bindings
+    pub fn empty() -> Keymap {
+        Keymap {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Binds the key `code` pressed together with `modifiers` to `action`, replacing any previous binding
+    pub fn bind(&mut self, code: KeyCode, modifiers: KeyModifiers, action: impl Into<Action>) {
+        self.bindings
+            .insert(Self::normalize(code, modifiers), action.into());
+    }
+
+    pub fn unbind(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        self.bindings.remove(&Self::normalize(code, modifiers));
+    }
+
+    /// The action bound to `key`, if any
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .get(&Self::normalize(key.code, key.modifiers))
+            .copied()
+    }
+
+    /// Shift is ignored for chars since it is already reflected by the case of the char
+    fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+        match code {
+            KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+            _ => (code, modifiers),
+        }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use self::EditAction::*;
+        use self::ReplAction::*;
+
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+        let shift = KeyModifiers::SHIFT;
+
+        let mut keymap = Keymap::empty();
+        keymap.bind(KeyCode::Left, none, MoveLeft);
+        keymap.bind(KeyCode::Char('b'), ctrl, MoveLeft);
+        keymap.bind(KeyCode::Right, none, MoveRight);
+        keymap.bind(KeyCode::Char('f'), ctrl, MoveRight);
+        keymap.bind(KeyCode::Left, ctrl, MoveWordLeft);
+        keymap.bind(KeyCode::Char('b'), alt, MoveWordLeft);
+        keymap.bind(KeyCode::Right, ctrl, MoveWordRight);
+        keymap.bind(KeyCode::Char('f'), alt, MoveWordRight);
+        keymap.bind(KeyCode::Home, none, MoveToStart);
+        keymap.bind(KeyCode::Char('a'), ctrl, MoveToStart);
+        keymap.bind(KeyCode::End, none, MoveToEnd);
+        keymap.bind(KeyCode::Char('e'), ctrl, MoveToEnd);
+        keymap.bind(KeyCode::Backspace, none, DeleteBackward);
+        keymap.bind(KeyCode::Char('h'), ctrl, DeleteBackward);
+        keymap.bind(KeyCode::Delete, none, DeleteForward);
+        keymap.bind(KeyCode::Char('w'), ctrl, DeleteWordBackward);
+        keymap.bind(KeyCode::Backspace, alt, DeleteWordBackward);
+        keymap.bind(KeyCode::Char('d'), alt, DeleteWordForward);
+        keymap.bind(KeyCode::Char('u'), ctrl, KillToStart);
+        keymap.bind(KeyCode::Char('k'), ctrl, KillToEnd);
+        keymap.bind(KeyCode::Char('y'), ctrl, Yank);
+        keymap.bind(KeyCode::Char('y'), alt, YankPop);
+        keymap.bind(KeyCode::Left, shift, SelectLeft);
+        keymap.bind(KeyCode::Right, shift, SelectRight);
+        keymap.bind(KeyCode::Left, ctrl | shift, SelectWordLeft);
+        keymap.bind(KeyCode::Right, ctrl | shift, SelectWordRight);
+        keymap.bind(KeyCode::Home, shift, SelectToStart);
+        keymap.bind(KeyCode::End, shift, SelectToEnd);
+        keymap.bind(KeyCode::Enter, shift, NewLine);
+        keymap.bind(KeyCode::Enter, alt, NewLine);
+
+        keymap.bind(KeyCode::Enter, none, Submit);
+        keymap.bind(KeyCode::Up, none, HistoryPrevious);
+        keymap.bind(KeyCode::Char('p'), ctrl, HistoryPrevious);
+        keymap.bind(KeyCode::Down, none, HistoryNext);
+        keymap.bind(KeyCode::Char('n'), ctrl, HistoryNext);
+        keymap.bind(KeyCode::Char('r'), ctrl, HistorySearch);
+        keymap.bind(KeyCode::F(7), none, HistoryPopup);
+        keymap.bind(KeyCode::Tab, none, Complete);
+        keymap.bind(KeyCode::PageUp, none, ScrollPageUp);
+        keymap.bind(KeyCode::PageDown, none, ScrollPageDown);
+        keymap.bind(KeyCode::Left, alt, ScrollLeft);
+        keymap.bind(KeyCode::Right, alt, ScrollRight);
+        keymap.bind(KeyCode::Char('/'), alt, SearchOutput);
+        keymap.bind(KeyCode::F(3), none, SearchOutputNext);
+        keymap.bind(KeyCode::F(3), shift, SearchOutputPrevious);
+        keymap.bind(KeyCode::F(4), none, ClearOutputFilter);
+        keymap.bind(KeyCode::Char('s'), alt, SelectOutput);
+        keymap.bind(KeyCode::Char('l'), ctrl, ClearOutput);
+        keymap.bind(KeyCode::Char('t'), alt, ToggleFollow);
+        keymap.bind(KeyCode::Char('z'), alt, ToggleSection);
+        keymap.bind(KeyCode::Char('c'), alt, AcceptCorrection);
+        keymap.bind(KeyCode::Char('p'), alt, Preview);
+        keymap.bind(KeyCode::Char('c'), ctrl, Interrupt);
+        keymap.bind(KeyCode::Char('d'), ctrl, ExitOrDeleteForward);
+        #[cfg(feature = "clipboard")]
+        {
+            keymap.bind(KeyCode::Char('w'), alt, CopyToClipboard);
+            keymap.bind(KeyCode::Char('v'), ctrl, PasteFromClipboard);
+        }
+        keymap
+    }
+}
+
+impl CommandInputState {
+    /// Applies the edit `action` to the content
+    pub fn apply(&mut self, action: EditAction) {
+        match action {
+            EditAction::MoveLeft => self.move_cursor_left(),
+            EditAction::MoveRight => self.move_cursor_right(),
+            EditAction::MoveWordLeft => self.move_cursor_word_left(),
+            EditAction::MoveWordRight => self.move_cursor_word_right(),
+            EditAction::MoveToStart => self.move_cursor_to_start(),
+            EditAction::MoveToEnd => self.move_cursor_to_end(),
+            EditAction::DeleteBackward => self.del_char(),
+            EditAction::DeleteForward => self.del_next_char(),
+            EditAction::DeleteWordBackward => self.del_prev_word(),
+            EditAction::DeleteWordForward => self.del_next_word(),
+            EditAction::KillToStart => self.kill_to_start(),
+            EditAction::KillToEnd => self.kill_to_end(),
+            EditAction::Yank => self.yank(),
+            EditAction::YankPop => self.yank_pop(),
+            EditAction::SelectLeft => self.select_left(),
+            EditAction::SelectRight => self.select_right(),
+            EditAction::SelectWordLeft => self.select_word_left(),
+            EditAction::SelectWordRight => self.select_word_right(),
+            EditAction::SelectToStart => self.select_to_start(),
+            EditAction::SelectToEnd => self.select_to_end(),
+            EditAction::SelectAll => self.select_all(),
+            EditAction::NewLine => self.new_line(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ReplAction, TuiClap};
+    use clap::Command;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn ctrl_d() -> KeyEvent {
+        KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn ctrl_d_deletes_forward_unless_input_is_empty() {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        tui.state().insert_str("ab");
+        tui.state().move_cursor_to_start();
+        assert_eq!(tui.handle_key_event(ctrl_d()), None);
+        assert_eq!(tui.state().content(), "b");
+        assert_eq!(tui.handle_key_event(ctrl_d()), None);
+        assert_eq!(tui.state().content(), "");
+        assert_eq!(tui.handle_key_event(ctrl_d()), Some(ReplAction::Exit));
+    }
+
+    #[test]
+    fn ctrl_d_at_end_of_input_keeps_it() {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        tui.state().insert_str("ab");
+        assert_eq!(tui.handle_key_event(ctrl_d()), None);
+        assert_eq!(tui.state().content(), "ab");
+    }
+}
@@ -1,86 +1,831 @@
-use clap::{App, ArgMatches, ErrorKind};
-use crossterm::event::{poll, read, Event, KeyCode};
-use std::borrow::BorrowMut;
+mod activation;
+mod aliases;
+mod ansi;
+mod async_completion;
+#[cfg(feature = "tokio")]
+mod async_handlers;
+mod builtins;
+mod cancellation;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod completion;
+mod completion_menu;
+mod confirmation;
+mod correction;
+mod derive;
+mod diagnostics;
+mod focus;
+mod fuzzy;
+mod grammar;
+mod handlers;
+mod help_popup;
+mod help_styles;
+mod highlight;
+mod history;
+mod history_popup;
+mod hooks;
+mod hyperlink;
+mod jobs;
+#[cfg(feature = "json")]
+mod json;
+mod keymap;
+mod markdown;
+mod modes;
+mod output;
+mod output_search;
+mod output_selection;
+mod path_completion;
+mod pipes;
+mod preview;
+mod questions;
+mod resize;
+mod scopes;
+mod scroll;
+mod search;
+mod sections;
+mod shell_history;
+mod signature_help;
+mod source;
+mod spinner;
+mod status;
+mod table;
+mod timing;
+mod tokenizer;
+mod variables;
+mod vi;
+mod waker;
+mod wizard;
+
+use crate::ansi::parse_ansi;
+use crate::async_completion::AsyncCompletion;
+#[cfg(feature = "tokio")]
+use crate::async_handlers::AsyncHandlers;
+use crate::builtins::{default_builtins, Builtin};
+#[cfg(feature = "clipboard")]
+use crate::clipboard::Clipboard;
+use crate::confirmation::Confirmation;
+use crate::diagnostics::Diagnostics;
+use crate::focus::FocusState;
+use crate::handlers::Handler;
+use crate::help_popup::HelpPopup;
+use crate::help_styles::style_help;
+use crate::history_popup::HistoryPopup;
+use crate::hooks::Hooks;
+use crate::hyperlink::{rendered_links, Link, RenderedLink};
+use crate::jobs::Jobs;
+use crate::markdown::parse_markdown;
+use crate::modes::Mode;
+use crate::output::{crop, format_timestamp, wrap, OutputLine, TIMESTAMP_WIDTH};
+use crate::output_search::{OutputSearch, OutputSearchInput};
+use crate::output_selection::OutputSelection;
+use crate::pipes::{default_filters, Filter};
+use crate::questions::Questions;
+use crate::resize::Resize;
+use crate::scopes::Scopes;
+use crate::search::HistorySearch;
+use crate::spinner::{Echo, Spinner};
+use crate::status::USAGE_ERROR;
+use crate::timing::Timing;
+use crate::vi::ViState;
+use crate::waker::Waker;
+use clap::error::ErrorKind;
+use clap::{ArgMatches, Command};
+use crossterm::event::{
+    poll, read, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+    EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+};
+use crossterm::execute;
+use regex::Regex;
+use std::borrow::{BorrowMut, Cow};
 use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{self, stdout};
+use std::iter;
+use std::mem;
+use std::path::PathBuf;
 use std::str::Lines;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{RecvError, TryRecvError};
 use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tui::backend::Backend;
 use tui::buffer::Buffer;
 use tui::layout::Rect;
-use tui::style::Style;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
 use tui::widgets::{StatefulWidget, Widget};
 use tui::Frame;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+pub use crate::cancellation::CancellationToken;
+pub use crate::completion::{Candidate, Completer, CompletionMode};
+pub use crate::completion_menu::{CompletionMenu, CompletionMenuState};
+pub use crate::focus::Focus;
+pub use crate::help_styles::HelpStyles;
+pub use crate::highlight::HighlightStyles;
+pub use crate::history::{HistoryDuplicates, HistoryEntry};
+#[cfg(feature = "json")]
+pub use crate::json::JsonStyles;
+pub use crate::keymap::{Action, EditAction, Keymap, ReplAction};
+pub use crate::markdown::MarkdownStyles;
+pub use crate::output::{Level, LineNumbers, OutputHandle, WrapMode};
+pub use crate::path_completion::PathCompleter;
+pub use crate::shell_history::HistoryFormat;
+pub use crate::signature_help::SignatureHelp;
+pub use crate::status::CommandResult;
+pub use crate::tokenizer::{tokenize, TokenizeError};
+pub use crate::vi::ViMode;
+
+/// An event read by `Events`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    /// An input event of `crossterm`, like a key, a paste, a mouse or a resize event
+    Input(Event),
+    /// Sent every `Config::tick_rate`, e.g. to animate a spinner or redraw periodically
+    Tick,
+    /// Sent once an asynchronous completer delivered its candidates, see `TuiClap::add_async_completer`.
+    /// `TuiClap::fetch_event` presents them once all completers finished.
+    CompletionReady,
+    /// Sent once an asynchronous handler completed, see `TuiClap::on_async`, which needs the `tokio` feature.
+    /// `TuiClap::fetch_event` writes its result to the output.
+    HandlerCompleted,
+}
 
 /// Helper struct to read from `crossterm`'s input events
 pub struct Events {
-    rx: mpsc::Receiver<Event>,
+    rx: mpsc::Receiver<TerminalEvent>,
+    /// Sends to `rx` from background work, see `TuiClap::connect_events`
+    waker: Waker,
     ignore_exit_key: Arc<AtomicBool>,
+    bracketed_paste: bool,
+    mouse_capture: bool,
 }
 
 /// The command input widget itself
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct CommandInput {
     prompt: String,
+    continuation_prompt: String,
+    echo_mode: EchoMode,
+    invalid_style: Style,
+    suggestion_style: Style,
+    placeholder: String,
+    placeholder_style: Style,
+    enabled: bool,
+    disabled_style: Style,
+}
+
+impl Default for CommandInput {
+    fn default() -> Self {
+        CommandInput {
+            prompt: String::new(),
+            continuation_prompt: String::new(),
+            echo_mode: EchoMode::default(),
+            invalid_style: Style::default()
+                .fg(Color::Red)
+                .add_modifier(Modifier::UNDERLINED),
+            suggestion_style: Style::default().fg(Color::DarkGray),
+            placeholder: String::new(),
+            placeholder_style: Style::default().fg(Color::DarkGray),
+            enabled: true,
+            disabled_style: Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+/// Result of validating the content of the input widget while it is typed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Validation {
+    #[default]
+    Valid,
+    /// The content is invalid, optionally with a message describing why
+    Invalid(Option<String>),
+}
+
+type Validator = Box<dyn Fn(&str) -> Validation>;
+type HistoryFilter = Box<dyn Fn(&str) -> bool>;
+type OutputFilter = Box<dyn Fn(&str) -> bool>;
+type LineCallback = Box<dyn FnMut(&str)>;
+
+/// How the content of the `CommandInput` widget is displayed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EchoMode {
+    /// Display the content as typed
+    #[default]
+    Normal,
+    /// Display every grapheme cluster as the given char, e.g. for passwords
+    Masked(char),
+    /// Display nothing at all
+    Hidden,
+}
+
+impl EchoMode {
+    fn display<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            EchoMode::Normal => Cow::Borrowed(text),
+            EchoMode::Masked(c) => Cow::Owned(c.to_string().repeat(text.graphemes(true).count())),
+            EchoMode::Hidden => Cow::Borrowed(""),
+        }
+    }
+
+    fn width(&self, text: &str) -> usize {
+        match self {
+            EchoMode::Normal => text.width(),
+            EchoMode::Masked(c) => text.graphemes(true).count() * c.width().unwrap_or(0),
+            EchoMode::Hidden => 0,
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct CommandInputState {
-    history: Vec<String>,
-    index_of_history: usize,
+    history: Vec<HistoryEntry>,
+    /// Index of the history entry shown as content, `None` while editing the draft
+    index_of_history: Option<usize>,
+    /// The content typed before navigating into the history, restored when navigating forward past the newest entry
+    draft: String,
     content: String,
+    /// Byte offset of the cursor into `content`, always on a grapheme cluster boundary
+    cursor: usize,
+    /// Screen position of the cursor, as computed by the last render
+    cursor_position: Option<(u16, u16)>,
+    /// Killed text, most recent first
+    kill_ring: VecDeque<String>,
+    /// Byte range of the last yanked text and the kill ring index it was taken from
+    last_yank: Option<(usize, usize, usize)>,
+    /// Byte offset of the other end of the selection, which spans from here to the cursor
+    selection_anchor: Option<usize>,
+    /// Number of history entries that are already stored in the history file
+    saved_history: usize,
+    /// Whether entered commands are kept out of the history
+    history_disabled: bool,
+    /// Whether the most recently entered command was recorded in the history
+    last_recorded: bool,
+    /// The exit status last reported by `report_status`, even if the command was not recorded
+    pub(crate) last_status: Option<i32>,
+    history_expansion_disabled: bool,
+    /// Whether commands starting with a space are kept out of the history
+    history_ignore_space: bool,
+    history_ignore_patterns: Vec<Regex>,
+    history_ignore_filter: Option<HistoryFilter>,
+    /// Maximum number of history entries, `None` if unlimited
+    history_limit: Option<usize>,
+    history_duplicates: HistoryDuplicates,
+    /// Whether history entries are suggested while typing
+    autosuggest: bool,
+    /// Result of the last validation of the content
+    validation: Validation,
+    /// Styled byte ranges of the content, e.g. from syntax highlighting
+    highlights: Vec<(usize, usize, Style)>,
+    /// State of the vi editing mode, `None` if disabled
+    vi: Option<ViState>,
+    /// State of the reverse incremental history search, `None` if not searching
+    search: Option<HistorySearch>,
+    fuzzy_history_search: bool,
+    /// Prompt rendered instead of the usual one, e.g. while typing an output search
+    prompt_override: Option<String>,
+    /// Frame of the spinner shown in front of the prompt and its style, see `TuiClap::start_spinner`
+    spinner: Option<(String, Style)>,
+    /// Byte offset of the first visible grapheme cluster of the cursor line when it is wider than the widget.
+    /// Kept in the state since `CommandInput` is recreated on every render.
+    view_offset: usize,
 }
 
-#[derive(Default, Clone)]
-pub struct CommandOutput {}
+/// Maximum number of entries kept in the kill ring of `CommandInputState`
+const KILL_RING_SIZE: usize = 16;
+
+#[derive(Clone)]
+pub struct CommandOutput {
+    debug_style: Style,
+    info_style: Style,
+    warn_style: Style,
+    error_style: Style,
+    match_style: Style,
+    current_match_style: Style,
+    wrap_mode: WrapMode,
+    continuation_prefix: String,
+    timestamps: bool,
+    timestamp_style: Style,
+    selection_style: Style,
+    new_lines_style: Style,
+    line_numbers: Option<LineNumbers>,
+    line_number_style: Style,
+    hyperlinks: bool,
+    link_style: Style,
+    echo_style: Style,
+    table_separators: bool,
+    table_header_style: Style,
+    table_separator_style: Style,
+    #[cfg(feature = "json")]
+    json_styles: JsonStyles,
+    #[cfg(feature = "json")]
+    json_fold: Option<usize>,
+    markdown_styles: MarkdownStyles,
+    help_styles: HelpStyles,
+    scrollbar: bool,
+    scrollbar_style: Style,
+    section_style: Style,
+}
 
 #[derive(Default)]
 pub struct CommandOutputState {
-    history: Vec<String>,
+    history: VecDeque<OutputLine>,
+    /// Maximum number of lines kept in `history`
+    scrollback_limit: Option<usize>,
+    /// Number of rows the view is scrolled up from the newest output
+    scroll: usize,
+    /// Number of columns the view is scrolled to the right in `WrapMode::None`
+    horizontal_scroll: usize,
+    /// Number of rows shown by the last render
+    height: usize,
+    /// Number of columns available to the text of a line in the last render, 0 before the first render
+    width: usize,
+    /// Width, wrap mode and continuation prefix the cached row counts of the lines are computed for,
+    /// see `OutputLine::row_count`
+    wrapped_as: Option<(usize, WrapMode, String)>,
+    /// Line to scroll to by the next render, which knows how the lines are wrapped
+    scroll_to_line: Option<usize>,
+    search: Option<OutputSearch>,
+    /// Hides the lines for which it returns `false`
+    filter: Option<OutputFilter>,
+    /// Lines below the level are hidden
+    min_level: Option<Level>,
+    selection: Option<OutputSelection>,
+    /// Line to scroll into the view by the next render
+    reveal_line: Option<usize>,
+    /// Index of the line shown in each row by the last render, `None` for blank rows
+    rendered_rows: Vec<Option<usize>>,
+    /// Area of the last render, to locate mouse events
+    area: Rect,
+    /// Number of commands started, see `start_command`
+    sections_started: usize,
+    /// Sections of which only the first line is shown
+    folded: HashSet<usize>,
+    /// Number of lines when the screen was cleared, the lines before are scrolled out of the view
+    cleared_at: Option<usize>,
+    /// Number of blank rows below the newest line in the last render, see `cleared_at`
+    padding: usize,
+    /// Scrolls to the newest output whenever a line is written, instead of keeping the view while scrolled up
+    follow: bool,
+    /// Number of shown lines written while scrolled up since the last render, which scrolls up by their rows
+    unrendered_lines: usize,
+    /// Number of shown lines written while scrolled up since the view was at the newest output
+    new_lines: usize,
+    /// Number of lines ever written, see `LineNumbers::Absolute`
+    lines_written: usize,
+    /// Number of lines written since the current command started, see `LineNumbers::PerCommand`
+    command_lines: usize,
+    /// Number of writes returning an `OutputHandle`
+    blocks_written: usize,
+    /// Hyperlinks rendered by the last render, if rendered as OSC 8 hyperlinks
+    rendered_links: Vec<RenderedLink>,
+    /// The text of the lines written while capturing, innermost capture last, see `CommandOutputState::start_capture`
+    captures: Vec<Vec<String>>,
 }
 
 impl CommandInputState {
+    /// Inserts `c` at the cursor position, replacing the selection, and moves the cursor behind it
     pub fn add_char(&mut self, c: char) {
-        self.content.push(c);
+        self.delete_selection();
+        self.content.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
     }
 
+    /// Deletes the selection or else the grapheme cluster in front of the cursor
     pub fn del_char(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let start = self.prev_grapheme_boundary();
+        self.content.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Deletes the selection or else the grapheme cluster behind the cursor
+    pub fn del_next_char(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let end = self.next_grapheme_boundary();
+        self.content.drain(self.cursor..end);
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.selection_anchor = None;
+        self.cursor = self.prev_grapheme_boundary();
+    }
+
+    /// Moves the cursor one grapheme cluster to the right, or accepts the suggestion at the end of the content
+    pub fn move_cursor_right(&mut self) {
+        if self.accept_suggestion() {
+            return;
+        }
+        self.selection_anchor = None;
+        self.cursor = self.next_grapheme_boundary();
+    }
+
+    /// Extends the selection by one grapheme cluster to the left
+    pub fn select_left(&mut self) {
+        self.extend_selection(Self::move_cursor_left);
+    }
+
+    /// Extends the selection by one grapheme cluster to the right
+    pub fn select_right(&mut self) {
+        self.extend_selection(Self::move_cursor_right);
+    }
+
+    /// Extends the selection to the start of the current or previous word
+    pub fn select_word_left(&mut self) {
+        self.extend_selection(Self::move_cursor_word_left);
+    }
+
+    /// Extends the selection to the end of the current or next word
+    pub fn select_word_right(&mut self) {
+        self.extend_selection(Self::move_cursor_word_right);
+    }
+
+    /// Extends the selection to the start of the current line
+    pub fn select_to_start(&mut self) {
+        self.extend_selection(Self::move_cursor_to_start);
+    }
+
+    /// Extends the selection to the end of the current line
+    pub fn select_to_end(&mut self) {
+        self.extend_selection(Self::move_cursor_to_end);
+    }
+
+    /// Selects the whole content
+    pub fn select_all(&mut self) {
+        self.selection_anchor = Some(0);
+        self.cursor = self.content.len();
+    }
+
+    /// The selected byte range of the content, if any
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.selection_anchor?;
+        if anchor == self.cursor {
+            return None;
+        }
+        Some((min(anchor, self.cursor), max(anchor, self.cursor)))
+    }
+
+    /// The selected text, if any
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection()
+            .map(|(start, end)| &self.content[start..end])
+    }
+
+    /// Deletes the selected text. Returns `true` if there was a selection.
+    pub fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        self.selection_anchor = None;
+        match selection {
+            Some((start, end)) => {
+                self.content.drain(start..end);
+                self.cursor = start;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor with `movement` while keeping the selection anchored at its previous position
+    fn extend_selection(&mut self, movement: fn(&mut Self)) {
+        let anchor = self.selection_anchor.unwrap_or(self.cursor);
+        movement(self);
+        self.selection_anchor = Some(anchor);
+    }
+
+    fn prev_grapheme_boundary(&self) -> usize {
+        self.content[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_grapheme_boundary(&self) -> usize {
+        self.content[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |g| self.cursor + g.len())
+    }
+
+    /// Moves the cursor to the start of the current or previous word
+    pub fn move_cursor_word_left(&mut self) {
+        self.selection_anchor = None;
+        self.cursor = self.prev_word_boundary();
+    }
+
+    /// Moves the cursor to the end of the current or next word
+    pub fn move_cursor_word_right(&mut self) {
+        self.selection_anchor = None;
+        self.cursor = self.next_word_boundary();
+    }
+
+    /// Kills from the start of the current or previous word up to the cursor
+    pub fn del_prev_word(&mut self) {
+        let start = self.prev_word_boundary();
+        self.kill(start, self.cursor);
+    }
+
+    /// Kills from the cursor up to the end of the current or next word
+    pub fn del_next_word(&mut self) {
+        let end = self.next_word_boundary();
+        self.kill(self.cursor, end);
+    }
+
+    /// Kills from the start of the current line up to the cursor
+    pub fn kill_to_start(&mut self) {
+        self.kill(self.line_start(), self.cursor);
+    }
+
+    /// Kills from the cursor up to the end of the current line
+    pub fn kill_to_end(&mut self) {
+        self.kill(self.cursor, self.line_end());
+    }
+
+    /// Inserts the most recently killed text at the cursor
+    pub fn yank(&mut self) {
+        self.yank_from_ring(0);
+    }
+
+    /// Replaces the text inserted by the previous `yank` or `yank_pop` with the next older kill ring entry.
+    /// Does nothing if the cursor was moved or the content edited since the last yank.
+    pub fn yank_pop(&mut self) {
+        if let Some((start, end, index)) = self.last_yank {
+            if end != self.cursor || self.content.get(start..end) != Some(&self.kill_ring[index]) {
+                self.last_yank = None;
+                return;
+            }
+            self.content.drain(start..end);
+            self.cursor = start;
+            self.yank_from_ring((index + 1) % self.kill_ring.len());
+        }
+    }
+
+    fn yank_from_ring(&mut self, index: usize) {
+        self.delete_selection();
+        if let Some(text) = self.kill_ring.get(index) {
+            let start = self.cursor;
+            self.content.insert_str(start, text);
+            self.cursor += text.len();
+            self.last_yank = Some((start, self.cursor, index));
+        }
+    }
+
+    /// Removes the text between the byte offsets `start` and `end` and pushes it onto the kill ring
+    fn kill(&mut self, start: usize, end: usize) {
+        if start == end {
+            return;
+        }
+        self.selection_anchor = None;
+        let killed: String = self.content.drain(start..end).collect();
+        self.cursor = start;
+        self.kill_ring.push_front(killed);
+        self.kill_ring.truncate(KILL_RING_SIZE);
+    }
+
+    fn prev_word_boundary(&self) -> usize {
+        let before = self.content[..self.cursor].trim_end();
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    fn next_word_boundary(&self) -> usize {
+        let after = &self.content[self.cursor..];
+        let word_start = after.len() - after.trim_start().len();
+        after[word_start..]
+            .find(char::is_whitespace)
+            .map_or(self.content.len(), |i| self.cursor + word_start + i)
+    }
+
+    /// Moves the cursor to the start of the current line
+    pub fn move_cursor_to_start(&mut self) {
+        self.selection_anchor = None;
+        self.cursor = self.line_start();
+    }
+
+    /// Moves the cursor to the end of the current line, or accepts the suggestion at the end of the content
+    pub fn move_cursor_to_end(&mut self) {
+        if self.accept_suggestion() {
+            return;
+        }
+        self.selection_anchor = None;
+        self.cursor = self.line_end();
+    }
+
+    /// Inserts `text` at the cursor position as a single edit, e.g. when pasting, replacing the selection.
+    /// Line breaks in `text` start continuation lines.
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.content.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Inserts a line break at the cursor, starting a continuation line
+    pub fn new_line(&mut self) {
+        self.add_char('\n');
+    }
+
+    /// Starts a continuation line if the content ends with a backslash.
+    /// The backslash is replaced by a line break, which is treated like a space when the command is parsed.
+    /// Returns `true` if a continuation line was started.
+    pub fn continue_line(&mut self) -> bool {
+        if !self.content.ends_with('\\') {
+            return false;
+        }
+        self.selection_anchor = None;
         self.content.pop();
+        self.content
+            .truncate(self.content.trim_end_matches(' ').len());
+        self.content.push('\n');
+        self.cursor = self.content.len();
+        true
+    }
+
+    /// Number of lines of the content, which is the number of rows the `CommandInput` widget needs
+    pub fn line_count(&self) -> usize {
+        self.content.split('\n').count()
+    }
+
+    fn line_start(&self) -> usize {
+        self.content[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self) -> usize {
+        self.content[self.cursor..]
+            .find('\n')
+            .map_or(self.content.len(), |i| self.cursor + i)
+    }
+
+    /// The cursor position as byte offset into the content
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The result of the last validation of the content, see `TuiClap::set_validator`
+    pub fn validation(&self) -> &Validation {
+        &self.validation
+    }
+
+    /// The screen position `(x, y)` of the cursor, as computed by the last render of the `CommandInput` widget.
+    /// Returns `None` if the widget has not been rendered yet or the cursor lies outside the rendered area.
+    pub fn cursor_position(&self) -> Option<(u16, u16)> {
+        self.cursor_position
     }
 
     pub fn reset(&mut self) {
         self.content.drain(..);
+        self.cursor = 0;
+        self.selection_anchor = None;
+        self.view_offset = 0;
+        self.index_of_history = None;
+        self.draft.clear();
+    }
+
+    /// Adjusts the view offset so that the cursor is visible within `width` columns of the current line,
+    /// using `measure` to compute the display width of text
+    fn scroll_to_cursor(&mut self, width: usize, measure: impl Fn(&str) -> usize) {
+        let line_start = self.line_start();
+        if measure(&self.content[line_start..self.line_end()]) < width {
+            self.view_offset = line_start;
+            return;
+        }
+        if self.view_offset < line_start
+            || self.view_offset > self.cursor
+            || !self.content.is_char_boundary(self.view_offset)
+        {
+            self.view_offset = line_start;
+        }
+        // keep one column free for the cursor behind the last grapheme
+        for (i, _) in self.content[self.view_offset..self.cursor].grapheme_indices(true) {
+            if measure(&self.content[self.view_offset + i..self.cursor]) < width {
+                self.view_offset += i;
+                return;
+            }
+        }
+        self.view_offset = self.cursor;
     }
 
     pub fn enter(&mut self) -> String {
         let command = self.content.clone();
-        self.history.push(command.clone());
+        self.last_recorded = !self.history_disabled && !self.is_ignored_in_history(&command);
+        if self.last_recorded {
+            self.history.push(HistoryEntry::new(command.clone()));
+            self.trim_history();
+        }
         self.reset();
 
         command
     }
 
-    pub fn back_in_history(&mut self) {
-        if self.history.is_empty() {
-            return;
+    /// Sets whether entered commands are recorded in the history, e.g. to keep secrets out of it
+    pub fn record_history(&mut self, record: bool) {
+        self.history_disabled = !record;
+    }
+
+    /// Sets whether the most recent history entry starting with the content is suggested while typing.
+    /// The suggestion is rendered behind the cursor and accepted by moving the cursor right or to the end.
+    pub fn autosuggest(&mut self, enabled: bool) {
+        self.autosuggest = enabled;
+    }
+
+    /// The rest of the most recent history entry that starts with the content,
+    /// if autosuggestions are enabled and the cursor is at the end of the content
+    pub fn suggestion(&self) -> Option<&str> {
+        if !self.autosuggest
+            || self.search.is_some()
+            || self.content.is_empty()
+            || self.cursor != self.content.len()
+        {
+            return None;
         }
+        self.history
+            .iter()
+            .rev()
+            .map(|entry| &entry.command)
+            .find(|command| {
+                command.len() > self.content.len() && command.starts_with(&self.content)
+            })
+            .map(|command| &command[self.content.len()..])
+    }
 
-        self.index_of_history = min(self.index_of_history + 1, self.history.len() - 1);
+    /// Appends the current suggestion to the content. Returns `true` if there was a suggestion.
+    pub fn accept_suggestion(&mut self) -> bool {
+        match self.suggestion().map(str::to_string) {
+            Some(suggestion) => {
+                self.selection_anchor = None;
+                self.content.push_str(&suggestion);
+                self.cursor = self.content.len();
+                true
+            }
+            None => false,
+        }
+    }
 
-        self.content = self.history[self.index_of_history].clone();
+    /// The entered commands, oldest first
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
     }
 
-    pub fn forward_in_history(&mut self) {
-        if self.history.is_empty() {
+    /// Shows the history entry at `index` as content, keeping the current content as draft like `back_in_history`
+    pub fn recall_history(&mut self, index: usize) {
+        if index >= self.history.len() {
             return;
         }
+        if self.index_of_history.is_none() {
+            self.draft = self.content.clone();
+        }
+        self.index_of_history = Some(index);
+        self.show_history_entry(self.history[index].command.clone());
+    }
 
-        self.index_of_history = max(self.index_of_history - 1, 0);
+    /// Shows the next older history entry as content. The content typed before navigating into the history is kept as draft.
+    pub fn back_in_history(&mut self) {
+        let index = match self.index_of_history {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.content.clone();
+                self.history.len() - 1
+            }
+            Some(0) => return,
+            Some(index) => index - 1,
+        };
+        self.index_of_history = Some(index);
+        self.show_history_entry(self.history[index].command.clone());
+    }
 
-        self.content = self.history[self.index_of_history].clone();
+    /// Shows the next newer history entry as content or the draft when navigating past the newest entry
+    pub fn forward_in_history(&mut self) {
+        let index = match self.index_of_history {
+            None => return,
+            Some(index) => index + 1,
+        };
+        if index < self.history.len() {
+            self.index_of_history = Some(index);
+            self.show_history_entry(self.history[index].command.clone());
+        } else {
+            self.index_of_history = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.show_history_entry(draft);
+        }
+    }
+
+    fn show_history_entry(&mut self, entry: String) {
+        self.content = entry;
+        self.cursor = self.content.len();
+        self.selection_anchor = None;
+        self.view_offset = 0;
     }
 }
 
@@ -88,19 +833,194 @@ impl CommandInput {
     pub fn prompt(&mut self, prompt: &str) {
         self.prompt = prompt.to_string();
     }
+
+    /// Sets how the content is displayed, e.g. `EchoMode::Masked('*')` for password input
+    pub fn echo_mode(&mut self, echo_mode: EchoMode) {
+        self.echo_mode = echo_mode;
+    }
+
+    /// Sets the style of the content while it is invalid, red and underlined by default
+    pub fn invalid_style(&mut self, style: Style) {
+        self.invalid_style = style;
+    }
+
+    /// Sets the style of the autosuggestion behind the cursor, dark gray by default
+    pub fn suggestion_style(&mut self, style: Style) {
+        self.suggestion_style = style;
+    }
+
+    /// Sets a hint that is shown while the content is empty, e.g. "type `help` for commands"
+    pub fn placeholder(&mut self, placeholder: &str) {
+        self.placeholder = placeholder.to_string();
+    }
+
+    /// Sets the style of the placeholder, dark gray by default
+    pub fn placeholder_style(&mut self, style: Style) {
+        self.placeholder_style = style;
+    }
+
+    /// Sets whether the widget is enabled. A disabled widget is greyed out and shows no cursor.
+    pub fn enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Sets the style of prompt and content while the widget is disabled, dark gray by default
+    pub fn disabled_style(&mut self, style: Style) {
+        self.disabled_style = style;
+    }
+
+    /// Sets the prompt shown in front of continuation lines.
+    /// If empty, continuation lines are aligned with the content of the first line.
+    pub fn continuation_prompt(&mut self, prompt: &str) {
+        self.continuation_prompt = prompt.to_string();
+    }
+
+    fn continuation_prompt_or_indent(&self) -> String {
+        if self.continuation_prompt.is_empty() {
+            " ".repeat(self.prompt.width())
+        } else {
+            self.continuation_prompt.clone()
+        }
+    }
 }
 
 impl StatefulWidget for CommandInput {
     type State = CommandInputState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        buf.set_string(area.left(), area.top(), &self.prompt, Style::default());
-        buf.set_string(
-            area.left() + self.prompt.len() as u16,
-            area.top(),
-            &state.content,
-            Style::default(),
-        );
+        let continuation_prompt = self.continuation_prompt_or_indent();
+
+        let echo_mode = self.echo_mode;
+        let measure = |text: &str| echo_mode.width(text);
+        let (prompt_style, content_style) = match (self.enabled, &state.validation) {
+            (false, _) => (self.disabled_style, self.disabled_style),
+            (true, Validation::Valid) => (Style::default(), Style::default()),
+            (true, Validation::Invalid(_)) => (Style::default(), self.invalid_style),
+        };
+
+        // the search prompt grows with the query, two thirds of the width are left to it
+        let search_prompt = state
+            .fitted_history_search_prompt(area.width as usize * 2 / 3)
+            .or_else(|| state.prompt_override.clone());
+        let mut lines = vec![];
+        let mut line_start = 0;
+        for line in state.content.split('\n') {
+            lines.push((line_start, line_start + line.len()));
+            line_start += line.len() + 1;
+        }
+        let cursor_row = state.content[..state.cursor].matches('\n').count();
+        let first_row = (cursor_row + 1).saturating_sub(area.height as usize);
+
+        state.cursor_position = None;
+        for (row, (line_start, line_end)) in lines
+            .into_iter()
+            .enumerate()
+            .skip(first_row)
+            .take(area.height as usize)
+        {
+            let y = area.top() + (row - first_row) as u16;
+            let prompt = if row == 0 {
+                search_prompt.as_ref().unwrap_or(&self.prompt)
+            } else {
+                &continuation_prompt
+            };
+            let (prompt_x, _) = match (&state.spinner, row) {
+                (Some((frame, style)), 0) if area.width > 0 => buf.set_stringn(
+                    area.left(),
+                    y,
+                    format!("{} ", frame),
+                    area.width as usize,
+                    *style,
+                ),
+                _ => (area.left(), y),
+            };
+            if prompt_x >= area.right() {
+                continue;
+            }
+            let (content_x, _) = buf.set_stringn(
+                prompt_x,
+                y,
+                prompt,
+                area.right().saturating_sub(prompt_x) as usize,
+                prompt_style,
+            );
+            // the prompt fills the row, no column is left for the content and the cursor
+            if content_x >= area.right() {
+                continue;
+            }
+            let content_width = (area.right() - content_x) as usize;
+
+            let visible_start = if row == cursor_row {
+                state.scroll_to_cursor(content_width, measure);
+                state.view_offset
+            } else {
+                line_start
+            };
+            if state.content.is_empty() {
+                buf.set_stringn(
+                    content_x,
+                    y,
+                    &self.placeholder,
+                    content_width,
+                    self.placeholder_style,
+                );
+            }
+            buf.set_stringn(
+                content_x,
+                y,
+                echo_mode.display(&state.content[visible_start..line_end]),
+                content_width,
+                content_style,
+            );
+
+            // applies `style` to the cells showing the byte range `start..end` of the content
+            let content = &state.content;
+            let mut style_range = |start: usize, end: usize, style: Style| {
+                let start = max(start, visible_start);
+                let end = min(end, line_end);
+                if start >= end {
+                    return;
+                }
+                let x = content_x as usize + measure(&content[visible_start..start]);
+                let x_end = min(x + measure(&content[start..end]), area.right() as usize);
+                if x < x_end {
+                    buf.set_style(Rect::new(x as u16, y, (x_end - x) as u16, 1), style);
+                }
+            };
+            if !self.enabled {
+                continue;
+            }
+            if echo_mode == EchoMode::Normal {
+                for &(start, end, style) in &state.highlights {
+                    style_range(start, end, style);
+                }
+            }
+            if let Some((start, end)) = state.selection() {
+                style_range(
+                    start,
+                    end,
+                    Style::default().add_modifier(Modifier::REVERSED),
+                );
+            }
+
+            if row == cursor_row {
+                let cursor_x =
+                    content_x as usize + measure(&state.content[visible_start..state.cursor]);
+                if cursor_x < area.right() as usize {
+                    state.cursor_position = Some((cursor_x as u16, y));
+                    if let (EchoMode::Normal, Some(suggestion)) = (echo_mode, state.suggestion()) {
+                        let suggestion = suggestion.split('\n').next().unwrap_or_default();
+                        buf.set_stringn(
+                            cursor_x as u16,
+                            y,
+                            suggestion,
+                            area.right() as usize - cursor_x,
+                            self.suggestion_style,
+                        );
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -114,34 +1034,232 @@ impl StatefulWidget for CommandOutput {
     type State = CommandOutputState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        // nothing fits into an empty area, e.g. while the terminal is resized to nothing
+        if area.area() == 0 {
+            state.invalidate_layout();
+            return;
+        }
         let max_lines = area.height - 1;
         let max_chars_per_line = area.width - 1;
 
-        let mut lines_to_render: Vec<&str> = vec![];
+        // line numbers are as wide as the number of lines ever written, which no per-command number exceeds
+        let number_width = match self.line_numbers {
+            Some(_) => state.lines_written.to_string().len() + 1,
+            None => 0,
+        };
+        let timestamp_width = if self.timestamps { TIMESTAMP_WIDTH } else { 0 };
+        let gutter_width = number_width + timestamp_width;
+        let width = (max_chars_per_line as usize).saturating_sub(gutter_width);
+        let prefix = self.continuation_prefix.as_str();
+        let row_count = |line: &Spans| wrap(line, width, self.wrap_mode, prefix).len();
+        // the rows of the lines in the history, cached since the scrollbar needs those of all lines
+        state.keep_row_counts(width, self.wrap_mode, prefix);
+        let rows_of = |line: &OutputLine| line.row_count(width, self.wrap_mode, prefix);
 
-        let history_to_show = state.history.iter().rev().take(max_lines as usize).rev();
-        let mut y = 0;
-        for line in history_to_show {
-            if line.len() > max_chars_per_line as usize {
-                let mut rest_of_line = line.as_str();
-                loop {
-                    if rest_of_line.len() > max_chars_per_line as usize {
-                        let split_line = rest_of_line.split_at(max_chars_per_line as usize);
-                        lines_to_render.push(split_line.0);
-                        rest_of_line = split_line.1;
-                    } else {
-                        lines_to_render.push(rest_of_line);
-                        break;
-                    }
+        // blank rows below the newest line, pushing the lines written before the screen was cleared
+        // out of the view until the lines written since fill it
+        let mut padding = 0;
+        if let Some(cleared_at) = state.cleared_at {
+            let mut rows = 0;
+            for index in cleared_at..state.history.len() {
+                if rows >= max_lines as usize {
+                    break;
                 }
-            } else {
-                lines_to_render.push(line);
+                if state.is_shown(index) {
+                    rows += rows_of(&state.history[index]);
+                }
+            }
+            padding = (max_lines as usize).saturating_sub(rows);
+            if padding == 0 {
+                state.cleared_at = None;
+            }
+        }
+
+        // keeps the view in place if lines were written while scrolled up, minus the blank rows they filled
+        let unrendered_lines = mem::take(&mut state.unrendered_lines);
+        let new_rows: usize = (0..state.history.len())
+            .rev()
+            .filter(|&index| state.is_shown(index))
+            .take(unrendered_lines)
+            .map(|index| rows_of(&state.history[index]))
+            .sum();
+        state.scroll =
+            (state.scroll + new_rows).saturating_sub(state.padding.saturating_sub(padding));
+        state.padding = padding;
+
+        // the rows of the lines change with the width, the line at the top of the view stays in place
+        if state.width != 0
+            && state.width != width
+            && state.scroll > 0
+            && state.scroll_to_line.is_none()
+        {
+            state.scroll_to_line = state.rendered_rows.iter().find_map(|&index| index);
+        }
+        if let Some(target) = state.scroll_to_line.take() {
+            // scrolls the line to the top of the view
+            let rows: usize = (target..state.history.len())
+                .filter(|&index| state.is_shown(index))
+                .map(|index| rows_of(&state.history[index]))
+                .sum();
+            state.scroll = (rows + padding).saturating_sub(max_lines as usize);
+        }
+        if let Some(target) = state.reveal_line.take() {
+            let below: usize = (target + 1..state.history.len())
+                .filter(|&index| state.is_shown(index))
+                .map(|index| rows_of(&state.history[index]))
+                .sum::<usize>()
+                + padding;
+            let rows = rows_of(&state.history[target]);
+            if below < state.scroll {
+                state.scroll = below;
+            } else if below + rows > state.scroll + max_lines as usize {
+                state.scroll = (below + rows).saturating_sub(max_lines as usize);
             }
         }
+        state.height = max_lines as usize;
+        state.width = width;
 
-        for line in lines_to_render.iter().rev().take(max_lines as usize).rev() {
-            buf.set_string(area.left(), area.top() + y, line, Style::default());
-            y += 1;
+        // only the lines up to the top of the scrolled view are wrapped, starting with the newest one
+        let rows_needed = max_lines as usize + state.scroll;
+        let mut lines_shown = vec![];
+        let mut rows = padding;
+        for index in (0..state.history.len()).rev() {
+            if !state.is_shown(index) {
+                continue;
+            }
+            if rows >= rows_needed {
+                break;
+            }
+            let line = state.styled_line(index, &self);
+            rows += row_count(&line);
+            lines_shown.push((index, line));
+        }
+        // rows of the older lines which are not wrapped to render the view
+        let rows_not_wrapped: usize = match (self.scrollbar, lines_shown.last()) {
+            (true, Some((oldest, _))) => (0..*oldest)
+                .filter(|&index| state.is_shown(index))
+                .map(|index| rows_of(&state.history[index]))
+                .sum(),
+            _ => 0,
+        };
+        let horizontal_scroll = match self.wrap_mode {
+            WrapMode::None => {
+                // scrolls no further than the widest line of the view fits into it
+                let widest = lines_shown
+                    .iter()
+                    .map(|(_, line)| line.width())
+                    .max()
+                    .unwrap_or(0);
+                state.horizontal_scroll.min(widest.saturating_sub(width))
+            }
+            _ => state.horizontal_scroll,
+        };
+        let mut lines_to_render: Vec<Spans> = vec![];
+        let mut row_lines = vec![];
+        // byte range of the text of the line in each row and the column it starts at, to locate hyperlinks
+        let mut row_texts = vec![];
+        for (index, line) in lines_shown.iter().rev() {
+            let (mut offset, rows) = match self.wrap_mode {
+                WrapMode::None => {
+                    let (offset, spans) = crop(line, horizontal_scroll, width);
+                    (offset, vec![spans])
+                }
+                mode => (0, wrap(line, width, mode, prefix)),
+            };
+            for (row, mut spans) in rows.into_iter().enumerate() {
+                let (prefix_len, prefix_width) = match row {
+                    0 => (0, 0),
+                    _ => (prefix.len(), prefix.width()),
+                };
+                let row_len = spans.0.iter().map(|span| span.content.len()).sum::<usize>();
+                let row_end = offset + row_len - prefix_len;
+                row_texts.push((offset..row_end, (gutter_width + prefix_width) as u16));
+                offset = row_end;
+                if self.timestamps {
+                    // the timestamp is only shown on the first row of a wrapped line
+                    let gutter = match row {
+                        0 => format_timestamp(state.history[*index].timestamp),
+                        _ => " ".repeat(TIMESTAMP_WIDTH),
+                    };
+                    spans
+                        .0
+                        .insert(0, Span::styled(gutter, self.timestamp_style));
+                }
+                if let Some(numbers) = self.line_numbers {
+                    let gutter = match row {
+                        0 => format!(
+                            "{:>width$} ",
+                            state.history[*index].number(numbers),
+                            width = number_width - 1
+                        ),
+                        _ => " ".repeat(number_width),
+                    };
+                    spans
+                        .0
+                        .insert(0, Span::styled(gutter, self.line_number_style));
+                }
+                lines_to_render.push(spans);
+                row_lines.push(Some(*index));
+            }
+        }
+        lines_to_render.extend(iter::repeat_n(Spans::default(), padding));
+        row_lines.extend(iter::repeat_n(None, padding));
+        row_texts.extend(iter::repeat_n((0..0, 0), padding));
+
+        let scroll = state
+            .scroll
+            .min(lines_to_render.len().saturating_sub(max_lines as usize));
+        let end = lines_to_render.len() - scroll;
+        let total_rows = lines_to_render.len() + rows_not_wrapped;
+        let start = end.saturating_sub(max_lines as usize);
+        for (y, line) in lines_to_render[start..end].iter().enumerate() {
+            buf.set_spans(area.left(), area.top() + y as u16, line, area.width);
+        }
+        state.scroll = scroll;
+        state.horizontal_scroll = horizontal_scroll;
+        state.rendered_rows = row_lines[start..end].to_vec();
+        state.area = area;
+        state.rendered_links.clear();
+        if self.hyperlinks {
+            let rows = row_lines[start..end].iter().zip(&row_texts[start..end]);
+            for (y, (index, (text, x))) in rows.enumerate() {
+                if let Some(index) = index {
+                    state.rendered_links.extend(rendered_links(
+                        &state.history[*index],
+                        text.clone(),
+                        area.left() + x,
+                        area.top() + y as u16,
+                        area.right(),
+                        buf,
+                    ));
+                }
+            }
+        }
+
+        if self.scrollbar {
+            self.render_scrollbar(area, buf, start + rows_not_wrapped, end - start, total_rows);
+        }
+
+        // only blank rows are below the view
+        if scroll <= padding {
+            state.new_lines = 0;
+        } else if state.new_lines > 0 {
+            let indicator = match state.new_lines {
+                1 => " 1 new line ↓ ".to_string(),
+                n => format!(" {} new lines ↓ ", n),
+            };
+            let right = match self.scrollbar {
+                true => area.right().saturating_sub(1),
+                false => area.right(),
+            };
+            let x = right.saturating_sub(indicator.width() as u16);
+            buf.set_stringn(
+                x.max(area.left()),
+                area.bottom() - 1,
+                indicator,
+                area.width as usize,
+                self.new_lines_style,
+            );
         }
     }
 }
@@ -155,7 +1273,14 @@ impl Widget for CommandOutput {
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
     pub exit_key: KeyCode,
+    /// Interval of the `TerminalEvent::Tick` events, 250ms by default
     pub tick_rate: Duration,
+    /// Enables bracketed paste so that pasted text arrives as a single `Event::Paste`
+    pub bracketed_paste: bool,
+    /// Enables mouse capture so that `Event::Mouse` arrives, to scroll the output with the wheel, select its lines and
+    /// focus the input or output by clicking them, see `TuiClap::handle_mouse_event`. Disabled by default, since
+    /// capturing the mouse keeps the terminal from selecting text.
+    pub mouse_capture: bool,
 }
 
 impl Default for Config {
@@ -163,6 +1288,8 @@ impl Default for Config {
         Config {
             exit_key: KeyCode::Char('q'),
             tick_rate: Duration::from_millis(250),
+            bracketed_paste: true,
+            mouse_capture: false,
         }
     }
 }
@@ -176,41 +1303,63 @@ impl Default for Events {
 impl Events {
     /// Creates an `Events` instance from `Config` and starts a thread to listen on `crossterm` input events
     pub fn from_config(config: Config) -> Events {
+        if config.bracketed_paste {
+            if let Err(err) = execute!(stdout(), EnableBracketedPaste) {
+                eprintln!("{}", err);
+            }
+        }
+        if config.mouse_capture {
+            if let Err(err) = execute!(stdout(), EnableMouseCapture) {
+                eprintln!("{}", err);
+            }
+        }
+
         let (tx, rx) = mpsc::channel();
+        let waker = Waker::new(tx.clone());
         let ignore_exit_key = Arc::new(AtomicBool::new(false));
         {
             let ignore_exit_key = ignore_exit_key.clone();
-            thread::spawn(move || loop {
-                if let Ok(b) = poll(config.tick_rate) {
-                    if !b {
-                        continue;
-                    }
-                    let read = read();
-                    if let Ok(event) = read {
-                        if let Err(err) = tx.send(event) {
-                            eprintln!("{}", err);
-                            return;
-                        }
-                        if !ignore_exit_key.load(Ordering::Relaxed) {
-                            if let Event::Key(key) = event {
-                                if key.code == config.exit_key {
-                                    return;
-                                }
+            let waker = waker.clone();
+            thread::spawn(move || {
+                let mut last_tick = Instant::now();
+                loop {
+                    let timeout = config.tick_rate.saturating_sub(last_tick.elapsed());
+                    if let Ok(true) = poll(timeout) {
+                        if let Ok(event) = read() {
+                            let is_exit_key =
+                                matches!(&event, Event::Key(key) if key.code == config.exit_key);
+                            if let Err(err) = tx.send(TerminalEvent::Input(event)) {
+                                eprintln!("{}", err);
+                                break;
+                            }
+                            if is_exit_key && !ignore_exit_key.load(Ordering::Relaxed) {
+                                break;
                             }
                         }
                     }
+                    if last_tick.elapsed() >= config.tick_rate {
+                        // the receiver is gone once `Events` is dropped
+                        if tx.send(TerminalEvent::Tick).is_err() {
+                            break;
+                        }
+                        last_tick = Instant::now();
+                    }
                 }
+                waker.close();
             })
         };
         Events {
             rx,
+            waker,
             ignore_exit_key,
+            bracketed_paste: config.bracketed_paste,
+            mouse_capture: config.mouse_capture,
         }
     }
 
-    /// Checks if there was a new event to read from.
-    /// Returns `Some(Event)` if there was some, `None` if not and `Result::Err` if the connection was disconnected.
-    pub fn next(&self) -> Result<Option<Event>, mpsc::RecvError> {
+    /// Checks if there was a new event to read from, an input event or a tick.
+    /// Returns `Some(TerminalEvent)` if there was some, `None` if not and `Result::Err` if the connection was disconnected.
+    pub fn next(&self) -> Result<Option<TerminalEvent>, mpsc::RecvError> {
         match self.rx.try_recv() {
             Ok(event) => Ok(Some(event)),
             Err(err) => match err {
@@ -220,6 +1369,12 @@ impl Events {
         }
     }
 
+    /// Waits for the next event, which is a tick at the latest after `Config::tick_rate`, so that a loop drawing on
+    /// each event redraws periodically without spinning. Returns `Result::Err` if the connection was disconnected.
+    pub fn wait(&self) -> Result<TerminalEvent, mpsc::RecvError> {
+        self.rx.recv()
+    }
+
     pub fn disable_exit_key(&mut self) {
         self.ignore_exit_key.store(true, Ordering::Relaxed);
     }
@@ -229,90 +1384,768 @@ impl Events {
     }
 }
 
-/// A struct holding widgets for input and output for interaction with a `clap:App`
-pub struct TuiClap<'a> {
+impl Drop for Events {
+    fn drop(&mut self) {
+        if self.bracketed_paste {
+            let _ = execute!(stdout(), DisableBracketedPaste);
+        }
+        if self.mouse_capture {
+            let _ = execute!(stdout(), DisableMouseCapture);
+        }
+    }
+}
+
+/// A struct holding widgets for input and output for interaction with a `clap::Command`
+pub struct TuiClap {
     command_input_state: CommandInputState,
     command_output_state: CommandOutputState,
+    /// Output written to the error stream while it is rendered in a separate area
+    command_error_state: CommandOutputState,
+    /// Whether the error stream is rendered by `render_errors` instead of in-line with the output
+    separate_errors: bool,
     command_input_widget: CommandInput,
     command_output_widget: CommandOutput,
-    clap: App<'a>
+    clap: Command,
+    keymap: Keymap,
+    validator: Option<Validator>,
+    highlight_styles: Option<HighlightStyles>,
+    history_file: Option<PathBuf>,
+    history_popup: Option<HistoryPopup>,
+    /// Whether help texts are shown in the help popup instead of the output
+    help_popup_enabled: bool,
+    help_popup: Option<HelpPopup>,
+    completers: Vec<Box<dyn Completer>>,
+    fuzzy_completion: bool,
+    completion_mode: CompletionMode,
+    async_completion: AsyncCompletion,
+    /// Sends events to the connected `Events`, see `TuiClap::connect_events`
+    waker: Waker,
+    /// Completers for the values of arguments by their id
+    arg_completers: HashMap<String, Box<dyn Completer>>,
+    completion_menu_widget: CompletionMenu,
+    /// State of the completion menu, `None` if it is closed
+    completion_menu_state: Option<CompletionMenuState>,
+    signature_help_widget: SignatureHelp,
+    ansi_parsing: bool,
+    /// Whether `clear_output` keeps the lines to scroll back to
+    clear_keeps_scrollback: bool,
+    /// Whether `parse` writes the entered command to the output
+    echo_commands: bool,
+    /// Whether help and version texts are written as Markdown
+    markdown_help: bool,
+    /// Whether commands start with the binary name of the app
+    binary_name: bool,
+    spinner: Spinner,
+    /// The last command written to the output, to show the spinner behind it
+    echo: Option<Echo>,
+    /// Called with the payload of an activated line of the output
+    line_activated: Option<LineCallback>,
+    /// Handlers of subcommands by their path
+    handlers: HashMap<String, Handler>,
+    /// The last command with the mistyped word replaced by the one clap suggested
+    correction: Option<String>,
+    /// Built-in commands by their name
+    builtins: BTreeMap<String, Builtin>,
+    /// Whether the `exit` built-in command was entered
+    exit_requested: bool,
+    /// Expansions of aliases by their name
+    aliases: BTreeMap<String, String>,
+    /// Filters the output of commands can be piped through by their name
+    filters: BTreeMap<String, Filter>,
+    /// Values of variables by their name
+    variables: BTreeMap<String, String>,
+    /// Whether `source` runs the remaining lines of a file after a command failed
+    source_continue_on_error: bool,
+    /// The rc file whose commands are still to be run when the session starts
+    rc_file: Option<PathBuf>,
+    /// Cancelled by `ReplAction::Interrupt` to tell the running handlers
+    cancellation_token: CancellationToken,
+    jobs: Jobs,
+    /// Style of the output of failed commands
+    failure_style: Style,
+    /// Name of the active mode, see `TuiClap::add_mode`
+    mode: String,
+    /// The modes which are not active by name
+    modes: BTreeMap<String, Mode>,
+    /// Questions asked by handlers, see `TuiClap::ask`
+    questions: Questions,
+    /// Questions confirming destructive subcommands by their path
+    confirmations: HashMap<String, Confirmation>,
+    /// Whether missing required arguments are asked for, see `TuiClap::set_wizard`
+    wizard: bool,
+    diagnostics: Diagnostics,
+    hooks: Hooks,
+    timing: Timing,
+    scopes: Scopes,
+    resize: Resize,
+    focus: FocusState,
+    #[cfg(feature = "tokio")]
+    async_handlers: AsyncHandlers,
+    /// The pattern typed to search the output, `None` if not typing one
+    output_search_input: Option<OutputSearchInput>,
+    /// Content of the input widget the validation result was computed for
+    validated_content: Option<String>,
+    #[cfg(feature = "clipboard")]
+    clipboard: Clipboard,
 }
 
-impl TuiClap<'_> {
-    /// Creates a `TuiClap` struct from a `clap:App`
-    pub fn from_app<'a>(
-        app: App<'a>,
-    ) -> TuiClap {
+impl TuiClap {
+    /// Creates a `TuiClap` struct from a `clap::Command`
+    pub fn from_app(app: Command) -> TuiClap {
+        let mode = app.get_name().to_string();
         TuiClap {
             command_input_state: CommandInputState::default(),
             command_output_state: CommandOutputState::default(),
+            command_error_state: CommandOutputState::default(),
+            separate_errors: false,
             command_input_widget: Default::default(),
             command_output_widget: Default::default(),
             clap: app,
+            keymap: Keymap::default(),
+            validator: None,
+            highlight_styles: None,
+            history_file: None,
+            history_popup: None,
+            help_popup_enabled: false,
+            help_popup: None,
+            completers: vec![],
+            fuzzy_completion: false,
+            completion_mode: CompletionMode::default(),
+            async_completion: AsyncCompletion::default(),
+            waker: Waker::default(),
+            arg_completers: HashMap::new(),
+            completion_menu_widget: CompletionMenu::default(),
+            completion_menu_state: None,
+            signature_help_widget: SignatureHelp::default(),
+            ansi_parsing: false,
+            clear_keeps_scrollback: true,
+            echo_commands: false,
+            markdown_help: false,
+            binary_name: false,
+            spinner: Spinner::default(),
+            echo: None,
+            line_activated: None,
+            handlers: HashMap::new(),
+            correction: None,
+            builtins: default_builtins(),
+            exit_requested: false,
+            aliases: BTreeMap::new(),
+            filters: default_filters(),
+            variables: BTreeMap::new(),
+            source_continue_on_error: false,
+            rc_file: None,
+            cancellation_token: CancellationToken::new(),
+            jobs: Jobs::default(),
+            failure_style: Style::default().fg(Color::LightRed),
+            mode,
+            modes: BTreeMap::new(),
+            questions: Questions::default(),
+            confirmations: HashMap::new(),
+            wizard: false,
+            diagnostics: Diagnostics::default(),
+            hooks: Hooks::default(),
+            timing: Timing::default(),
+            scopes: Scopes::default(),
+            resize: Resize::default(),
+            focus: FocusState::default(),
+            #[cfg(feature = "tokio")]
+            async_handlers: AsyncHandlers::default(),
+            output_search_input: None,
+            validated_content: None,
+            #[cfg(feature = "clipboard")]
+            clipboard: Clipboard::default(),
         }
     }
 
-    /// Write `string` to the output widget
-    pub fn write_to_output(&mut self, string: String) {
-        let lines: Lines = string.lines();
-        for str in lines {
-            self.command_output_state.history.push(str.to_string());
+    /// Write `string` to the output widget. The returned handle allows to replace the written lines later,
+    /// see `TuiClap::update_output`.
+    pub fn write_to_output(&mut self, string: String) -> OutputHandle {
+        self.write_styled(string, Style::default())
+    }
+
+    /// Write `string` to the output widget in `style`, see `TuiClap::set_ansi_parsing` for escape sequences in `string`
+    pub fn write_styled(&mut self, string: String, style: Style) -> OutputHandle {
+        self.write_lines(string, style, Level::Info)
+    }
+
+    /// Write a line made of differently styled spans to the output widget
+    pub fn write_spans(&mut self, line: Spans<'static>) -> OutputHandle {
+        self.command_output_state
+            .push_block(vec![(line, vec![])], Level::Info)
+    }
+
+    /// Write `string` to the output widget with the severity `level`, which determines its style,
+    /// see `CommandOutput::level_style`, and whether it is shown, see `CommandOutputState::min_level`
+    pub fn write_line(&mut self, level: Level, string: String) -> OutputHandle {
+        self.write_lines(string, Style::default(), level)
+    }
+
+    /// Replaces the lines written with `handle` by the lines of `string` in place, e.g. to update a progress line
+    /// from `downloading… 45%` to `downloading… done` instead of writing a new line.
+    /// Returns `false` if the lines are no longer in the output, e.g. after clearing it.
+    pub fn update_output(&mut self, handle: OutputHandle, string: String) -> bool {
+        let lines = split_lines(string, Style::default(), self.ansi_parsing);
+        self.command_output_state.replace_block(handle, lines)
+    }
+
+    /// Write `string` to the error stream as `Level::Error`, red by default. The error stream is shown in-line with the
+    /// output unless it is rendered in a separate area, see `TuiClap::set_separate_errors`.
+    pub fn write_to_error(&mut self, string: String) {
+        let state = if self.separate_errors {
+            &mut self.command_error_state
+        } else {
+            &mut self.command_output_state
+        };
+        push_lines(
+            state,
+            string,
+            Style::default(),
+            Level::Error,
+            self.ansi_parsing,
+        );
+    }
+
+    /// Shorthand for `TuiClap::write_to_error`
+    pub fn write_error(&mut self, string: String) {
+        self.write_to_error(string);
+    }
+
+    /// Sets whether the error stream is rendered in a separate area by `TuiClap::render_errors`
+    /// instead of in-line with the output, disabled by default
+    pub fn set_separate_errors(&mut self, enabled: bool) {
+        self.separate_errors = enabled;
+    }
+
+    /// Access the state of the error stream rendered by `TuiClap::render_errors`, e.g. to scroll it
+    pub fn error_state(&mut self) -> &mut CommandOutputState {
+        &mut self.command_error_state
+    }
+
+    /// Clears the output widget. The lines are scrolled out of the view but kept to scroll back to,
+    /// unless disabled with `TuiClap::set_clear_keeps_scrollback`. Bound to `Ctrl+L` by default.
+    pub fn clear_output(&mut self) {
+        if self.clear_keeps_scrollback {
+            self.command_output_state.clear_screen();
+        } else {
+            self.command_output_state.clear();
         }
     }
 
+    /// Sets whether `clear_output` keeps the lines to scroll back to or removes them, enabled by default
+    pub fn set_clear_keeps_scrollback(&mut self, enabled: bool) {
+        self.clear_keeps_scrollback = enabled;
+    }
+
+    /// Sets whether commands start with the binary name of the app, like `myapp status --all`.
+    /// Disabled by default, so that `status --all` is parsed as a command of the app.
+    pub fn set_binary_name(&mut self, required: bool) {
+        self.binary_name = required;
+    }
+
+    /// Sets whether `TuiClap::parse` writes the entered command behind the prompt to the output, like a shell session,
+    /// styled with `CommandOutput::echo_style`. Disabled by default.
+    pub fn set_echo_commands(&mut self, enabled: bool) {
+        self.echo_commands = enabled;
+    }
+
+    fn write_lines(&mut self, string: String, style: Style, level: Level) -> OutputHandle {
+        push_lines(
+            &mut self.command_output_state,
+            string,
+            style,
+            level,
+            self.ansi_parsing,
+        )
+    }
+
     /// Access the input widget's state
     pub fn state(&mut self) -> &mut CommandInputState {
         self.command_input_state.borrow_mut()
     }
 
+    /// Loads the command history from the file at `path` and appends every entered command to it from now on.
+    /// A command is appended once the next command is entered or `TuiClap` is dropped.
+    pub fn set_history_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let path = path.into();
+        self.command_input_state.load_history(&path)?;
+        self.history_file = Some(path);
+        Ok(())
+    }
+
+    /// Access the keymap used by `handle_key_event`
+    pub fn keymap(&mut self) -> &mut Keymap {
+        self.keymap.borrow_mut()
+    }
+
+    /// Enables or disables the input widget, e.g. to lock it while a long-running command executes.
+    /// While disabled, the widget is greyed out and `handle_key_event` ignores all keys but the ones bound to
+    /// `ReplAction::Interrupt` and `ReplAction::Exit`, or `ReplAction::ExitOrDeleteForward` on an empty input.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.command_input_widget.enabled(enabled);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.command_input_widget.enabled
+    }
+
+    /// Handles `key` according to the vi editing mode, if enabled, and the keymap.
+    /// Edit actions, history navigation and completion are applied to the input widget, unbound chars are inserted.
+    /// Returns the `ReplAction` bound to `key` if the application has to act on it, e.g. to parse the input on `ReplAction::Submit`.
+    pub fn handle_key_event(&mut self, key: KeyEvent) -> Option<ReplAction> {
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+        // a key pressed while completing cancels the completion, since it may change the completed word
+        self.poll_async_completion();
+        self.cancel_completion();
+        if !self.is_enabled() {
+            return match self.keymap.action(&key) {
+                Some(Action::Repl(ReplAction::Interrupt)) => {
+                    self.interrupt();
+                    None
+                }
+                Some(Action::Repl(ReplAction::Exit)) => Some(ReplAction::Exit),
+                Some(Action::Repl(ReplAction::ExitOrDeleteForward))
+                    if self.command_input_state.content.is_empty() =>
+                {
+                    Some(ReplAction::Exit)
+                }
+                _ => None,
+            };
+        }
+        if self.handle_help_popup_key(key)
+            || self.handle_history_popup_key(key)
+            || self.handle_completion_menu_key(key)
+            || self.handle_output_search_key(key)
+            || self.handle_output_selection_key(key)
+            || self.handle_focus_key(key)
+        {
+            return None;
+        }
+        if self.command_input_state.is_searching_history() {
+            if let Some(Action::Repl(ReplAction::HistorySearch)) = self.keymap.action(&key) {
+                self.command_input_state.start_history_search();
+                return None;
+            }
+            if self.command_input_state.handle_history_search_key(key) {
+                return None;
+            }
+            self.command_input_state.accept_history_search();
+        }
+        if self.command_input_state.handle_vi_key(key) {
+            return None;
+        }
+
+        match self.keymap.action(&key) {
+            Some(Action::Edit(action)) => self.command_input_state.apply(action),
+            Some(Action::Repl(ReplAction::HistoryPrevious)) => {
+                self.command_input_state.back_in_history()
+            }
+            Some(Action::Repl(ReplAction::HistoryNext)) => {
+                self.command_input_state.forward_in_history()
+            }
+            Some(Action::Repl(ReplAction::HistorySearch)) => {
+                self.command_input_state.start_history_search()
+            }
+            Some(Action::Repl(ReplAction::HistoryPopup)) => self.open_history_popup(),
+            Some(Action::Repl(ReplAction::Complete)) => self.complete(),
+            Some(Action::Repl(ReplAction::ScrollPageUp)) => self.command_output_state.page_up(),
+            Some(Action::Repl(ReplAction::ScrollPageDown)) => self.command_output_state.page_down(),
+            Some(Action::Repl(ReplAction::SearchOutput)) => self.start_output_search(),
+            Some(Action::Repl(ReplAction::SelectOutput)) => {
+                self.command_output_state.start_selection()
+            }
+            Some(Action::Repl(ReplAction::ClearOutputFilter)) => {
+                self.command_output_state.clear_filter()
+            }
+            Some(Action::Repl(ReplAction::ClearOutput)) => self.clear_output(),
+            Some(Action::Repl(ReplAction::Interrupt)) => self.interrupt(),
+            Some(Action::Repl(ReplAction::ToggleSection)) => self.toggle_output_section(),
+            Some(Action::Repl(ReplAction::AcceptCorrection)) => {
+                self.accept_correction();
+            }
+            Some(Action::Repl(ReplAction::Preview)) => self.preview(),
+            Some(Action::Repl(ReplAction::ExitOrDeleteForward)) => {
+                if self.command_input_state.content.is_empty() {
+                    return Some(ReplAction::Exit);
+                }
+                self.command_input_state.del_next_char()
+            }
+            Some(Action::Repl(ReplAction::ToggleFollow)) => {
+                let following = self.command_output_state.is_following();
+                self.command_output_state.follow(!following)
+            }
+            Some(Action::Repl(ReplAction::SearchOutputNext)) => {
+                self.command_output_state.search_next();
+            }
+            Some(Action::Repl(ReplAction::SearchOutputPrevious)) => {
+                self.command_output_state.search_previous();
+            }
+            Some(Action::Repl(ReplAction::ScrollHalfPageUp)) => {
+                self.command_output_state.half_page_up()
+            }
+            Some(Action::Repl(ReplAction::ScrollHalfPageDown)) => {
+                self.command_output_state.half_page_down()
+            }
+            Some(Action::Repl(ReplAction::ScrollLeft)) => {
+                let step = self.command_output_state.horizontal_step();
+                self.command_output_state.scroll_left(step)
+            }
+            Some(Action::Repl(ReplAction::ScrollRight)) => {
+                let step = self.command_output_state.horizontal_step();
+                self.command_output_state.scroll_right(step)
+            }
+            #[cfg(feature = "clipboard")]
+            Some(Action::Repl(ReplAction::CopyToClipboard)) => {
+                if let Err(err) = self.copy_to_clipboard() {
+                    self.write_error(format!("clipboard: {}", err));
+                }
+            }
+            #[cfg(feature = "clipboard")]
+            Some(Action::Repl(ReplAction::PasteFromClipboard)) => {
+                if let Err(err) = self.paste_from_clipboard() {
+                    self.write_error(format!("clipboard: {}", err));
+                }
+            }
+            Some(Action::Repl(action)) => return Some(action),
+            None => {
+                if let KeyCode::Char(c) = key.code {
+                    if !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                    {
+                        self.command_input_state.add_char(c);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Runs the rc file on the first call, see `TuiClap::set_rc_file`.
+    /// Handles the next event of `events`, if any: keys with `TuiClap::handle_key_event`, executing the input with
+    /// `TuiClap::execute` on `ReplAction::Submit`, pasted text, mouse events and resize events, see `TuiClap::take_resize`,
+    /// the candidates of asynchronous completers on `TerminalEvent::CompletionReady` and the results of asynchronous
+    /// handlers on `TerminalEvent::HandlerCompleted`.
+    /// Returns `false` if the application should exit, on `ReplAction::Exit` or if `events` is disconnected.
+    pub fn fetch_event(&mut self, events: &Events) -> bool {
+        self.connect_events(events);
+        self.run_rc_file();
+        self.poll_jobs();
+        #[cfg(feature = "tokio")]
+        self.poll_handlers();
+        match events.next() {
+            Ok(Some(TerminalEvent::Input(Event::Key(key)))) => match self.handle_key_event(key) {
+                Some(ReplAction::Submit) => {
+                    self.execute();
+                    if self.exit_requested {
+                        return false;
+                    }
+                }
+                Some(ReplAction::Exit) => return false,
+                _ => {}
+            },
+            Ok(Some(TerminalEvent::Input(Event::Paste(text)))) => {
+                self.command_input_state.insert_str(&text)
+            }
+            Ok(Some(TerminalEvent::Input(Event::Mouse(mouse)))) => self.handle_mouse_event(mouse),
+            Ok(Some(TerminalEvent::Input(Event::Resize(width, height)))) => {
+                self.handle_resize(width, height)
+            }
+            Ok(Some(TerminalEvent::CompletionReady)) => self.poll_async_completion(),
+            #[cfg(feature = "tokio")]
+            Ok(Some(TerminalEvent::HandlerCompleted)) => self.poll_handlers(),
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+        true
+    }
+
     /// Parses the current content of the input widget, resets it and returns the matches if successful.
     /// If the command was not matched by clap, the error will be written to the output widget and a `Result::Err` is returned.
+    /// If the content ends with a backslash, a continuation line is started instead of parsing and `Result::Err` is returned.
+    /// Built-in commands like `history` are run instead of parsing as well and return `Result::Err`.
+    /// History references like `!!` are expanded before, see `CommandInputState::expand_history`,
+    /// and the expanded command is written to the output widget.
+    /// Commands that fail to parse are recorded as failed in the history, the success of matched commands can be reported
+    /// with `CommandInputState::report_success` once they ran.
+    #[allow(clippy::result_unit_err)]
     pub fn parse(&mut self) -> Result<ArgMatches, ()> {
-        let content = self.command_input_state.content.clone();
-        self.state().enter();
+        let content = self.enter_input().ok_or(())?;
+        if self.echo_commands {
+            self.echo_command(&content);
+        }
+        self.run_command(&content)
+    }
+
+    /// Parses `line` like `TuiClap::parse`, e.g. a command of a script, a test or one issued by the application,
+    /// without touching the input widget and the history. Aliases, variables, built-in commands and hooks apply like
+    /// to entered commands and errors are written to the output, but `line` is not echoed and missing arguments are
+    /// not asked for, see `TuiClap::set_wizard`. Use `TuiClap::peek_parse` to parse the input without any output.
+    #[allow(clippy::result_unit_err)]
+    pub fn parse_line(&mut self, line: &str) -> Result<ArgMatches, ()> {
+        let recorded = mem::replace(&mut self.command_input_state.last_recorded, false);
+        let wizard = mem::replace(&mut self.wizard, false);
+        let result = self.run_command(line);
+        self.command_input_state.last_recorded = recorded;
+        self.wizard = wizard;
+        result
+    }
+
+    /// Takes the content of the input widget to be run and enters it into the history, expanding history references.
+    /// Returns `None` if there is no command to run, if it answered a question asked with `TuiClap::ask`,
+    /// if a continuation line was started or the expansion failed.
+    pub(crate) fn enter_input(&mut self) -> Option<String> {
+        if self.answer_question() || self.command_input_state.continue_line() {
+            return None;
+        }
+        self.command_output_state.start_command();
+
+        // the previous command is saved only now, so that its success can be reported in the meantime
+        if let Some(path) = &self.history_file {
+            if let Err(err) = self.command_input_state.save_history(path) {
+                self.write_error(format!("history: {}", err));
+            }
+        }
+        match self
+            .command_input_state
+            .expand_history(&self.command_input_state.content)
+        {
+            Ok(Some(expanded)) => {
+                if !self.echo_commands {
+                    self.write_to_output(expanded.clone());
+                }
+                self.command_input_state.content = expanded;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.write_error(format!("error: {}", err));
+                self.command_input_state.reset();
+                return None;
+            }
+        }
+        self.echo = None;
+        self.correction = None;
+        Some(self.state().enter())
+    }
+
+    /// Runs the command `content` if it is a built-in command, expanding aliases and substituting variables,
+    /// or parses it with clap otherwise. The hooks registered with `TuiClap::before_parse` are called first.
+    pub(crate) fn run_command(&mut self, content: &str) -> Result<ArgMatches, ()> {
+        self.command_input_state.last_status = None;
+        let mut content = content.to_string();
+        self.run_before_parse_hooks(&mut content);
+        let content = match self.substitute_variables(&self.expand_alias(&content)) {
+            Ok(content) => content,
+            Err(err) => {
+                self.write_error(err);
+                self.command_input_state.report_success(false);
+                return Err(());
+            }
+        };
+        if self.run_builtin(&content) || self.enter_typed_scope(&content) {
+            return Err(());
+        }
+        self.parse_command(&content)
+    }
+
+    /// Writes `content` to the output behind the prompt, displayed according to the echo mode of the input widget
+    fn echo_command(&mut self, content: &str) {
+        let widget = &self.command_input_widget;
+        let continuation_prompt = widget.continuation_prompt_or_indent();
+        let style = self.command_output_widget.echo_style;
+        let lines: Vec<Spans<'static>> = content
+            .split('\n')
+            .enumerate()
+            .map(|(row, line)| {
+                let prompt = if row == 0 {
+                    widget.prompt.clone()
+                } else {
+                    continuation_prompt.clone()
+                };
+                let line = widget.echo_mode.display(line).into_owned();
+                Spans::from(vec![Span::styled(prompt, style), Span::styled(line, style)])
+            })
+            .collect();
+        let handle = self.command_output_state.push_block(
+            lines.iter().cloned().map(|line| (line, vec![])).collect(),
+            Level::Info,
+        );
+        self.echo = Some(Echo { handle, lines });
+    }
+
+    /// Splits `content` into words like a shell, see `tokenize`, parses them with clap and reports the success
+    /// to the history, unless the command has to be run first
+    fn parse_command(&mut self, content: &str) -> Result<ArgMatches, ()> {
+        let words = match tokenize(content) {
+            Ok(words) => self.command_words(words),
+            Err(err) => {
+                self.write_clap_error(&format!("error: {}", err));
+                self.command_input_state.report_status(USAGE_ERROR);
+                return Err(());
+            }
+        };
+        self.parse_words(words, content)
+    }
 
-        let commands_vec = content.split(' ').collect::<Vec<&str>>();
-        let matches_result = self.clap.try_get_matches_from_mut(commands_vec.clone());
+    /// Parses the words of the command line `content`, starting with the binary name, with clap and reports the
+    /// success to the history, unless the command has to be run first or missing arguments are asked for,
+    /// see `TuiClap::set_wizard`
+    pub(crate) fn parse_words(
+        &mut self,
+        words: Vec<String>,
+        content: &str,
+    ) -> Result<ArgMatches, ()> {
+        let wizard_words = self.wizard.then(|| words.clone());
+        let matches_result = self.clap.try_get_matches_from_mut(words);
 
         match matches_result {
-            Ok(matches) => Ok(matches),
-            Err(err) => match err.kind {
+            Ok(matches) => {
+                self.run_after_parse_hooks(&matches);
+                Ok(matches)
+            }
+            Err(err) => match err.kind() {
                 ErrorKind::DisplayHelp => {
-                    let mut buf = Vec::new();
-                    let mut writer = Box::new(&mut buf);
-                    self.clap
-                        .write_help(&mut writer)
-                        .expect("Could not write help");
-                    self.write_to_output(std::str::from_utf8(buf.as_slice()).unwrap().to_string());
+                    // the error is the help text of the subcommand it was requested for
+                    if self.help_popup_enabled {
+                        self.show_help_popup(&err.to_string());
+                    } else {
+                        self.write_help(err.to_string());
+                    }
+                    self.command_input_state.report_success(true);
                     Err(())
                 }
                 ErrorKind::DisplayVersion => {
-                    self.write_to_output(self.clap.render_long_version());
+                    self.write_help(self.clap.render_long_version());
+                    self.command_input_state.report_success(true);
                     Err(())
                 }
                 ErrorKind::Format => {
+                    self.command_input_state.report_success(false);
+                    Err(())
+                }
+                ErrorKind::MissingRequiredArgument
+                    if wizard_words.is_some_and(|words| self.start_wizard(words)) =>
+                {
                     Err(())
                 }
                 _ => {
-                    self.write_to_output(format!("error: {}", err));
+                    self.write_clap_error(&err.to_string());
+                    self.suggest_correction(content, &err);
+                    self.command_input_state.report_status(USAGE_ERROR);
                     Err(())
-                },
+                }
             },
         }
     }
 
+    /// Writes a help or version text of the clap app, as Markdown if enabled
+    fn write_help(&mut self, text: String) {
+        let lines = self
+            .help_lines(&text)
+            .into_iter()
+            .map(|line| (line, vec![]))
+            .collect();
+        self.command_output_state.push_block(lines, Level::Info);
+    }
+
+    /// The lines of a help or version text of the clap app, rendered as Markdown if enabled
+    fn help_lines(&self, text: &str) -> Vec<Spans<'static>> {
+        if self.markdown_help {
+            parse_markdown(text, &self.command_output_widget.markdown_styles)
+        } else {
+            style_help(text, &self.command_output_widget.help_styles)
+        }
+    }
+
+    /// Inserts the text of the system clipboard at the cursor of the input widget
+    #[cfg(feature = "clipboard")]
+    pub fn paste_from_clipboard(&mut self) -> Result<(), arboard::Error> {
+        let text = self.clipboard.get_text()?;
+        self.command_input_state.insert_str(&text);
+        Ok(())
+    }
+
+    /// Copies the selection, or the whole content if nothing is selected, of the input widget to the system clipboard
+    #[cfg(feature = "clipboard")]
+    pub fn copy_to_clipboard(&mut self) -> Result<(), arboard::Error> {
+        let state = &self.command_input_state;
+        let text = state.selected_text().unwrap_or(&state.content);
+        self.clipboard.set_text(text)
+    }
+
     /// Access the input widget
     pub fn input_widget(&mut self) -> &mut CommandInput {
         self.command_input_widget.borrow_mut()
     }
 
-    /// Render the input widget on `tui:Frame`
+    /// Registers a validator that is run on the content of the input widget whenever it changed.
+    /// While the content is invalid it is rendered with the invalid style of the input widget,
+    /// and the result is available through `CommandInputState::validation`, e.g. to show the message.
+    pub fn set_validator(&mut self, validator: impl Fn(&str) -> Validation + 'static) {
+        self.validator = Some(Box::new(validator));
+        self.validated_content = None;
+    }
+
+    /// Runs the validator if the content of the input widget changed since the last validation
+    fn validate_input(&mut self) {
+        let validator = match &self.validator {
+            Some(validator) => validator,
+            None => return,
+        };
+        if self.is_typing_output_search() || self.is_asking() {
+            self.command_input_state.validation = Validation::Valid;
+            self.validated_content = None;
+            return;
+        }
+        let content = &self.command_input_state.content;
+        if self.validated_content.as_ref() == Some(content) {
+            return;
+        }
+        self.command_input_state.validation = validator(content);
+        self.validated_content = Some(content.clone());
+    }
+
+    /// Enables syntax highlighting of the input widget according to the grammar of the clap app
+    /// with the given styles, or disables it if `None`
+    pub fn set_highlighting(&mut self, styles: Option<HighlightStyles>) {
+        if styles.is_some() {
+            self.clap.build();
+        }
+        self.highlight_styles = styles;
+    }
+
+    /// Render the input widget on `tui:Frame` and place the terminal cursor at the current edit position
     pub fn render_input<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        self.poll_async_completion();
+        self.validate_input();
+        self.command_input_state.highlights = match &self.highlight_styles {
+            // a typed output search is no command
+            _ if self.is_typing_output_search() || self.is_asking() => vec![],
+            Some(styles) => self
+                .builtin_highlights(styles, &self.command_input_state.content)
+                .unwrap_or_else(|| {
+                    styles.highlight(
+                        self.scoped_app(),
+                        &self.command_input_state.content,
+                        self.typed_binary_name(),
+                    )
+                }),
+            None => vec![],
+        };
         frame.render_stateful_widget(
             self.command_input_widget.clone(),
             area,
             self.command_input_state.borrow_mut(),
         );
+        self.focus.input_area = area;
+        if let Some((x, y)) = self.command_input_state.cursor_position() {
+            // the cursor is hidden while the output has the focus
+            if self.focus() == Focus::Input {
+                frame.set_cursor(x, y);
+            }
+        }
+        self.render_completion_menu(frame);
     }
 
     /// Access the output widget
@@ -322,10 +2155,116 @@ impl TuiClap<'_> {
 
     /// Render the output widget on `tui:Frame`
     pub fn render_output<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        self.poll_jobs();
+        #[cfg(feature = "tokio")]
+        self.poll_handlers();
         frame.render_stateful_widget(
             self.command_output_widget.clone(),
             area,
             self.command_output_state.borrow_mut(),
         );
     }
+
+    /// Render the error stream with the output widget on `tui:Frame`, if it is separated from the output,
+    /// see `TuiClap::set_separate_errors`
+    pub fn render_errors<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        frame.render_stateful_widget(
+            self.command_output_widget.clone(),
+            area,
+            self.command_error_state.borrow_mut(),
+        );
+    }
+}
+
+/// Appends the lines of `string` to `state`, converting ANSI escape sequences if `ansi_parsing` is set
+fn push_lines(
+    state: &mut CommandOutputState,
+    string: String,
+    style: Style,
+    level: Level,
+    ansi_parsing: bool,
+) -> OutputHandle {
+    state.push_block(split_lines(string, style, ansi_parsing), level)
+}
+
+/// The lines of `string` in `style` with their hyperlinks, converting ANSI escape sequences if `ansi_parsing` is set
+fn split_lines(
+    string: String,
+    style: Style,
+    ansi_parsing: bool,
+) -> Vec<(Spans<'static>, Vec<Link>)> {
+    if ansi_parsing {
+        return parse_ansi(&string, style);
+    }
+    let lines: Lines = string.lines();
+    lines
+        .map(|str| (Spans::from(Span::styled(str.to_string(), style)), vec![]))
+        .collect()
+}
+
+impl Drop for TuiClap {
+    fn drop(&mut self) {
+        if let Some(path) = &self.history_file {
+            let _ = self.command_input_state.save_history(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CommandInput, CommandInputState, TuiClap};
+    use clap::Command;
+    use tui::backend::TestBackend;
+    use tui::buffer::Buffer;
+    use tui::layout::Rect;
+    use tui::widgets::StatefulWidget;
+    use tui::Terminal;
+
+    fn render(prompt: &str, width: u16) -> (Buffer, CommandInputState) {
+        let mut widget = CommandInput::default();
+        widget.prompt(prompt);
+        let mut state = CommandInputState::default();
+        state.insert_str("status");
+        let area = Rect::new(0, 0, width, 1);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf, &mut state);
+        (buf, state)
+    }
+
+    #[test]
+    fn prompt_as_wide_as_area_hides_content() {
+        for width in [0, 5, 9] {
+            let (_, state) = render("prompt > ", width);
+            assert_eq!(state.cursor_position(), None);
+        }
+    }
+
+    #[test]
+    fn content_follows_prompt() {
+        let (buf, state) = render("> ", 10);
+        let text: String = (0..10).map(|x| buf.get(x, 0).symbol.as_str()).collect();
+        assert_eq!(text, "> status  ");
+        assert_eq!(state.cursor_position(), Some((8, 0)));
+    }
+
+    #[test]
+    fn output_renders_into_empty_area() {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        for i in 0..20 {
+            tui.write_to_output(format!("line {}", i));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(20, 6)).unwrap();
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+        tui.output_state().scroll_up(3);
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+        for empty in [
+            Rect::default(),
+            Rect::new(0, 0, 20, 0),
+            Rect::new(0, 0, 0, 6),
+        ] {
+            terminal.draw(|f| tui.render_output(f, empty)).unwrap();
+        }
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+        assert_eq!(tui.output_state().scroll_offset(), 3);
+    }
 }
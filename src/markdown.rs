@@ -0,0 +1,147 @@
+//! Rendering a small subset of Markdown written to the output: headings, bold text, code spans and blocks,
+//! and bullet lists
+
+use crate::output::Level;
+use crate::{CommandOutput, TuiClap};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Styles of Markdown written with `TuiClap::write_markdown`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownStyles {
+    /// Style of the text of headings, which are written without the leading `#`
+    pub heading: Style,
+    /// Style of text between `**` or `__`
+    pub bold: Style,
+    /// Style of code spans between backticks and of code blocks between lines of three backticks
+    pub code: Style,
+    /// Style of the `•` replacing the `-`, `*` or `+` of list items
+    pub bullet: Style,
+}
+
+impl Default for MarkdownStyles {
+    fn default() -> Self {
+        MarkdownStyles {
+            heading: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            bold: Style::default().add_modifier(Modifier::BOLD),
+            code: Style::default().fg(Color::Yellow),
+            bullet: Style::default().fg(Color::Cyan),
+        }
+    }
+}
+
+impl CommandOutput {
+    /// Sets the styles of Markdown written with `TuiClap::write_markdown`
+    pub fn markdown_styles(&mut self, styles: MarkdownStyles) {
+        self.markdown_styles = styles;
+    }
+}
+
+/// Splits `text` into lines styled according to the Markdown in it
+pub(crate) fn parse_markdown(text: &str, styles: &MarkdownStyles) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    let mut in_code_block = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(Spans::from(Span::styled(line.to_string(), styles.code)));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            let heading = trimmed[hashes..].trim();
+            lines.push(Spans::from(Span::styled(
+                heading.to_string(),
+                styles.heading,
+            )));
+            continue;
+        }
+        let item = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker));
+        let mut spans = vec![];
+        let rest = match item {
+            Some(item) => {
+                spans.push(Span::raw(indent.to_string()));
+                spans.push(Span::styled("• ", styles.bullet));
+                item
+            }
+            None => line,
+        };
+        spans.extend(parse_inline(rest, styles));
+        lines.push(Spans::from(spans));
+    }
+    lines
+}
+
+/// Splits `text` into spans styled according to the bold markers and code spans in it.
+/// Markers without a closing counterpart are kept as they are.
+fn parse_inline(text: &str, styles: &MarkdownStyles) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut bold = false;
+    let mut content = String::new();
+    let mut rest = text;
+    let style = |bold: bool| if bold { styles.bold } else { Style::default() };
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                if !content.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut content), style(bold)));
+                }
+                let code = &rest[1..end + 1];
+                spans.push(Span::styled(
+                    code.to_string(),
+                    style(bold).patch(styles.code),
+                ));
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        let marker = ["**", "__"]
+            .iter()
+            .copied()
+            .find(|&marker| rest.starts_with(marker));
+        if let Some(marker) = marker {
+            // an opening marker needs a closing one
+            if bold || rest[2..].contains(marker) {
+                if !content.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut content), style(bold)));
+                }
+                bold = !bold;
+                rest = &rest[2..];
+                continue;
+            }
+        }
+        content.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !content.is_empty() {
+        spans.push(Span::styled(content, style(bold)));
+    }
+    spans
+}
+
+impl TuiClap {
+    /// Writes `text` to the output widget, rendering the Markdown in it: headings, bold text, code spans and blocks,
+    /// and bullet lists, see `CommandOutput::markdown_styles`. Other Markdown is written as it is.
+    pub fn write_markdown(&mut self, text: &str) {
+        for line in parse_markdown(text, &self.command_output_widget.markdown_styles) {
+            self.command_output_state
+                .push_line(line, vec![], Level::Info);
+        }
+    }
+
+    /// Sets whether help and version texts of the clap app are written with `TuiClap::write_markdown`,
+    /// e.g. to format its `about` with Markdown. Disabled by default.
+    pub fn set_markdown_help(&mut self, enabled: bool) {
+        self.markdown_help = enabled;
+    }
+}
@@ -0,0 +1,82 @@
+//! Several command sets, like the configuration and operational mode of a network device, switched at runtime
+
+use crate::handlers::Handler;
+use crate::jobs::WorkerHandler;
+use crate::TuiClap;
+use clap::Command;
+use std::collections::HashMap;
+use std::mem;
+
+/// A command set which is not active at the moment, with its prompt and handlers
+pub(crate) struct Mode {
+    clap: Command,
+    prompt: String,
+    handlers: HashMap<String, Handler>,
+    workers: HashMap<String, WorkerHandler>,
+}
+
+impl TuiClap {
+    /// Adds the command set `app` as mode named after the app, e.g. `configure`, with its own `prompt`.
+    /// The app `TuiClap` was created from is the first mode. Completion, help and highlighting follow the mode
+    /// switched to with `TuiClap::set_mode`, and the handlers registered with `TuiClap::on` belong to the active mode,
+    /// while built-in commands, aliases and variables are shared. An existing mode of the same name is replaced.
+    pub fn add_mode(&mut self, app: Command, prompt: &str) {
+        let name = app.get_name().to_string();
+        if name == self.mode {
+            self.clap = app;
+            self.command_input_widget.prompt(prompt);
+            return;
+        }
+        let mode = Mode {
+            clap: app,
+            prompt: prompt.to_string(),
+            handlers: HashMap::new(),
+            workers: HashMap::new(),
+        };
+        self.modes.insert(name, mode);
+    }
+
+    /// Switches to the mode `name`, e.g. from the handler of a `configure` subcommand, replacing the app, prompt and
+    /// handlers. Returns `false` if there is no such mode.
+    pub fn set_mode(&mut self, name: &str) -> bool {
+        if name == self.mode {
+            return true;
+        }
+        let mut mode = match self.modes.remove(name) {
+            Some(mode) => mode,
+            None => return false,
+        };
+        // the scopes are subcommands of the app of the mode
+        self.leave_scopes();
+        mem::swap(&mut self.clap, &mut mode.clap);
+        mem::swap(&mut self.command_input_widget.prompt, &mut mode.prompt);
+        mem::swap(&mut self.handlers, &mut mode.handlers);
+        self.jobs.swap_workers(&mut mode.workers);
+        let previous = mem::replace(&mut self.mode, name.to_string());
+        self.modes.insert(previous, mode);
+        true
+    }
+
+    /// The name of the active mode, e.g. to show it in the prompt
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    /// The names of all modes sorted
+    pub fn modes(&self) -> Vec<&str> {
+        let mut modes: Vec<&str> = self.modes.keys().map(String::as_str).collect();
+        modes.push(&self.mode);
+        modes.sort_unstable();
+        modes
+    }
+
+    /// Puts `handler` back after it ran, into the handlers of the mode `mode` it belongs to,
+    /// which is no longer the active one if the handler switched the mode
+    pub(crate) fn restore_handler(&mut self, mode: &str, key: String, handler: Handler) {
+        let handlers = match self.modes.get_mut(mode) {
+            Some(mode) => &mut mode.handlers,
+            None => &mut self.handlers,
+        };
+        handlers.entry(key).or_insert(handler);
+    }
+}
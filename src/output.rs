@@ -0,0 +1,713 @@
+//! Lines of the output, how many of them are kept, which of them are shown and how they are wrapped
+
+use crate::hyperlink::Link;
+use crate::output_search::line_text;
+#[cfg(feature = "json")]
+use crate::JsonStyles;
+use crate::{CommandOutput, CommandOutputState, HelpStyles, MarkdownStyles};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Severity of a line of the output, ordered from `Level::Debug` to `Level::Error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Level {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// A line of the output
+#[derive(Debug, Clone)]
+pub(crate) struct OutputLine {
+    pub(crate) spans: Spans<'static>,
+    pub(crate) level: Level,
+    /// When the line was written
+    pub(crate) timestamp: SystemTime,
+    /// Number of the line counted from the first line written, starting at 1
+    pub(crate) number: usize,
+    /// Number of the line counted from the first line written by the command, starting at 1
+    pub(crate) command_number: usize,
+    pub(crate) links: Vec<Link>,
+    /// Id of the write the line belongs to, see `OutputHandle`
+    pub(crate) block: Option<usize>,
+    /// Number of the command which wrote the line, see `CommandOutputState::toggle_section`
+    pub(crate) section: usize,
+    /// Passed to the callback of `TuiClap::on_line_activated` when the line is activated
+    pub(crate) payload: Option<String>,
+    /// Number of rows the line is wrapped into, cached for the layout of `CommandOutputState::wrapped_as`
+    pub(crate) rows: Cell<Option<usize>>,
+}
+
+/// Identifies the lines of a write to the output widget to replace them later, see `TuiClap::update_output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutputHandle(usize);
+
+impl OutputLine {
+    pub(crate) fn number(&self, numbers: LineNumbers) -> usize {
+        match numbers {
+            LineNumbers::Absolute => self.number,
+            LineNumbers::PerCommand => self.command_number,
+        }
+    }
+
+    /// Number of rows the line is wrapped into, see `wrap`. Computed once for the layout the cache is kept for,
+    /// see `CommandOutputState::keep_row_counts`.
+    pub(crate) fn row_count(&self, width: usize, mode: WrapMode, prefix: &str) -> usize {
+        if let Some(rows) = self.rows.get() {
+            return rows;
+        }
+        let rows = wrap(&self.spans, width, mode, prefix).len();
+        self.rows.set(Some(rows));
+        rows
+    }
+}
+
+/// How the lines are numbered by `CommandOutput::line_numbers`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// Count all lines ever written, so that the number of a line does not change when older lines are dropped
+    Absolute,
+    /// Count the lines written by each command separately, see `CommandOutputState::start_command`
+    PerCommand,
+}
+
+/// Width of the timestamp gutter, `HH:MM:SS` and a space
+pub(crate) const TIMESTAMP_WIDTH: usize = 9;
+
+/// The local time of `timestamp` as `HH:MM:SS`, padded to the width of the timestamp gutter
+pub(crate) fn format_timestamp(timestamp: SystemTime) -> String {
+    format!("{} ", DateTime::<Local>::from(timestamp).format("%H:%M:%S"))
+}
+
+/// How `CommandOutput` breaks lines wider than the widget into rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// Break at the last grapheme cluster fitting into the row
+    #[default]
+    Character,
+    /// Break behind the last whitespace fitting into the row, or like `WrapMode::Character` if there is none
+    Word,
+    /// Do not break lines but cut them off at the edge of the widget. The output can be scrolled horizontally
+    /// to show the rest of them, see `CommandOutputState::scroll_right`.
+    None,
+}
+
+impl Default for CommandOutput {
+    fn default() -> Self {
+        CommandOutput {
+            debug_style: Style::default().fg(Color::DarkGray),
+            info_style: Style::default(),
+            warn_style: Style::default().fg(Color::Yellow),
+            error_style: Style::default().fg(Color::Red),
+            match_style: Style::default().bg(Color::Yellow).fg(Color::Black),
+            current_match_style: Style::default().bg(Color::LightRed).fg(Color::Black),
+            wrap_mode: WrapMode::default(),
+            continuation_prefix: String::new(),
+            timestamps: false,
+            timestamp_style: Style::default().fg(Color::DarkGray),
+            selection_style: Style::default().add_modifier(Modifier::REVERSED),
+            new_lines_style: Style::default().bg(Color::Cyan).fg(Color::Black),
+            line_numbers: None,
+            line_number_style: Style::default().fg(Color::DarkGray),
+            hyperlinks: false,
+            link_style: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::UNDERLINED),
+            echo_style: Style::default().add_modifier(Modifier::BOLD),
+            table_separators: false,
+            table_header_style: Style::default().add_modifier(Modifier::BOLD),
+            table_separator_style: Style::default().fg(Color::DarkGray),
+            #[cfg(feature = "json")]
+            json_styles: JsonStyles::default(),
+            #[cfg(feature = "json")]
+            json_fold: None,
+            markdown_styles: MarkdownStyles::default(),
+            help_styles: HelpStyles::default(),
+            scrollbar: false,
+            scrollbar_style: Style::default().fg(Color::DarkGray),
+            section_style: Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+impl CommandOutput {
+    /// Sets the style of lines of `level`, underneath the styles of their spans. By default, debug lines are dark gray,
+    /// warnings yellow, errors red and info lines unstyled.
+    pub fn level_style(&mut self, level: Level, style: Style) {
+        match level {
+            Level::Debug => self.debug_style = style,
+            Level::Info => self.info_style = style,
+            Level::Warn => self.warn_style = style,
+            Level::Error => self.error_style = style,
+        }
+    }
+
+    pub(crate) fn style_of(&self, level: Level) -> Style {
+        match level {
+            Level::Debug => self.debug_style,
+            Level::Info => self.info_style,
+            Level::Warn => self.warn_style,
+            Level::Error => self.error_style,
+        }
+    }
+
+    /// Sets the style of the matches of an output search, black on yellow by default
+    pub fn match_style(&mut self, style: Style) {
+        self.match_style = style;
+    }
+
+    /// Sets the style of the matches in the line of the current match of an output search, black on light red by default
+    pub fn current_match_style(&mut self, style: Style) {
+        self.current_match_style = style;
+    }
+
+    /// Sets whether the time each line was written at is shown in a gutter in front of it as `HH:MM:SS`, disabled by default
+    pub fn timestamps(&mut self, enabled: bool) {
+        self.timestamps = enabled;
+    }
+
+    /// Sets the style of the timestamps, dark gray by default
+    pub fn timestamp_style(&mut self, style: Style) {
+        self.timestamp_style = style;
+    }
+
+    /// Sets whether and how the lines are numbered in a gutter in front of them, none by default
+    pub fn line_numbers(&mut self, numbers: Option<LineNumbers>) {
+        self.line_numbers = numbers;
+    }
+
+    /// Sets the style of the line numbers, dark gray by default
+    pub fn line_number_style(&mut self, style: Style) {
+        self.line_number_style = style;
+    }
+
+    /// Sets the style of the selected lines, see `CommandOutputState::start_selection`, reversed by default
+    pub fn selection_style(&mut self, style: Style) {
+        self.selection_style = style;
+    }
+
+    /// Sets the style of the indicator of lines written while the output is scrolled up, black on cyan by default
+    pub fn new_lines_style(&mut self, style: Style) {
+        self.new_lines_style = style;
+    }
+
+    /// Sets the style of the commands written to the output by `TuiClap::set_echo_commands`, bold by default
+    pub fn echo_style(&mut self, style: Style) {
+        self.echo_style = style;
+    }
+
+    /// Sets how lines wider than the widget are broken into rows, `WrapMode::Character` by default
+    pub fn wrap_mode(&mut self, mode: WrapMode) {
+        self.wrap_mode = mode;
+    }
+
+    /// Sets the text shown in front of the continuation rows of wrapped lines, e.g. `↪ `, none by default
+    pub fn continuation_prefix(&mut self, prefix: &str) {
+        self.continuation_prefix = prefix.to_string();
+    }
+}
+
+impl CommandOutputState {
+    /// Sets the maximum number of lines kept in the output, dropping the oldest ones beyond it.
+    /// `None`, the default, keeps all lines.
+    pub fn scrollback_limit(&mut self, limit: Option<usize>) {
+        self.scrollback_limit = limit;
+        self.trim_scrollback();
+    }
+
+    /// Hides the lines for which `filter` returns `false` from the output until the filter is cleared,
+    /// replacing any previous filter. The lines are kept and shown again once the filter is cleared.
+    pub fn set_filter(&mut self, filter: impl Fn(&str) -> bool + 'static) {
+        self.filter = Some(Box::new(filter));
+        self.scroll = 0;
+    }
+
+    /// Hides the lines not matching the regular expression `pattern` from the output, like `CommandOutputState::set_filter`
+    pub fn set_filter_pattern(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        let pattern = Regex::new(pattern)?;
+        self.set_filter(move |line| pattern.is_match(line));
+        Ok(())
+    }
+
+    /// Shows all lines again
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+        self.scroll = 0;
+    }
+
+    pub fn is_filtered(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    /// Hides the lines below `level` from the output, `Level::Debug` by default showing all lines
+    pub fn min_level(&mut self, level: Level) {
+        self.min_level = Some(level);
+        self.scroll = 0;
+    }
+
+    /// Whether the line at `index` passes the filter and the minimum level and is not hidden in a folded section
+    pub(crate) fn is_shown(&self, index: usize) -> bool {
+        let line = &self.history[index];
+        !self.is_folded_away(index)
+            && self
+                .min_level
+                .is_none_or(|min_level| line.level >= min_level)
+            && match &self.filter {
+                Some(filter) => filter(&line_text(&line.spans)),
+                None => true,
+            }
+    }
+
+    /// Writes all lines of the output to the file at `path` as plain text, including lines hidden by a filter,
+    /// replacing the file if it exists
+    pub fn dump(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for line in &self.history {
+            writeln!(file, "{}", line_text(&line.spans))?;
+        }
+        file.flush()
+    }
+
+    /// Removes all lines from the output
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.search = None;
+        self.selection = None;
+        self.scroll = 0;
+        self.cleared_at = None;
+    }
+
+    /// Scrolls all lines out of the view like `clear` in a terminal, so that the next line is shown at the top.
+    /// The lines are kept and can be scrolled back to.
+    pub fn clear_screen(&mut self) {
+        self.cleared_at = Some(self.history.len());
+        self.selection = None;
+        self.scroll = 0;
+    }
+
+    /// Starts counting the lines of a new command for `LineNumbers::PerCommand` and a new section of the output,
+    /// see `CommandOutputState::toggle_section`. Called by `TuiClap::parse`.
+    pub fn start_command(&mut self) {
+        self.command_lines = 0;
+        self.sections_started += 1;
+    }
+
+    /// Number of lines of the output, including lines hidden by a filter
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// Appends `line` to the output, dropping the oldest line if the scrollback limit is exceeded.
+    /// While capturing, the text of lines other than errors is captured instead.
+    pub(crate) fn push_line(&mut self, spans: Spans<'static>, links: Vec<Link>, level: Level) {
+        if self.capture(&spans, level) {
+            return;
+        }
+        self.lines_written += 1;
+        self.command_lines += 1;
+        self.history.push_back(OutputLine {
+            spans,
+            level,
+            timestamp: SystemTime::now(),
+            number: self.lines_written,
+            command_number: self.command_lines,
+            links,
+            block: None,
+            section: self.sections_started,
+            payload: None,
+            rows: Cell::default(),
+        });
+        self.trim_scrollback();
+        if self.follow {
+            self.scroll = 0;
+        } else if self.scroll > 0 && self.is_shown(self.history.len() - 1) {
+            self.unrendered_lines += 1;
+            self.new_lines += 1;
+        }
+    }
+
+    /// Appends `lines` to the output like `push_line`, returning a handle to replace them with `replace_block`
+    pub(crate) fn push_block(
+        &mut self,
+        lines: Vec<(Spans<'static>, Vec<Link>)>,
+        level: Level,
+    ) -> OutputHandle {
+        self.blocks_written += 1;
+        let block = self.blocks_written;
+        for (spans, links) in lines {
+            if self.capture(&spans, level) {
+                continue;
+            }
+            self.push_line(spans, links, level);
+            if let Some(line) = self.history.back_mut() {
+                line.block = Some(block);
+            }
+        }
+        OutputHandle(block)
+    }
+
+    /// Replaces the lines of `handle` with `lines`, keeping their level and timestamp.
+    /// The lines behind are renumbered if the number of lines changes.
+    /// Returns `false` if they are no longer in the output.
+    pub(crate) fn replace_block(
+        &mut self,
+        handle: OutputHandle,
+        lines: Vec<(Spans<'static>, Vec<Link>)>,
+    ) -> bool {
+        let block = Some(handle.0);
+        let end = match self.history.iter().rposition(|line| line.block == block) {
+            Some(last) => last + 1,
+            None => return false,
+        };
+        let start = self
+            .history
+            .range(..end)
+            .rposition(|line| line.block != block)
+            .map_or(0, |before| before + 1);
+        let first = &self.history[start];
+        let (level, timestamp) = (first.level, first.timestamp);
+        let (number, command_number) = (first.number, first.command_number);
+        let section = first.section;
+        let payload = first.payload.clone();
+
+        let count = lines.len();
+        self.history.drain(start..end);
+        for (i, (spans, links)) in lines.into_iter().enumerate() {
+            let line = OutputLine {
+                spans,
+                level,
+                timestamp,
+                number: number + i,
+                command_number: command_number + i,
+                links,
+                block,
+                section,
+                payload: payload.clone(),
+                rows: Cell::default(),
+            };
+            self.history.insert(start + i, line);
+        }
+
+        // indices and numbers behind the replaced lines move by the difference in their number
+        if count != end - start {
+            let delta = count as isize - (end - start) as isize;
+            let renumber = |number: usize| number.saturating_add_signed(delta);
+            let mut same_command = true;
+            for line in self.history.range_mut(start + count..) {
+                line.number = renumber(line.number);
+                same_command &= line.command_number != 1;
+                if same_command {
+                    line.command_number = renumber(line.command_number);
+                }
+            }
+            self.lines_written = renumber(self.lines_written);
+            if same_command {
+                self.command_lines = renumber(self.command_lines);
+            }
+            let shift = |index: usize| match index {
+                index if index >= end => Some(index + count - (end - start)),
+                index if index >= start => None,
+                index => Some(index),
+            };
+            if let Some(search) = &mut self.search {
+                search.current = search.current.and_then(shift);
+            }
+            self.cleared_at = self
+                .cleared_at
+                .map(|cleared_at| shift(cleared_at).unwrap_or(start + count));
+            self.selection = None;
+        }
+        true
+    }
+
+    /// Keeps the cached row counts of the lines if they were computed for the layout of `width`, `mode` and `prefix`,
+    /// otherwise drops them, e.g. after the width of the widget changed
+    pub(crate) fn keep_row_counts(&mut self, width: usize, mode: WrapMode, prefix: &str) {
+        let layout = (width, mode, prefix);
+        if self
+            .wrapped_as
+            .as_ref()
+            .map(|(w, m, p)| (*w, *m, p.as_str()))
+            != Some(layout)
+        {
+            self.clear_row_counts();
+            self.wrapped_as = Some((width, mode, prefix.to_string()));
+        }
+    }
+
+    /// Drops the cached row counts of the lines, see `OutputLine::row_count`
+    pub(crate) fn clear_row_counts(&mut self) {
+        for line in &self.history {
+            line.rows.set(None);
+        }
+        self.wrapped_as = None;
+    }
+
+    fn trim_scrollback(&mut self) {
+        if let Some(limit) = self.scrollback_limit {
+            let excess = self.history.len().saturating_sub(limit);
+            self.history.drain(..excess);
+            if let Some(search) = &mut self.search {
+                search.current = search
+                    .current
+                    .and_then(|current| current.checked_sub(excess));
+            }
+            if excess > 0 {
+                self.selection = None;
+            }
+            self.cleared_at = self
+                .cleared_at
+                .map(|cleared_at| cleared_at.saturating_sub(excess));
+        }
+    }
+}
+
+/// The grapheme clusters of `line` with the index of their span and their byte offset in it
+fn graphemes<'a>(line: &'a Spans<'_>) -> Vec<(usize, usize, &'a str)> {
+    line.0
+        .iter()
+        .enumerate()
+        .flat_map(|(s, span)| {
+            span.content
+                .grapheme_indices(true)
+                .map(move |(i, grapheme)| (s, i, grapheme))
+        })
+        .collect()
+}
+
+/// The spans of the grapheme clusters from `start` to `end` of `line`, keeping their styles
+fn spans_between<'a>(
+    line: &'a Spans<'_>,
+    graphemes: &[(usize, usize, &'a str)],
+    start: usize,
+    end: usize,
+) -> Vec<Span<'a>> {
+    let mut spans = vec![];
+    let mut i = start;
+    while i < end {
+        let (s, offset, _) = graphemes[i];
+        while i < end && graphemes[i].0 == s {
+            i += 1;
+        }
+        let span = &line.0[s];
+        let span_end = match graphemes.get(i) {
+            Some(&(next, next_offset, _)) if next == s => next_offset,
+            _ => span.content.len(),
+        };
+        spans.push(Span::styled(&span.content[offset..span_end], span.style));
+    }
+    spans
+}
+
+/// Splits `line` into rows at most `width` columns wide, keeping the styles of its spans.
+/// Rows are only broken between grapheme clusters, a cluster wider than the rest of a row starts the next row.
+/// In `WrapMode::Word`, rows are broken behind whitespace if possible. Continuation rows start with `prefix`.
+/// In `WrapMode::None`, the line is kept in a single row, see `crop`.
+pub(crate) fn wrap<'a>(
+    line: &'a Spans<'_>,
+    width: usize,
+    mode: WrapMode,
+    prefix: &'a str,
+) -> Vec<Spans<'a>> {
+    let graphemes = graphemes(line);
+
+    let width = match mode {
+        WrapMode::None => usize::MAX,
+        _ => width.max(1),
+    };
+    let continuation_width = width.saturating_sub(prefix.width()).max(1);
+    // indices of the grapheme clusters starting a row
+    let mut starts = vec![0];
+    let mut row_width = 0;
+    // index behind the last whitespace in the current row
+    let mut word_break = None;
+    for (i, &(_, _, grapheme)) in graphemes.iter().enumerate() {
+        let row_start = *starts.last().unwrap_or(&0);
+        let available = if starts.len() == 1 {
+            width
+        } else {
+            continuation_width
+        };
+        let grapheme_width = grapheme.width();
+        let whitespace = grapheme.chars().all(char::is_whitespace);
+        // whitespace hangs over the end of the row instead of starting the next one when breaking at words
+        let hangs = mode == WrapMode::Word && whitespace;
+        if row_width + grapheme_width > available && i > row_start && !hangs {
+            let start = match word_break {
+                Some(start) if mode == WrapMode::Word => start,
+                _ => i,
+            };
+            starts.push(start);
+            row_width = graphemes[start..i]
+                .iter()
+                .map(|(_, _, grapheme)| grapheme.width())
+                .sum();
+            word_break = None;
+        }
+        row_width += grapheme_width;
+        if whitespace {
+            word_break = Some(i + 1);
+        }
+    }
+
+    let mut rows = vec![];
+    for (row, &start) in starts.iter().enumerate() {
+        let end = starts.get(row + 1).copied().unwrap_or(graphemes.len());
+        let mut spans = vec![];
+        if row > 0 && !prefix.is_empty() {
+            spans.push(Span::raw(prefix));
+        }
+        spans.extend(spans_between(line, &graphemes, start, end));
+        rows.push(Spans::from(spans));
+    }
+    rows
+}
+
+/// Cuts the `width` columns starting at column `offset` out of `line`, returning them together with the byte offset
+/// in the text of the line they start at. Grapheme clusters only partly within the columns are left out.
+pub(crate) fn crop<'a>(line: &'a Spans<'_>, offset: usize, width: usize) -> (usize, Spans<'a>) {
+    let graphemes = graphemes(line);
+    let mut start = None;
+    let mut end = graphemes.len();
+    let mut column = 0;
+    for (i, &(_, _, grapheme)) in graphemes.iter().enumerate() {
+        if column >= offset && start.is_none() {
+            start = Some(i);
+        }
+        column += grapheme.width();
+        if column > offset + width {
+            end = i;
+            break;
+        }
+    }
+    let start = start.unwrap_or(end).min(end);
+    let byte_offset = match graphemes.get(start) {
+        Some(&(s, offset, _)) => {
+            line.0[..s]
+                .iter()
+                .map(|span| span.content.len())
+                .sum::<usize>()
+                + offset
+        }
+        None => line.0.iter().map(|span| span.content.len()).sum(),
+    };
+    (
+        byte_offset,
+        Spans::from(spans_between(line, &graphemes, start, end)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crop, wrap, WrapMode};
+    use tui::style::{Color, Style};
+    use tui::text::{Span, Spans};
+
+    fn rows(line: &str, width: usize, mode: WrapMode, prefix: &str) -> Vec<String> {
+        let line = Spans::from(line);
+        wrap(&line, width, mode, prefix)
+            .iter()
+            .map(|row| row.0.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_characters() {
+        assert_eq!(
+            rows("abcdefgh", 3, WrapMode::Character, ""),
+            ["abc", "def", "gh"]
+        );
+        assert_eq!(rows("abc", 3, WrapMode::Character, ""), ["abc"]);
+        assert_eq!(rows("", 3, WrapMode::Character, ""), [""]);
+    }
+
+    #[test]
+    fn wraps_at_words() {
+        assert_eq!(
+            rows("hello big world", 7, WrapMode::Word, ""),
+            ["hello ", "big ", "world"]
+        );
+        // whitespace hangs over the end of the row
+        assert_eq!(rows("abc   def", 3, WrapMode::Word, ""), ["abc   ", "def"]);
+    }
+
+    #[test]
+    fn wraps_words_without_whitespace_at_characters() {
+        assert_eq!(
+            rows("abcdefgh", 3, WrapMode::Word, ""),
+            ["abc", "def", "gh"]
+        );
+        assert_eq!(
+            rows("a abcdefgh", 3, WrapMode::Word, ""),
+            ["a ", "abc", "def", "gh"]
+        );
+    }
+
+    #[test]
+    fn continuation_rows_start_with_prefix() {
+        assert_eq!(
+            rows("abcdef", 4, WrapMode::Character, "↪ "),
+            ["abcd", "↪ ef"]
+        );
+        assert_eq!(
+            rows("abcdefgh", 4, WrapMode::Character, "↪ "),
+            ["abcd", "↪ ef", "↪ gh"]
+        );
+    }
+
+    #[test]
+    fn keeps_line_in_single_row_without_wrapping() {
+        assert_eq!(rows("abcdefgh", 3, WrapMode::None, "↪ "), ["abcdefgh"]);
+    }
+
+    #[test]
+    fn wide_characters_start_next_row() {
+        assert_eq!(rows("日本語", 4, WrapMode::Character, ""), ["日本", "語"]);
+        assert_eq!(
+            rows("日本語", 3, WrapMode::Character, ""),
+            ["日", "本", "語"]
+        );
+        assert_eq!(
+            rows("e\u{301}e\u{301}", 1, WrapMode::Character, ""),
+            ["e\u{301}", "e\u{301}"]
+        );
+    }
+
+    #[test]
+    fn keeps_styles() {
+        let red = Style::default().fg(Color::Red);
+        let line = Spans::from(vec![Span::raw("ab"), Span::styled("cd", red)]);
+        let rows = wrap(&line, 3, WrapMode::Character, "");
+        assert_eq!(rows[0].0, [Span::raw("ab"), Span::styled("c", red)]);
+        assert_eq!(rows[1].0, [Span::styled("d", red)]);
+    }
+
+    #[test]
+    fn crops_columns() {
+        let text = |(offset, spans): (usize, Spans)| {
+            let text: String = spans.0.iter().map(|span| span.content.as_ref()).collect();
+            (offset, text)
+        };
+        let line = Spans::from("abcdef");
+        assert_eq!(text(crop(&line, 2, 3)), (2, "cde".to_string()));
+        assert_eq!(text(crop(&line, 10, 3)), (6, "".to_string()));
+        let line = Spans::from("日本語");
+        assert_eq!(text(crop(&line, 1, 4)), (3, "本".to_string()));
+        assert_eq!(text(crop(&line, 2, 4)), (3, "本語".to_string()));
+    }
+}
@@ -0,0 +1,227 @@
+//! Searching the output for a regular expression and highlighting the matches
+
+use crate::keymap::Action;
+use crate::{CommandOutput, CommandOutputState, TuiClap};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use regex::Regex;
+use std::mem;
+use tui::style::Style;
+use tui::text::{Span, Spans};
+
+#[derive(Debug, Clone)]
+pub(crate) struct OutputSearch {
+    pattern: Regex,
+    /// Index of the line of the current match
+    pub(crate) current: Option<usize>,
+}
+
+/// A pattern typed in the input widget to search the output incrementally, see `TuiClap::start_output_search`
+#[derive(Debug, Clone)]
+pub(crate) struct OutputSearchInput {
+    /// Content and cursor of the input before typing the pattern, restored afterwards
+    original: (String, usize),
+}
+
+impl CommandOutputState {
+    /// Searches the output for the regular expression `pattern`, starting with the newest line,
+    /// and scrolls to the first match. Matches are highlighted until the search is cleared.
+    /// Returns whether a line matched.
+    pub fn search(&mut self, pattern: &str) -> Result<bool, regex::Error> {
+        self.search = Some(OutputSearch {
+            pattern: Regex::new(pattern)?,
+            current: None,
+        });
+        Ok(self.search_next())
+    }
+
+    /// Scrolls to the next older line matching the search, returns `false` if there is none.
+    /// Lines hidden by the filter are skipped, see `CommandOutputState::set_filter`.
+    pub fn search_next(&mut self) -> bool {
+        let before = self
+            .search
+            .as_ref()
+            .and_then(|search| search.current)
+            .unwrap_or(self.history.len());
+        let found = (0..before).rev().find(|&index| self.is_match(index));
+        self.select_match(found)
+    }
+
+    /// Scrolls to the next newer line matching the search, returns `false` if there is none
+    pub fn search_previous(&mut self) -> bool {
+        let after = match self.search.as_ref().and_then(|search| search.current) {
+            Some(current) => current + 1,
+            None => return false,
+        };
+        let found = (after..self.history.len()).find(|&index| self.is_match(index));
+        self.select_match(found)
+    }
+
+    /// Ends the search, removing the highlights of its matches
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    fn is_match(&self, index: usize) -> bool {
+        match &self.search {
+            Some(search) => {
+                search
+                    .pattern
+                    .is_match(&line_text(&self.history[index].spans))
+                    && self.is_shown(index)
+            }
+            None => false,
+        }
+    }
+
+    fn select_match(&mut self, found: Option<usize>) -> bool {
+        if let (Some(search), Some(index)) = (&mut self.search, found) {
+            search.current = Some(index);
+            self.scroll_to_line = Some(index);
+        }
+        found.is_some()
+    }
+
+    /// The line at `index` styled by `output` according to its level, with the matches of the search highlighted
+    pub(crate) fn styled_line(&self, index: usize, output: &CommandOutput) -> Spans<'_> {
+        let line = &self.history[index];
+        let mut level_style = output.style_of(line.level);
+        if self.is_selected(index) {
+            level_style = level_style.patch(output.selection_style);
+        }
+        let spans: Vec<(&str, Style)> = line
+            .spans
+            .0
+            .iter()
+            .map(|span| (span.content.as_ref(), level_style.patch(span.style)))
+            .collect();
+        let matches: Vec<(usize, usize)> = match &self.search {
+            Some(search) => search
+                .pattern
+                .find_iter(&line_text(&line.spans))
+                .filter(|found| !found.range().is_empty())
+                .map(|found| (found.start(), found.end()))
+                .collect(),
+            None => vec![],
+        };
+        let match_style = if self.search.as_ref().and_then(|search| search.current) == Some(index) {
+            output.current_match_style
+        } else {
+            output.match_style
+        };
+
+        let mut styled = vec![];
+        let mut offset = 0;
+        for (content, style) in spans {
+            let span_end = offset + content.len();
+            let mut start = offset;
+            for &(match_start, match_end) in &matches {
+                let (match_start, match_end) =
+                    (match_start.clamp(start, span_end), match_end.min(span_end));
+                if match_start >= match_end {
+                    continue;
+                }
+                if start < match_start {
+                    styled.push(Span::styled(
+                        &content[start - offset..match_start - offset],
+                        style,
+                    ));
+                }
+                styled.push(Span::styled(
+                    &content[match_start - offset..match_end - offset],
+                    style.patch(match_style),
+                ));
+                start = match_end;
+            }
+            if start < span_end {
+                styled.push(Span::styled(&content[start - offset..], style));
+            }
+            offset = span_end;
+        }
+        let styled = output.show_urls(line, styled, level_style);
+        match self.folded_lines(index) {
+            Some(folded) => Spans::from(output.show_folded(folded, styled)),
+            None => Spans::from(styled),
+        }
+    }
+}
+
+/// The text of `line` without styles
+pub(crate) fn line_text(line: &Spans) -> String {
+    line.0.iter().map(|span| span.content.as_ref()).collect()
+}
+
+impl TuiClap {
+    /// Starts typing a pattern to search the output for in the input widget, which shows `/` as prompt meanwhile.
+    /// The output is searched while typing, `Enter` keeps the search and `Esc` or `Ctrl+G` clear it.
+    /// Afterwards, the content typed before is restored.
+    pub fn start_output_search(&mut self) {
+        let state = &mut self.command_input_state;
+        let original = (mem::take(&mut state.content), state.cursor);
+        state.cursor = 0;
+        state.selection_anchor = None;
+        state.prompt_override = Some("/".to_string());
+        self.output_search_input = Some(OutputSearchInput { original });
+    }
+
+    /// Whether a pattern to search the output for is typed in the input widget
+    pub fn is_typing_output_search(&self) -> bool {
+        self.output_search_input.is_some()
+    }
+
+    /// Handles `key` while typing a pattern to search the output for, returns `false` if no pattern is typed
+    pub(crate) fn handle_output_search_key(&mut self, key: KeyEvent) -> bool {
+        if self.output_search_input.is_none() {
+            return false;
+        }
+        let ctrl_or_alt = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match (key.code, self.keymap.action(&key)) {
+            (KeyCode::Enter, _) => {
+                self.end_output_search();
+                return true;
+            }
+            (KeyCode::Esc, _) => {
+                self.end_output_search();
+                self.command_output_state.clear_search();
+                return true;
+            }
+            (KeyCode::Char('g'), _) if key.modifiers == KeyModifiers::CONTROL => {
+                self.end_output_search();
+                self.command_output_state.clear_search();
+                return true;
+            }
+            (_, Some(Action::Edit(action))) => self.command_input_state.apply(action),
+            (KeyCode::Char(c), None) if !ctrl_or_alt => self.command_input_state.add_char(c),
+            _ => return true,
+        }
+
+        let pattern = self.command_input_state.content.clone();
+        let found = if pattern.is_empty() {
+            self.command_output_state.clear_search();
+            true
+        } else {
+            self.command_output_state.search(&pattern).unwrap_or(false)
+        };
+        self.command_input_state.prompt_override = Some(if found {
+            "/".to_string()
+        } else {
+            "(not found) /".to_string()
+        });
+        true
+    }
+
+    fn end_output_search(&mut self) {
+        if let Some(input) = self.output_search_input.take() {
+            let state = &mut self.command_input_state;
+            state.content = input.original.0;
+            state.cursor = input.original.1;
+            state.selection_anchor = None;
+            state.prompt_override = None;
+        }
+    }
+}
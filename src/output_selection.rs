@@ -0,0 +1,218 @@
+//! Selecting lines of the output with the keyboard or the mouse, e.g. to copy them to the clipboard
+
+use crate::focus::contains;
+use crate::output_search::line_text;
+use crate::{CommandOutputState, TuiClap};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::cmp::{max, min};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OutputSelection {
+    /// Index of the line the selection was started at
+    pub(crate) anchor: usize,
+    /// Index of the line the selection is extended to
+    pub(crate) cursor: usize,
+}
+
+impl CommandOutputState {
+    /// Selects the newest line shown in the view, to move the selection with `select_up` and `select_down`
+    pub fn start_selection(&mut self) {
+        let line = self
+            .rendered_rows
+            .iter()
+            .rev()
+            .find_map(|&index| index)
+            .or_else(|| (0..self.history.len()).rev().find(|&i| self.is_shown(i)));
+        self.selection = line.map(|line| OutputSelection {
+            anchor: line,
+            cursor: line,
+        });
+        self.reveal_line = line;
+    }
+
+    pub fn is_selecting(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    /// Moves the selection to the previous shown line, or extends it if `extend` is set
+    pub fn select_up(&mut self, extend: bool) {
+        if let Some(selection) = self.selection {
+            let line = (0..selection.cursor).rev().find(|&i| self.is_shown(i));
+            self.move_selection(line, extend);
+        }
+    }
+
+    /// Moves the selection to the next shown line, or extends it if `extend` is set
+    pub fn select_down(&mut self, extend: bool) {
+        if let Some(selection) = self.selection {
+            let line = (selection.cursor + 1..self.history.len()).find(|&i| self.is_shown(i));
+            self.move_selection(line, extend);
+        }
+    }
+
+    fn move_selection(&mut self, line: Option<usize>, extend: bool) {
+        if let (Some(selection), Some(line)) = (&mut self.selection, line) {
+            selection.cursor = line;
+            if !extend {
+                selection.anchor = line;
+            }
+            self.reveal_line = Some(line);
+        }
+    }
+
+    /// The indices of the first and last selected line
+    pub fn selected_lines(&self) -> Option<(usize, usize)> {
+        self.selection.map(|selection| {
+            (
+                min(selection.anchor, selection.cursor),
+                max(selection.anchor, selection.cursor),
+            )
+        })
+    }
+
+    /// The text of the selected lines without styles, separated by line breaks. Lines hidden by a filter are left out.
+    pub fn selected_text(&self) -> Option<String> {
+        let (first, last) = self.selected_lines()?;
+        let lines: Vec<String> = (first..=last)
+            .filter(|&i| self.is_shown(i))
+            .map(|i| line_text(&self.history[i].spans))
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    /// Selects the line shown in `row` of the terminal by the last render, or extends the selection to it if `extend`
+    /// is set. Rows above or below the view scroll it by a row and select the line at its edge instead.
+    /// Returns `false` if no line is shown in the row.
+    pub fn select_row(&mut self, row: u16, extend: bool) -> bool {
+        let area = self.area;
+        if area.height <= 1 {
+            return false;
+        }
+        // the view does not cover the last row of the area
+        let last = area.bottom() - 2;
+        if row < area.top() || row > last {
+            if !extend {
+                return false;
+            } else if row < area.top() {
+                self.scroll_up(1);
+            } else {
+                self.scroll_down(1);
+            }
+        }
+        let y = (row.clamp(area.top(), last) - area.top()) as usize;
+        let line = match self.rendered_rows.get(y) {
+            Some(&Some(line)) => line,
+            _ => return false,
+        };
+        match &mut self.selection {
+            Some(selection) if extend => selection.cursor = line,
+            _ => {
+                self.selection = Some(OutputSelection {
+                    anchor: line,
+                    cursor: line,
+                })
+            }
+        }
+        true
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Whether the line at `index` is selected
+    pub(crate) fn is_selected(&self, index: usize) -> bool {
+        self.selected_lines()
+            .is_some_and(|(first, last)| (first..=last).contains(&index))
+    }
+}
+
+impl TuiClap {
+    /// Handles `key` while selecting lines of the output. `Up` and `Down` move the selection, together with `Shift`
+    /// they extend it. `Tab` or `z` folds or unfolds the section of the line, see `CommandOutputState::toggle_section`.
+    /// `Enter` activates the line with the cursor if it has a payload, see `TuiClap::on_line_activated`.
+    /// Otherwise `Enter` or `y` copies the selected lines to the clipboard, if the `clipboard` feature is enabled,
+    /// and ends the selection, `Esc` or `q` end it without copying. Other keys are ignored.
+    /// Returns `false` if no lines are selected.
+    pub(crate) fn handle_output_selection_key(&mut self, key: KeyEvent) -> bool {
+        let state = &mut self.command_output_state;
+        if !state.is_selecting() {
+            return false;
+        }
+        let extend = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => state.select_up(extend),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J') => state.select_down(extend),
+            KeyCode::Enter
+                if state
+                    .selection
+                    .is_some_and(|selection| state.payload(selection.cursor).is_some()) =>
+            {
+                if let Some(selection) = state.selection {
+                    self.activate_line(selection.cursor);
+                }
+                self.command_output_state.clear_selection();
+            }
+            KeyCode::Enter | KeyCode::Char('y') => {
+                #[cfg(feature = "clipboard")]
+                if let Err(err) = self.copy_output_selection() {
+                    self.write_error(format!("clipboard: {}", err));
+                }
+                self.command_output_state.clear_selection();
+            }
+            KeyCode::Tab | KeyCode::Char('z') => {
+                if let Some(selection) = state.selection {
+                    state.toggle_section(selection.cursor);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => state.clear_selection(),
+            _ => {}
+        }
+        true
+    }
+
+    /// Handles `event` selecting lines of the output. Clicking a line selects it and dragging extends the selection,
+    /// which is kept after releasing the button to act on it with the keys of `handle_output_selection_key`.
+    /// Clicking a line with a payload activates it, see `TuiClap::on_line_activated`. Clicking elsewhere ends the selection. Returns `false` if the event is not a left button event.
+    pub(crate) fn handle_output_selection_mouse(&mut self, event: MouseEvent) -> bool {
+        let state = &mut self.command_output_state;
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if !contains(state.area, &event) || !state.select_row(event.row, false) {
+                    state.clear_selection();
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) if state.is_selecting() => {
+                state.select_row(event.row, true);
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                // a click without dragging activates the line
+                if let Some(selection) = state.selection {
+                    let area = state.area;
+                    let clicked = event
+                        .row
+                        .checked_sub(area.top())
+                        .and_then(|y| state.rendered_rows.get(y as usize).copied().flatten());
+                    if selection.anchor == selection.cursor
+                        && clicked == Some(selection.cursor)
+                        && self.activate_line(selection.cursor)
+                    {
+                        self.command_output_state.clear_selection();
+                    }
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Copies the selected lines of the output to the system clipboard
+    #[cfg(feature = "clipboard")]
+    pub fn copy_output_selection(&mut self) -> Result<(), arboard::Error> {
+        match self.command_output_state.selected_text() {
+            Some(text) => self.clipboard.set_text(&text),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Completion of file and directory paths
+
+use crate::completion::typed_word;
+use crate::{Candidate, Completer};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// A `Completer` for paths of files and directories, relative to the working directory or absolute.
+/// A leading `~` is expanded to the home directory, completed directories end with a `/` and hidden entries
+/// are only completed if the typed name starts with a `.`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathCompleter {
+    directories_only: bool,
+}
+
+impl PathCompleter {
+    /// Completes paths of files and directories
+    pub fn files() -> PathCompleter {
+        PathCompleter {
+            directories_only: false,
+        }
+    }
+
+    /// Completes paths of directories only
+    pub fn directories() -> PathCompleter {
+        PathCompleter {
+            directories_only: true,
+        }
+    }
+}
+
+impl Completer for PathCompleter {
+    fn complete(&self, line: &str, cursor: usize) -> Vec<Candidate> {
+        let word = typed_word(line, cursor);
+        let word = word.as_str();
+        // `TuiClap` completes the value of `--option=value` behind the `=`
+        let path = match word.find('=') {
+            Some(i) if word.starts_with("--") => &word[i + 1..],
+            _ => word,
+        };
+        let (directory, name) = match path.rfind(is_separator) {
+            Some(i) => path.split_at(i + 1),
+            None => ("", path),
+        };
+
+        let entries = match fs::read_dir(expand_home(directory)) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let file_name = entry.file_name().into_string().ok()?;
+                if !file_name.starts_with(name)
+                    || (file_name.starts_with('.') && !name.starts_with('.'))
+                {
+                    return None;
+                }
+                // follows symlinks, so that links to directories are completed like directories
+                let is_dir = entry.path().is_dir();
+                if self.directories_only && !is_dir {
+                    return None;
+                }
+                let separator = if is_dir { "/" } else { "" };
+                Some(Candidate::new(format!(
+                    "{}{}{}",
+                    directory, file_name, separator
+                )))
+            })
+            .collect()
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == std::path::MAIN_SEPARATOR
+}
+
+/// Replaces a leading `~` of `directory` with the home directory, the working directory is used for an empty one
+fn expand_home(directory: &str) -> PathBuf {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    match (directory.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(is_separator) => {
+            PathBuf::from(home).join(rest.trim_start_matches(is_separator))
+        }
+        _ if directory.is_empty() => PathBuf::from("."),
+        _ => PathBuf::from(directory),
+    }
+}
@@ -0,0 +1,168 @@
+//! Piping the output of a command through filters like `grep`, e.g. `logs | grep error | head 20`
+
+use crate::output::Level;
+use crate::tokenizer::{split_pipeline, tokenize};
+use crate::{CommandOutputState, TuiClap};
+use clap::ArgMatches;
+use regex::RegexBuilder;
+use std::collections::BTreeMap;
+use tui::text::Spans;
+
+/// A filter of the lines written by a command, see `TuiClap::add_filter`
+pub(crate) type Filter = Box<dyn FnMut(&[String], Vec<String>) -> Result<Vec<String>, String>>;
+
+/// The filters every `TuiClap` starts with
+pub(crate) fn default_filters() -> BTreeMap<String, Filter> {
+    let mut filters: BTreeMap<String, Filter> = BTreeMap::new();
+    filters.insert("grep".to_string(), Box::new(grep));
+    filters.insert(
+        "head".to_string(),
+        Box::new(|args, lines| {
+            let count = count("head", args)?;
+            Ok(lines.into_iter().take(count).collect())
+        }),
+    );
+    filters.insert(
+        "tail".to_string(),
+        Box::new(|args, lines| {
+            let count = count("tail", args)?;
+            let skipped = lines.len().saturating_sub(count);
+            Ok(lines.into_iter().skip(skipped).collect())
+        }),
+    );
+    filters
+}
+
+/// `grep [-i] [-v] <pattern>` keeps the lines matching the regular expression `pattern`, ignoring case with `-i`,
+/// or the lines not matching it with `-v`
+fn grep(args: &[String], lines: Vec<String>) -> Result<Vec<String>, String> {
+    let mut ignore_case = false;
+    let mut invert = false;
+    let mut pattern = None;
+    for arg in args {
+        match arg.as_str() {
+            "-i" => ignore_case = true,
+            "-v" => invert = true,
+            _ if pattern.is_none() => pattern = Some(arg),
+            _ => return Err(format!("grep: unexpected argument '{}'", arg)),
+        }
+    }
+    let pattern = pattern.ok_or_else(|| "grep: missing pattern".to_string())?;
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|err| format!("grep: {}", err))?;
+    Ok(lines
+        .into_iter()
+        .filter(|line| regex.is_match(line) != invert)
+        .collect())
+}
+
+/// The number of lines `head` and `tail` keep, 10 by default
+fn count(name: &str, args: &[String]) -> Result<usize, String> {
+    match args {
+        [] => Ok(10),
+        [count] => count
+            .parse()
+            .map_err(|err| format!("{}: {}: {}", name, count, err)),
+        _ => Err(format!("{}: too many arguments", name)),
+    }
+}
+
+impl CommandOutputState {
+    /// Starts capturing the text of the lines written to the output instead of showing them, except for errors
+    pub(crate) fn start_capture(&mut self) {
+        self.captures.push(vec![]);
+    }
+
+    /// Stops the innermost capture, returning the captured lines
+    pub(crate) fn stop_capture(&mut self) -> Vec<String> {
+        self.captures.pop().unwrap_or_default()
+    }
+
+    /// Captures the text of `line` if capturing and it is no error, returns `false` otherwise
+    pub(crate) fn capture(&mut self, line: &Spans, level: Level) -> bool {
+        match self.captures.last_mut() {
+            Some(capture) if level != Level::Error => {
+                let text = line.0.iter().map(|span| span.content.as_ref()).collect();
+                capture.push(text);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl TuiClap {
+    /// Registers the filter `name`, replacing any previous filter of that name. Commands can pipe their output through
+    /// filters like `logs | grep error | head 20`: the filter is called with its arguments and the text of the lines
+    /// written by the command, or the previous filter, and returns the lines to write instead.
+    /// `grep [-i] [-v] <pattern>`, `head [n]` and `tail [n]` are registered by default.
+    pub fn add_filter(
+        &mut self,
+        name: &str,
+        filter: impl FnMut(&[String], Vec<String>) -> Result<Vec<String>, String> + 'static,
+    ) {
+        self.filters.insert(name.to_string(), Box::new(filter));
+    }
+
+    /// Removes the filter `name`. Returns `false` if there is no such filter.
+    pub fn remove_filter(&mut self, name: &str) -> bool {
+        self.filters.remove(name).is_some()
+    }
+
+    /// Runs `command` and dispatches its matches like `TuiClap::execute`, passing the lines it writes through the
+    /// filters it is piped into. Returns the matches if no handler is registered for them and there are no filters.
+    pub(crate) fn run_pipeline(&mut self, command: &str) -> Option<ArgMatches> {
+        let mut stages = split_pipeline(command).into_iter();
+        let command = stages.next().unwrap_or_default();
+        let filters: Vec<&str> = stages.collect();
+        if filters.is_empty() {
+            return self.run_and_dispatch(command);
+        }
+
+        self.command_output_state.start_capture();
+        let unhandled = self.run_and_dispatch(command);
+        let mut lines = self.command_output_state.stop_capture();
+        if unhandled.is_some() {
+            self.write_error(format!(
+                "error: '{}' has no handler whose output can be piped",
+                command.trim()
+            ));
+            self.command_input_state.report_success(false);
+            return None;
+        }
+        for filter in filters {
+            match self.apply_filter(filter, lines) {
+                Ok(filtered) => lines = filtered,
+                Err(err) => {
+                    self.write_error(err);
+                    self.command_input_state.report_success(false);
+                    return None;
+                }
+            }
+        }
+        for line in lines {
+            self.write_to_output(line);
+        }
+        None
+    }
+
+    /// Passes `lines` through the filter `filter`, given by its name and arguments
+    pub(crate) fn apply_filter(
+        &mut self,
+        filter: &str,
+        lines: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        let filter = self.substitute_variables(filter)?;
+        let words = tokenize(&filter).map_err(|err| format!("error: {}", err))?;
+        let (name, args) = words
+            .split_first()
+            .ok_or_else(|| "error: missing filter behind '|'".to_string())?;
+        let filter = self
+            .filters
+            .get_mut(name)
+            .ok_or_else(|| format!("error: unknown filter '{}'", name))?;
+        filter(args, lines)
+    }
+}
@@ -0,0 +1,165 @@
+//! Dry runs of the input, showing what clap resolves a command to without running it
+
+use crate::diagnostics::describe;
+use crate::tokenizer::{split_commands, split_pipeline, tokenize};
+use crate::TuiClap;
+use clap::error::Error;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+/// What a command would do if it was run, see `TuiClap::dry_run`
+pub(crate) enum DryRun {
+    /// Nothing, since the command is empty
+    Empty,
+    /// Run the built-in command of this name
+    Builtin(String),
+    /// Enter the scope of a subcommand, see `TuiClap::set_scopes`
+    Scope,
+    /// Dispatch these matches
+    Matches(ArgMatches),
+    /// Fail to parse
+    Failed(Error),
+    /// Fail to split the command into words
+    Invalid(String),
+    /// Fail to substitute a variable, which might be set by a command run before
+    Unresolved(String),
+}
+
+/// `arg` as given in a command line, e.g. `<name>` or `--force`
+fn label(arg: &Arg) -> String {
+    match (arg.get_long(), arg.get_short()) {
+        _ if arg.is_positional() => format!("<{}>", arg.get_id()),
+        (Some(long), _) => format!("--{}", long),
+        (None, Some(short)) => format!("-{}", short),
+        (None, None) => arg.get_id().to_string(),
+    }
+}
+
+/// Appends the name of the subcommand `matches` resolved to to `path` and the values of its arguments and the
+/// arguments of its parents to `lines`, one per argument. Flags which were not given and global arguments
+/// propagated to subcommands are left out.
+fn describe_matches(
+    command: &Command,
+    matches: &ArgMatches,
+    path: &mut Vec<String>,
+    lines: &mut Vec<String>,
+) {
+    path.push(command.get_name().to_string());
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let flag = matches!(
+            arg.get_action(),
+            ArgAction::SetTrue | ArgAction::SetFalse | ArgAction::Count
+        );
+        let source = matches.value_source(id);
+        if matches!(arg.get_action(), ArgAction::Help | ArgAction::Version)
+            || source.is_none()
+            || (flag && source == Some(ValueSource::DefaultValue))
+            || (arg.is_global_set() && path.len() > 1)
+        {
+            continue;
+        }
+        let values: Vec<String> = match matches.try_get_raw(id) {
+            Ok(Some(values)) => values
+                .map(|value| {
+                    if flag {
+                        value.to_string_lossy().into_owned()
+                    } else {
+                        format!("{:?}", value.to_string_lossy())
+                    }
+                })
+                .collect(),
+            _ => continue,
+        };
+        let value = match values.as_slice() {
+            [value] => value.clone(),
+            values => format!("[{}]", values.join(", ")),
+        };
+        let default = match source {
+            Some(ValueSource::DefaultValue) => " (default)",
+            _ => "",
+        };
+        lines.push(format!("  {} = {}{}", label(arg), value, default));
+    }
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        if let Some(subcommand) = command.find_subcommand(name) {
+            describe_matches(subcommand, sub_matches, path, lines);
+        }
+    }
+}
+
+impl TuiClap {
+    /// Writes what each command of the input resolves to without running it and keeps the input, e.g. to verify a
+    /// complex command: the subcommand and the values clap parsed for its arguments, the built-in command it runs
+    /// or why it fails to parse. Done on `ReplAction::Preview`, bound to `Alt+P`.
+    pub fn preview(&mut self) {
+        let content = self.command_input_state.content.clone();
+        for (command, _, _) in split_commands(&content) {
+            let command = split_pipeline(command)
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            match self.dry_run(command) {
+                DryRun::Empty => {}
+                DryRun::Builtin(name) => {
+                    self.write_to_output(format!("{}: built-in command", name));
+                }
+                DryRun::Scope => {
+                    self.write_to_output(format!("{}: enters the scope", command.trim()));
+                }
+                DryRun::Matches(matches) => {
+                    let mut path = vec![];
+                    let mut arguments = vec![];
+                    describe_matches(&self.clap, &matches, &mut path, &mut arguments);
+                    self.write_to_output(path.join(" "));
+                    for argument in arguments {
+                        self.write_to_output(argument);
+                    }
+                }
+                DryRun::Failed(err) => {
+                    if let Some(err) = describe(&err) {
+                        self.write_error(format!("error: {}", err));
+                    }
+                }
+                DryRun::Invalid(err) => self.write_error(format!("error: {}", err)),
+                DryRun::Unresolved(err) => self.write_error(err),
+            }
+        }
+    }
+
+    /// Parses the content of the input widget like `TuiClap::parse`, but without consuming it or writing anything,
+    /// e.g. to show what the command would do. `None` if it fails to parse or is a built-in command.
+    pub fn peek_parse(&self) -> Option<ArgMatches> {
+        match self.dry_run(&self.command_input_state.content) {
+            DryRun::Matches(matches) => Some(matches),
+            _ => None,
+        }
+    }
+
+    /// What `command`, one command of a chain without filters, would do if it was run, parsing it with a copy of
+    /// the clap app, so that the app is not changed
+    pub(crate) fn dry_run(&self, command: &str) -> DryRun {
+        let command = match self.substitute_variables(&self.expand_alias(command)) {
+            Ok(command) => command,
+            Err(err) => return DryRun::Unresolved(err),
+        };
+        let words = match tokenize(&command) {
+            Ok(words) => words,
+            Err(err) => return DryRun::Invalid(err.to_string()),
+        };
+        match words.first() {
+            None => return DryRun::Empty,
+            Some(name) if self.is_builtin(name) => return DryRun::Builtin(name.clone()),
+            Some(_) if self.enters_scope(&words) => return DryRun::Scope,
+            Some(_) => {}
+        }
+        match self
+            .clap
+            .clone()
+            .try_get_matches_from(self.command_words(words))
+        {
+            Ok(matches) => DryRun::Matches(matches),
+            Err(err) => DryRun::Failed(err),
+        }
+    }
+}
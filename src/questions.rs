@@ -0,0 +1,102 @@
+//! Follow-up questions asked from handlers, whose answers are the next lines entered instead of commands
+
+use crate::{CommandResult, TuiClap};
+use std::collections::VecDeque;
+use std::mem;
+
+/// Receives the answer to a question asked with `TuiClap::ask`.
+/// Returns `None` if it reported the status of the command itself, e.g. by dispatching a command.
+pub(crate) type AnswerHandler = Box<dyn FnOnce(&str, &mut TuiClap) -> Option<CommandResult>>;
+
+struct Question {
+    question: String,
+    handler: AnswerHandler,
+}
+
+/// The questions waiting for an answer, the first one being asked at the moment
+#[derive(Default)]
+pub(crate) struct Questions {
+    pending: VecDeque<Question>,
+    /// The prompt replaced by the question being asked, `None` if no question is asked
+    saved_prompt: Option<String>,
+}
+
+impl TuiClap {
+    /// Asks the user `question`, e.g. `Which node? [1-3] `, from a handler for multi-step flows like a login.
+    /// The question replaces the prompt until the next line is entered, which is passed to `answer` instead of being
+    /// run as a command and is not recorded in the history. The lines `answer` returns are written to the output,
+    /// an error to the error stream, and `answer` may ask the next question. Questions asked before the previous one
+    /// was answered are asked in turn. `ReplAction::Interrupt` discards the pending questions.
+    pub fn ask(
+        &mut self,
+        question: &str,
+        answer: impl FnOnce(&str, &mut TuiClap) -> Result<Vec<String>, String> + 'static,
+    ) {
+        self.ask_with(
+            question,
+            Box::new(move |answer_line, tui| Some(answer(answer_line, tui).into())),
+        );
+    }
+
+    pub(crate) fn ask_with(&mut self, question: &str, handler: AnswerHandler) {
+        self.questions.pending.push_back(Question {
+            question: question.to_string(),
+            handler,
+        });
+        self.show_question();
+    }
+
+    /// Whether a question asked with `TuiClap::ask` waits for its answer
+    pub fn is_asking(&self) -> bool {
+        !self.questions.pending.is_empty()
+    }
+
+    /// Discards the questions waiting for an answer and restores the prompt
+    pub fn cancel_questions(&mut self) {
+        self.questions.pending.clear();
+        self.hide_question();
+    }
+
+    /// Passes the content of the input widget to the handler of the question being asked, echoing it behind the
+    /// question. Returns `false` if no question is asked.
+    pub(crate) fn answer_question(&mut self) -> bool {
+        if !self.is_asking() {
+            return false;
+        }
+        let answer = self.command_input_state.content().to_string();
+        self.command_input_state.reset();
+        self.command_output_state.start_command();
+        self.echo_command(&answer);
+
+        // the prompt is restored while the handler runs, so that it can switch the mode or ask the next question
+        self.hide_question();
+        if let Some(question) = self.questions.pending.pop_front() {
+            if let Some(result) = (question.handler)(&answer, self) {
+                self.command_input_state.report_status(result.status);
+                self.write_result(result);
+            }
+        }
+        self.show_question();
+        true
+    }
+
+    /// Replaces the prompt by the question asked first, unless it is shown already
+    fn show_question(&mut self) {
+        if self.questions.saved_prompt.is_some() {
+            return;
+        }
+        if let Some(question) = self.questions.pending.front() {
+            let prompt = mem::replace(
+                &mut self.command_input_widget.prompt,
+                question.question.clone(),
+            );
+            self.questions.saved_prompt = Some(prompt);
+        }
+    }
+
+    fn hide_question(&mut self) {
+        if let Some(prompt) = self.questions.saved_prompt.take() {
+            self.command_input_widget.prompt = prompt;
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Changes of the terminal size, passed on to the application and dropping what the last render laid out
+
+use crate::{CommandOutputState, TuiClap};
+
+/// The size of the terminal as reported by the last resize event
+#[derive(Default)]
+pub(crate) struct Resize {
+    /// Columns and rows of the terminal, `None` before the first resize event
+    size: Option<(u16, u16)>,
+    /// Whether the size changed since `TuiClap::take_resize` was called
+    pending: bool,
+}
+
+impl CommandOutputState {
+    /// Drops the rows and hyperlinks of the last render and the cached row counts of the lines, which no longer match
+    /// the screen after its size changed, keeping the line at the top of the view in place if scrolled up
+    pub(crate) fn invalidate_layout(&mut self) {
+        if self.scroll > 0 && self.scroll_to_line.is_none() {
+            self.scroll_to_line = self.rendered_rows.iter().find_map(|&index| index);
+        }
+        self.rendered_rows.clear();
+        self.rendered_links.clear();
+        self.area = Default::default();
+        self.clear_row_counts();
+    }
+}
+
+impl TuiClap {
+    /// Handles the terminal being resized to `width` columns and `height` rows, e.g. on `crossterm::event::Event::Resize`.
+    /// Called by `TuiClap::fetch_event`. The rows of the output are wrapped anew by the next render, the line at the top
+    /// of a scrolled view stays in place, and mouse events are ignored by the output until then.
+    pub fn handle_resize(&mut self, width: u16, height: u16) {
+        self.command_output_state.invalidate_layout();
+        self.command_error_state.invalidate_layout();
+        if self.resize.size != Some((width, height)) {
+            self.resize.size = Some((width, height));
+            self.resize.pending = true;
+        }
+    }
+
+    /// The new size of the terminal as columns and rows if it changed since the last call, to recompute the layout
+    /// exactly when needed. See `TuiClap::handle_resize`.
+    pub fn take_resize(&mut self) -> Option<(u16, u16)> {
+        if !self.resize.pending {
+            return None;
+        }
+        self.resize.pending = false;
+        self.resize.size
+    }
+
+    /// The size of the terminal as columns and rows reported by the last resize event, `None` if there was none
+    pub fn terminal_size(&self) -> Option<(u16, u16)> {
+        self.resize.size
+    }
+}
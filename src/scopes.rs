@@ -0,0 +1,151 @@
+//! Entering the scope of a subcommand, so that the following lines are parsed as its arguments, like `cd` in a shell
+
+use crate::tokenizer::tokenize;
+use crate::TuiClap;
+use clap::Command;
+
+#[derive(Default)]
+pub(crate) struct Scopes {
+    /// Whether typing a subcommand which has subcommands itself enters its scope
+    enabled: bool,
+    /// The path of the subcommand whose scope was entered, empty at the top level
+    path: Vec<String>,
+    /// The prompt replaced by the one showing the path, `None` at the top level
+    prompt: Option<String>,
+}
+
+/// `prompt` with `path` inserted in front of its trailing symbol, e.g. `app remote> ` for `app> `
+fn scoped_prompt(prompt: &str, path: &str) -> String {
+    let stem = prompt
+        .trim_end()
+        .trim_end_matches(['>', '#', '$', '%', ':']);
+    let stem = stem.trim_end();
+    if stem.is_empty() {
+        format!("{}{}", path, &prompt[stem.len()..])
+    } else {
+        format!("{} {}{}", stem, path, &prompt[stem.len()..])
+    }
+}
+
+impl TuiClap {
+    /// Sets whether typing a subcommand which has subcommands itself, e.g. `config`, enters its scope instead of
+    /// running it, see `TuiClap::enter_scope`. Registers the built-in command `..` leaving the scope. Disabled by default.
+    pub fn set_scopes(&mut self, enabled: bool) {
+        self.scopes.enabled = enabled;
+        if enabled {
+            self.add_builtin("..", "Leave the scope of the subcommand", |_, tui| {
+                tui.leave_scope();
+                Ok(vec![])
+            });
+        } else {
+            self.remove_builtin("..");
+            self.leave_scopes();
+        }
+    }
+
+    /// Enters the scope of the subcommand `path`, e.g. `config` or `remote add`, relative to the current scope,
+    /// so that the following lines are parsed as its arguments and subcommands. The prompt shows the path, e.g.
+    /// `app config> `, until `..` or `exit` leaves the scope. Returns `false` if `path` is no subcommand
+    /// with subcommands.
+    pub fn enter_scope(&mut self, path: &str) -> bool {
+        let names: Vec<&str> = path.split_whitespace().collect();
+        match self.subcommand_scope(&names) {
+            Some(names) => {
+                self.scopes.path.extend(names);
+                self.show_scope_prompt();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Leaves the scope entered last. Returns `false` at the top level.
+    pub fn leave_scope(&mut self) -> bool {
+        if self.scopes.path.pop().is_none() {
+            return false;
+        }
+        self.show_scope_prompt();
+        true
+    }
+
+    /// Leaves all scopes, e.g. before switching the mode
+    pub fn leave_scopes(&mut self) {
+        self.scopes.path.clear();
+        self.show_scope_prompt();
+    }
+
+    /// The path of the subcommand whose scope was entered, empty at the top level
+    pub fn scope(&self) -> &[String] {
+        &self.scopes.path
+    }
+
+    /// The app or subcommand whose scope was entered, which the input is parsed against
+    pub(crate) fn scoped_app(&self) -> &Command {
+        self.scopes.path.iter().fold(&self.clap, |command, name| {
+            command.find_subcommand(name).unwrap_or(command)
+        })
+    }
+
+    /// Whether the input starts with the binary name, which is not typed within a scope, see `TuiClap::set_binary_name`
+    pub(crate) fn typed_binary_name(&self) -> bool {
+        self.binary_name && self.scopes.path.is_empty()
+    }
+
+    /// The words of a command line prefixed with the binary name, unless it was typed, and the path of the scope
+    pub(crate) fn command_words(&self, words: Vec<String>) -> Vec<String> {
+        if self.typed_binary_name() {
+            return words;
+        }
+        let mut prefixed = vec![self.clap.get_name().to_string()];
+        prefixed.extend(self.scopes.path.iter().cloned());
+        prefixed.extend(words);
+        prefixed
+    }
+
+    /// The names of the subcommand `path` relative to the current scope, if it has subcommands itself
+    fn subcommand_scope(&self, path: &[&str]) -> Option<Vec<String>> {
+        let mut command = self.scoped_app();
+        let mut names = vec![];
+        for name in path {
+            command = command.find_subcommand(name)?;
+            names.push(command.get_name().to_string());
+        }
+        (!names.is_empty() && command.has_subcommands()).then_some(names)
+    }
+
+    /// Whether the command line `words` enters a scope since scopes are enabled and it is a subcommand
+    /// with subcommands
+    pub(crate) fn enters_scope(&self, words: &[String]) -> bool {
+        let skip = if self.typed_binary_name() { 1 } else { 0 };
+        let path: Vec<&str> = words.iter().skip(skip).map(String::as_str).collect();
+        self.scopes.enabled && self.subcommand_scope(&path).is_some()
+    }
+
+    /// Enters the scope of the subcommand `content` if it enters a scope, see `TuiClap::set_scopes`,
+    /// reporting the success to the history. Returns `false` otherwise.
+    pub(crate) fn enter_typed_scope(&mut self, content: &str) -> bool {
+        let words = match tokenize(content) {
+            Ok(words) if self.enters_scope(&words) => words,
+            _ => return false,
+        };
+        let skip = if self.typed_binary_name() { 1 } else { 0 };
+        self.enter_scope(&words[skip..].join(" "));
+        self.command_input_state.report_success(true);
+        true
+    }
+
+    fn show_scope_prompt(&mut self) {
+        if self.scopes.path.is_empty() {
+            if let Some(prompt) = self.scopes.prompt.take() {
+                self.command_input_widget.prompt = prompt;
+            }
+            return;
+        }
+        let widget = &mut self.command_input_widget;
+        let prompt = self
+            .scopes
+            .prompt
+            .get_or_insert_with(|| widget.prompt.clone());
+        widget.prompt = scoped_prompt(prompt, &self.scopes.path.join(" "));
+    }
+}
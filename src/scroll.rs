@@ -0,0 +1,215 @@
+//! Scrolling of the output back to earlier lines
+
+use crate::focus::contains;
+use crate::{CommandOutput, CommandOutputState, TuiClap};
+use crossterm::event::{KeyModifiers, MouseEvent, MouseEventKind};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Style;
+
+/// Number of rows scrolled by a step of the mouse wheel
+pub(crate) const WHEEL_ROWS: usize = 3;
+
+impl CommandOutputState {
+    /// Scrolls `rows` rows up towards older output
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_add(rows);
+    }
+
+    /// Scrolls `rows` rows down towards newer output
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    /// Scrolls down to the newest output
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// Sets whether the output follows new lines, scrolling down to them whenever a line is written.
+    /// Otherwise, which is the default, the view only follows new lines while it shows the newest output and
+    /// stays in place while scrolled up, indicating the number of lines written meanwhile.
+    pub fn follow(&mut self, enabled: bool) {
+        self.follow = enabled;
+        if enabled {
+            self.scroll = 0;
+        }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.follow
+    }
+
+    /// Number of lines written while scrolled up, which are not shown yet
+    pub fn new_lines(&self) -> usize {
+        self.new_lines
+    }
+
+    /// Number of rows the output is scrolled up from the newest output
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll
+    }
+
+    /// Number of rows shown by the last render, used as the page size
+    fn page_height(&self) -> usize {
+        self.height.max(1)
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_height());
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.page_height());
+    }
+
+    pub fn half_page_up(&mut self) {
+        self.scroll_up(self.page_height().div_ceil(2));
+    }
+
+    pub fn half_page_down(&mut self) {
+        self.scroll_down(self.page_height().div_ceil(2));
+    }
+
+    /// Scrolls `columns` columns to the left in `WrapMode::None`
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.horizontal_scroll = self.horizontal_scroll.saturating_sub(columns);
+    }
+
+    /// Scrolls `columns` columns to the right in `WrapMode::None`, at most until the widest line of the view ends
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.horizontal_scroll = self.horizontal_scroll.saturating_add(columns);
+    }
+
+    /// Number of columns the output is scrolled to the right
+    pub fn horizontal_scroll_offset(&self) -> usize {
+        self.horizontal_scroll
+    }
+
+    /// Number of columns scrolled horizontally by a key, a quarter of the width of the last render
+    pub(crate) fn horizontal_step(&self) -> usize {
+        (self.width / 4).max(1)
+    }
+}
+
+impl CommandOutput {
+    /// Sets whether a scrollbar is shown on the right edge of the widget while the output does not fit into it,
+    /// its thumb reflecting the position and size of the view in all rows of the output. Disabled by default.
+    pub fn scrollbar(&mut self, enabled: bool) {
+        self.scrollbar = enabled;
+    }
+
+    /// Sets the style of the scrollbar, dark gray by default
+    pub fn scrollbar_style(&mut self, style: Style) {
+        self.scrollbar_style = style;
+    }
+
+    /// Draws the scrollbar into the last column of the rows of the view, given the number of rows above it,
+    /// its height and the number of all rows
+    pub(crate) fn render_scrollbar(
+        &self,
+        area: Rect,
+        buf: &mut Buffer,
+        rows_above: usize,
+        height: usize,
+        rows: usize,
+    ) {
+        if height == 0 || rows <= height || area.width == 0 {
+            return;
+        }
+        let thumb_height = (height * height / rows).max(1);
+        // the thumb only touches the top or bottom while the view shows the oldest or newest rows
+        let track = height - thumb_height;
+        let thumb_top = match rows_above {
+            0 => 0,
+            above if above + height >= rows => track,
+            above => (above * track / (rows - height)).clamp(1, track.saturating_sub(1).max(1)),
+        };
+        let x = area.right() - 1;
+        for row in 0..height {
+            let symbol = if (thumb_top..thumb_top + thumb_height).contains(&row) {
+                "█"
+            } else {
+                "│"
+            };
+            buf.get_mut(x, area.top() + row as u16)
+                .set_symbol(symbol)
+                .set_style(self.scrollbar_style);
+        }
+    }
+}
+
+impl TuiClap {
+    /// Access the state of the output widget, e.g. to scroll it
+    pub fn output_state(&mut self) -> &mut CommandOutputState {
+        &mut self.command_output_state
+    }
+
+    /// Handles `event` if mouse capture is enabled, see `Config::mouse_capture`.
+    /// The mouse wheel scrolls the output by three rows, or horizontally together with `Shift`, or the error stream
+    /// if it is over it, see `TuiClap::set_separate_errors`, or the help popup if it is open.
+    /// Clicking the input or the output focuses it, see `TuiClap::set_focus`, and clicking and dragging with the left
+    /// button selects lines of the output, see `CommandOutputState::select_row`.
+    pub fn handle_mouse_event(&mut self, event: MouseEvent) {
+        if self.handle_help_popup_mouse(event) {
+            return;
+        }
+        self.handle_focus_mouse(&event);
+        if self.handle_output_selection_mouse(event) {
+            return;
+        }
+        let state = if contains(self.command_error_state.area, &event) {
+            &mut self.command_error_state
+        } else {
+            &mut self.command_output_state
+        };
+        let horizontal = event.modifiers.contains(KeyModifiers::SHIFT);
+        match event.kind {
+            MouseEventKind::ScrollUp if horizontal => state.scroll_left(state.horizontal_step()),
+            MouseEventKind::ScrollDown if horizontal => state.scroll_right(state.horizontal_step()),
+            MouseEventKind::ScrollUp => state.scroll_up(WHEEL_ROWS),
+            MouseEventKind::ScrollDown => state.scroll_down(WHEEL_ROWS),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TuiClap;
+    use clap::Command;
+    use tui::backend::TestBackend;
+    use tui::Terminal;
+
+    fn render(tui: &mut TuiClap, width: u16) {
+        let mut terminal = Terminal::new(TestBackend::new(width, 6)).unwrap();
+        terminal.draw(|f| tui.render_output(f, f.size())).unwrap();
+    }
+
+    fn cached_rows(tui: &mut TuiClap) -> Vec<Option<usize>> {
+        let state = tui.output_state();
+        state.history.iter().map(|line| line.rows.get()).collect()
+    }
+
+    #[test]
+    fn scrollbar_caches_rows_above_view_per_width() {
+        let mut tui = TuiClap::from_app(Command::new("app"));
+        tui.output_widget().scrollbar(true);
+        for _ in 0..20 {
+            tui.write_to_output("x".repeat(30));
+        }
+        // the view shows the 3 newest lines in 2 rows each, the rows of the lines above size the scrollbar
+        render(&mut tui, 21);
+        assert_eq!(cached_rows(&mut tui)[..17], [Some(2); 17]);
+
+        tui.write_to_output("x".repeat(30));
+        render(&mut tui, 21);
+        assert_eq!(cached_rows(&mut tui)[..18], [Some(2); 18]);
+        assert_eq!(cached_rows(&mut tui)[20], None);
+
+        render(&mut tui, 41);
+        assert_eq!(cached_rows(&mut tui)[..16], [Some(1); 16]);
+        tui.handle_resize(80, 24);
+        assert_eq!(cached_rows(&mut tui), vec![None; 21]);
+    }
+}
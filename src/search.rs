@@ -0,0 +1,220 @@
+//! Readline-style reverse incremental history search for `CommandInputState`
+
+use crate::fuzzy::fuzzy_match;
+use crate::CommandInputState;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone)]
+pub(crate) struct HistorySearch {
+    query: String,
+    /// Index of the history entry currently matching the query
+    found: Option<usize>,
+    /// Whether the last search found no entry, in which case the content shows the previous match
+    failed: bool,
+    /// Content and cursor before the search started, restored when it is cancelled
+    original: (String, usize),
+}
+
+impl CommandInputState {
+    /// Starts a reverse incremental search through the history.
+    /// If a search is already running, the next older entry matching the query is searched instead.
+    pub fn start_history_search(&mut self) {
+        match self.search.take() {
+            Some(mut search) => {
+                let before = search.found.unwrap_or(self.history.len());
+                self.search_history(&mut search, before);
+                self.search = Some(search);
+            }
+            None => {
+                self.selection_anchor = None;
+                self.search = Some(HistorySearch {
+                    query: String::new(),
+                    found: None,
+                    failed: false,
+                    original: (self.content.clone(), self.cursor),
+                })
+            }
+        }
+    }
+
+    /// Sets whether the history search matches the query fuzzily, so that `gst` finds `git status`
+    pub fn fuzzy_history_search(&mut self, fuzzy: bool) {
+        self.fuzzy_history_search = fuzzy;
+    }
+
+    pub fn is_searching_history(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// The prompt to render instead of the usual one while searching the history, e.g. ``(reverse-i-search)`foo': ``
+    pub fn history_search_prompt(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            let failed = if search.failed { "failed " } else { "" };
+            format!("({}reverse-i-search)`{}': ", failed, search.query)
+        })
+    }
+
+    /// The history search prompt cut to at most `width` columns, dropping the start of the query first,
+    /// e.g. ``(reverse-i-search)`…tatus': ``, so that the matched entry stays visible
+    pub(crate) fn fitted_history_search_prompt(&self, width: usize) -> Option<String> {
+        let search = self.search.as_ref()?;
+        let prompt = self.history_search_prompt()?;
+        if prompt.width() <= width {
+            return Some(prompt);
+        }
+        let failed = if search.failed { "failed " } else { "" };
+        let head = format!("({}reverse-i-search)`…", failed);
+        let tail = "': ";
+        let room = width.saturating_sub(head.width() + tail.width());
+        if room > 0 {
+            // the end of the query is kept, since it was typed last
+            let mut query = search.query.as_str();
+            while query.width() > room {
+                let first = query.graphemes(true).next().map_or(query.len(), str::len);
+                query = &query[first..];
+            }
+            return Some(format!("{}{}{}", head, query, tail));
+        }
+        let mut cut = String::new();
+        for grapheme in prompt.graphemes(true) {
+            if cut.width() + grapheme.width() > width {
+                break;
+            }
+            cut.push_str(grapheme);
+        }
+        Some(cut)
+    }
+
+    /// Ends the history search, keeping the matched entry as content
+    pub fn accept_history_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Ends the history search, restoring the content from before the search
+    pub fn cancel_history_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.content = search.original.0;
+            self.cursor = search.original.1;
+        }
+    }
+
+    /// Handles `key` while searching the history. Chars narrow the search, `Backspace` widens it again,
+    /// `Enter` accepts the match and `Esc` or `Ctrl+G` cancel the search.
+    /// Any other key accepts the match and returns `false`, so that the key is processed as usual.
+    /// Also returns `false` if no search is running.
+    pub fn handle_history_search_key(&mut self, key: KeyEvent) -> bool {
+        let mut search = match self.search.take() {
+            Some(search) => search,
+            None => return false,
+        };
+        let ctrl_or_alt = key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char(c) if !ctrl_or_alt => {
+                search.query.push(c);
+                // the current match may still contain the longer query
+                let before = search.found.map_or(self.history.len(), |found| found + 1);
+                self.search_history(&mut search, before);
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                self.search_history(&mut search, self.history.len());
+            }
+            KeyCode::Enter => return true,
+            KeyCode::Esc => {
+                self.search = Some(search);
+                self.cancel_history_search();
+                return true;
+            }
+            KeyCode::Char('g') if key.modifiers == KeyModifiers::CONTROL => {
+                self.search = Some(search);
+                self.cancel_history_search();
+                return true;
+            }
+            _ => return false,
+        }
+        self.search = Some(search);
+        true
+    }
+
+    /// Searches the newest history entry before index `before` that contains the query and shows it as content
+    fn search_history(&mut self, search: &mut HistorySearch, before: usize) {
+        if search.query.is_empty() {
+            search.failed = false;
+            return;
+        }
+        let found = self.history[..before]
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, entry)| {
+                let offset = if self.fuzzy_history_search {
+                    fuzzy_match(&search.query, &entry.command)
+                        .and_then(|(_, matches)| matches.first().copied())
+                } else {
+                    entry.command.rfind(&search.query)
+                };
+                offset.map(|offset| (index, offset))
+            });
+        match found {
+            Some((index, offset)) => {
+                search.found = Some(index);
+                search.failed = false;
+                self.content = self.history[index].command.clone();
+                self.cursor = offset;
+            }
+            None => search.failed = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CommandInput, CommandInputState};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tui::buffer::Buffer;
+    use tui::layout::Rect;
+    use tui::widgets::StatefulWidget;
+
+    fn searching(query: &str) -> CommandInputState {
+        let mut state = CommandInputState::default();
+        state.insert_str("git status --short");
+        state.enter();
+        state.start_history_search();
+        for c in query.chars() {
+            state.handle_history_search_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        state
+    }
+
+    #[test]
+    fn fitted_prompt_keeps_end_of_query() {
+        let state = searching("status");
+        assert_eq!(
+            state.fitted_history_search_prompt(80).as_deref(),
+            Some("(reverse-i-search)`status': ")
+        );
+        assert_eq!(
+            state.fitted_history_search_prompt(26).as_deref(),
+            Some("(reverse-i-search)`…tus': ")
+        );
+        assert_eq!(
+            state.fitted_history_search_prompt(10).as_deref(),
+            Some("(reverse-i")
+        );
+    }
+
+    #[test]
+    fn long_query_renders_in_narrow_area() {
+        for width in [5, 20, 30] {
+            let mut state = searching("git status --short");
+            let area = Rect::new(0, 0, width, 1);
+            let mut buf = Buffer::empty(area);
+            CommandInput::default().render(area, &mut buf, &mut state);
+            assert!(state.cursor_position().is_some_and(|(x, _)| x < width));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Folding the lines written by each command into a section showing only its first line
+
+use crate::{CommandOutput, CommandOutputState, TuiClap};
+use tui::style::Style;
+use tui::text::Span;
+
+impl CommandOutput {
+    /// Sets the style of the number of lines hidden behind the first line of a folded section, dark gray by default
+    pub fn section_style(&mut self, style: Style) {
+        self.section_style = style;
+    }
+
+    /// Appends the number of lines hidden in the folded section behind its first line to `spans`
+    pub(crate) fn show_folded<'a>(&self, folded: usize, mut spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+        let summary = match folded {
+            1 => " (+1 line)".to_string(),
+            n => format!(" (+{} lines)", n),
+        };
+        spans.push(Span::styled(summary, self.section_style));
+        spans
+    }
+}
+
+impl CommandOutputState {
+    /// Folds the section of the output written by the command the line at `index` belongs to, or unfolds it if it is
+    /// folded. A folded section only shows its first line, which is the entered command with
+    /// `TuiClap::set_echo_commands`, followed by the number of lines hidden behind it.
+    /// A section is started by each command, see `CommandOutputState::start_command`.
+    pub fn toggle_section(&mut self, index: usize) {
+        let section = match self.history.get(index) {
+            Some(line) => line.section,
+            None => return,
+        };
+        let folding = !self.folded.remove(&section);
+        // keeps the first line in the view and the selection on shown lines
+        let first = (0..=index)
+            .rev()
+            .take_while(|&i| self.history[i].section == section)
+            .last()
+            .unwrap_or(index);
+        if folding {
+            self.folded.insert(section);
+            if let Some(selection) = &mut self.selection {
+                for line in [&mut selection.anchor, &mut selection.cursor] {
+                    if self.history[*line].section == section {
+                        *line = first;
+                    }
+                }
+            }
+        }
+        self.reveal_line = Some(first);
+    }
+
+    /// Folds the sections of all commands, see `CommandOutputState::toggle_section`
+    pub fn fold_all_sections(&mut self) {
+        self.folded = self.history.iter().map(|line| line.section).collect();
+        self.selection = None;
+    }
+
+    pub fn unfold_all_sections(&mut self) {
+        self.folded.clear();
+    }
+
+    /// Whether the section of the line at `index` is folded
+    pub fn is_folded(&self, index: usize) -> bool {
+        self.history
+            .get(index)
+            .is_some_and(|line| self.folded.contains(&line.section))
+    }
+
+    /// Whether the line at `index` is hidden in a folded section, behind its first line
+    pub(crate) fn is_folded_away(&self, index: usize) -> bool {
+        let section = self.history[index].section;
+        index > 0 && self.history[index - 1].section == section && self.folded.contains(&section)
+    }
+
+    /// Number of lines hidden behind the line at `index` if it is the first line of a folded section
+    pub(crate) fn folded_lines(&self, index: usize) -> Option<usize> {
+        if !self.is_folded(index) || self.is_folded_away(index) {
+            return None;
+        }
+        let section = self.history[index].section;
+        let hidden = self
+            .history
+            .range(index + 1..)
+            .take_while(|line| line.section == section)
+            .count();
+        Some(hidden)
+    }
+}
+
+impl TuiClap {
+    /// Folds or unfolds the section of the newest command shown in the view of the output
+    pub(crate) fn toggle_output_section(&mut self) {
+        let state = &mut self.command_output_state;
+        let line = state
+            .rendered_rows
+            .iter()
+            .rev()
+            .find_map(|&index| index)
+            .or_else(|| state.len().checked_sub(1));
+        if let Some(line) = line {
+            state.toggle_section(line);
+        }
+    }
+}
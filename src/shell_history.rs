@@ -0,0 +1,122 @@
+//! Import and export of the command history in the formats of other shells
+
+use crate::{CommandInputState, HistoryEntry};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// A history file format of another shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// One command per line like `~/.bash_history`, optionally preceded by a `#<seconds since the unix epoch>` line
+    /// as written if `HISTTIMEFORMAT` is set. With timestamps, all lines up to the next timestamp form one command.
+    Bash,
+    /// The extended history format of zsh, `: <seconds since the unix epoch>:<duration>;<command>`,
+    /// with the lines of multi-line commands ending in a backslash
+    Zsh,
+}
+
+impl CommandInputState {
+    /// Reads the history file of another shell at `path` and appends its commands to the history like entered ones,
+    /// so that they are saved to the history file of `TuiClap` as well
+    pub fn import_history(
+        &mut self,
+        path: impl AsRef<Path>,
+        format: HistoryFormat,
+    ) -> io::Result<()> {
+        let content = fs::read(path)?;
+        let content = String::from_utf8_lossy(&content);
+        let entries = match format {
+            HistoryFormat::Bash => parse_bash(&content),
+            HistoryFormat::Zsh => parse_zsh(&content),
+        };
+        self.history.extend(entries);
+        self.trim_history();
+        Ok(())
+    }
+
+    /// Writes the whole history to `path` in the format of another shell, replacing the file
+    pub fn export_history(&self, path: impl AsRef<Path>, format: HistoryFormat) -> io::Result<()> {
+        let mut content = String::new();
+        for entry in &self.history {
+            let secs = entry
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            match format {
+                HistoryFormat::Bash => content.push_str(&format!("#{}\n{}\n", secs, entry.command)),
+                HistoryFormat::Zsh => content.push_str(&format!(
+                    ": {}:0;{}\n",
+                    secs,
+                    entry.command.replace('\n', "\\\n")
+                )),
+            }
+        }
+        fs::write(path, content)
+    }
+}
+
+fn entry(command: String, secs: Option<u64>) -> HistoryEntry {
+    HistoryEntry {
+        command,
+        timestamp: UNIX_EPOCH + Duration::from_secs(secs.unwrap_or_default()),
+        success: None,
+    }
+}
+
+fn parse_bash(content: &str) -> Vec<HistoryEntry> {
+    let timestamp = |line: &str| {
+        line.strip_prefix('#')
+            .and_then(|secs| secs.parse::<u64>().ok())
+    };
+    let mut entries: Vec<HistoryEntry> = vec![];
+    // whether the following line continues the last command, which is only known with timestamps
+    let mut continues = false;
+    let mut secs = None;
+    for line in content.lines() {
+        if let Some(timestamp) = timestamp(line) {
+            secs = Some(timestamp);
+            continues = false;
+        } else if continues {
+            if let Some(last) = entries.last_mut() {
+                last.command.push('\n');
+                last.command.push_str(line);
+            }
+        } else if !line.is_empty() {
+            entries.push(entry(line.to_string(), secs));
+            continues = secs.is_some();
+        }
+    }
+    entries
+}
+
+fn parse_zsh(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = vec![];
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let (secs, mut command) = match line
+            .strip_prefix(": ")
+            .and_then(|line| line.split_once(';'))
+        {
+            Some((meta, command)) => {
+                let secs = meta.split(':').next().and_then(|secs| secs.parse().ok());
+                (secs, command.to_string())
+            }
+            None => (None, line.to_string()),
+        };
+        while command.ends_with('\\') {
+            command.pop();
+            command.push('\n');
+            match lines.next() {
+                Some(line) => command.push_str(line),
+                None => break,
+            }
+        }
+        if !command.is_empty() {
+            entries.push(entry(command, secs));
+        }
+    }
+    entries
+}
@@ -0,0 +1,154 @@
+//! Hint line listing the arguments the command being typed still expects, like the parameter hints of an IDE
+
+use crate::completion::word_start;
+use crate::grammar::{takes_multiple_values, Walker};
+use crate::tokenizer::words;
+use crate::TuiClap;
+use clap::{Arg, Command};
+use tui::backend::Backend;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::Paragraph;
+use tui::Frame;
+
+/// Styles of the hint line rendered by `TuiClap::render_signature_help`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureHelp {
+    style: Style,
+    current_style: Style,
+    help_style: Style,
+}
+
+impl Default for SignatureHelp {
+    fn default() -> Self {
+        SignatureHelp {
+            style: Style::default().fg(Color::DarkGray),
+            current_style: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+            help_style: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        }
+    }
+}
+
+impl SignatureHelp {
+    /// Sets the style of the expected arguments, dark gray by default
+    pub fn style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// Sets the style of the argument the word under the cursor is given to, bold gray by default
+    pub fn current_style(&mut self, style: Style) {
+        self.current_style = style;
+    }
+
+    /// Sets the style of the help of the current argument, italic dark gray by default
+    pub fn help_style(&mut self, style: Style) {
+        self.help_style = style;
+    }
+
+    /// The hint for the word under the byte offset `cursor` in `line`: the value of a pending option,
+    /// or the positional arguments and required options of the current subcommand not given yet,
+    /// followed by the help of the first of them
+    fn hint(&self, app: &Command, line: &str, cursor: usize, binary_name: bool) -> Spans<'static> {
+        let start = word_start(line, cursor);
+        let mut walker = Walker::new(app, binary_name);
+        for word in words(&line[..start]).0 {
+            walker.next(&word.text);
+        }
+        if !walker.started() {
+            return Spans::default();
+        }
+
+        let expected: Vec<&Arg> = match walker.pending_option {
+            Some(option) => vec![option],
+            None => {
+                let options = walker.command.get_arguments().filter(|arg| {
+                    !arg.is_positional()
+                        && arg.is_required_set()
+                        && !walker
+                            .given
+                            .iter()
+                            .any(|given| given.get_id() == arg.get_id())
+                });
+                walker
+                    .remaining_positionals()
+                    .into_iter()
+                    .chain(options)
+                    .collect()
+            }
+        };
+
+        let mut spans = vec![];
+        for (i, arg) in expected.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            let style = if i == 0 {
+                self.current_style
+            } else {
+                self.style
+            };
+            spans.push(Span::styled(signature(arg), style));
+        }
+        if let Some(help) = expected.first().and_then(|arg| arg.get_help()) {
+            spans.push(Span::styled(format!("  {}", help), self.help_style));
+        }
+        Spans::from(spans)
+    }
+}
+
+/// `arg` as in the usage of clap, e.g. `<INPUT>`, `[FILES]...` or `--config <FILE>`
+fn signature(arg: &Arg) -> String {
+    let name = arg
+        .get_value_names()
+        .and_then(|names| names.first())
+        .map_or_else(|| arg.get_id().as_str(), |name| name.as_str());
+    let multiple = if takes_multiple_values(arg) {
+        "..."
+    } else {
+        ""
+    };
+    if arg.is_positional() {
+        if arg.is_required_set() {
+            format!("<{}>{}", name, multiple)
+        } else {
+            format!("[{}]{}", name, multiple)
+        }
+    } else {
+        let flag = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{}", long),
+            (None, Some(short)) => format!("-{}", short),
+            (None, None) => name.to_string(),
+        };
+        if arg.get_action().takes_values() {
+            format!("{} <{}>{}", flag, name, multiple)
+        } else {
+            flag
+        }
+    }
+}
+
+impl TuiClap {
+    /// Access the signature help widget
+    pub fn signature_help(&mut self) -> &mut SignatureHelp {
+        &mut self.signature_help_widget
+    }
+
+    /// Renders a hint line on `area`, e.g. below the input, listing the arguments the subcommand being typed still
+    /// expects. The argument the word under the cursor is given to comes first, followed by its help.
+    pub fn render_signature_help<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        self.clap.build();
+        let state = &self.command_input_state;
+        let hint = self.signature_help_widget.hint(
+            self.scoped_app(),
+            &state.content,
+            state.cursor,
+            self.typed_binary_name(),
+        );
+        frame.render_widget(Paragraph::new(hint), area);
+    }
+}
@@ -0,0 +1,79 @@
+//! Running files of commands, e.g. setup sequences or demos, with the built-in command `source` or as rc file
+
+use crate::TuiClap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+impl TuiClap {
+    /// Runs the commands in the file at `path` line by line like `TuiClap::execute`, each echoed in front of its output.
+    /// Empty lines and lines starting with `#` are skipped. The remaining lines are skipped after a command failed,
+    /// unless `TuiClap::set_source_continue_on_error` is set. Also done by the built-in command `source <file>`.
+    /// Returns `false` if the file could not be read or a command failed, which is written to the error stream.
+    pub fn source(&mut self, path: impl AsRef<Path>) -> bool {
+        match self.run_script(path.as_ref()) {
+            Ok(()) => true,
+            Err(err) => {
+                self.write_error(err);
+                false
+            }
+        }
+    }
+
+    /// Sets whether `TuiClap::source` runs the remaining lines of a file after a command failed, disabled by default
+    pub fn set_source_continue_on_error(&mut self, enabled: bool) {
+        self.source_continue_on_error = enabled;
+    }
+
+    /// Sets the rc file, e.g. `.myapprc`, whose commands are run like `TuiClap::source` when the session starts,
+    /// with their output in the transcript. They are run by the first call of `TuiClap::fetch_event` before the first
+    /// event is handled, or by `TuiClap::run_rc_file` if the application handles events itself.
+    pub fn set_rc_file(&mut self, path: impl Into<PathBuf>) {
+        self.rc_file = Some(path.into());
+    }
+
+    /// Runs the commands of the rc file set with `TuiClap::set_rc_file`, unless they already ran.
+    /// A missing rc file is skipped. Returns `false` if it could not be read or a command failed.
+    pub fn run_rc_file(&mut self) -> bool {
+        let path = match self.rc_file.take() {
+            Some(path) => path,
+            None => return true,
+        };
+        match fs::metadata(&path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => true,
+            _ => self.source(path),
+        }
+    }
+
+    /// Runs the commands in the file at `path`, see `TuiClap::source`
+    pub(crate) fn run_script(&mut self, path: &Path) -> Result<(), String> {
+        let script = fs::read_to_string(path)
+            .map_err(|err| format!("source: {}: {}", path.display(), err))?;
+        let mut failed = None;
+        for (index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (unhandled, succeeded) = self.run_line(line, true);
+            if unhandled.is_some() {
+                self.write_error(format!("error: '{}' has no handler to run it", line));
+                self.command_input_state.report_success(false);
+            } else if succeeded {
+                continue;
+            }
+            failed.get_or_insert(index + 1);
+            if !self.source_continue_on_error {
+                break;
+            }
+        }
+        match failed {
+            Some(line) => Err(format!(
+                "source: {}:{}: command failed",
+                path.display(),
+                line
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! An animated spinner indicating that a command is running
+
+use crate::output::OutputHandle;
+use crate::TuiClap;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+
+pub(crate) struct Spinner {
+    frames: Vec<String>,
+    style: Style,
+    frame: usize,
+    running: bool,
+}
+
+impl Default for Spinner {
+    fn default() -> Self {
+        Spinner {
+            frames: ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+                .iter()
+                .map(|frame| frame.to_string())
+                .collect(),
+            style: Style::default().fg(Color::Cyan),
+            frame: 0,
+            running: false,
+        }
+    }
+}
+
+/// The lines of the last command written to the output by `TuiClap::set_echo_commands`
+#[derive(Clone)]
+pub(crate) struct Echo {
+    pub(crate) handle: OutputHandle,
+    pub(crate) lines: Vec<Spans<'static>>,
+}
+
+impl TuiClap {
+    /// Starts showing an animated spinner in front of the prompt and behind the last echoed command,
+    /// see `TuiClap::set_echo_commands`, e.g. while a handler is executing. `TuiClap::tick` animates it.
+    pub fn start_spinner(&mut self) {
+        self.spinner.running = true;
+        self.spinner.frame = 0;
+        self.show_spinner();
+    }
+
+    /// Stops showing the spinner, e.g. once the handler returned
+    pub fn stop_spinner(&mut self) {
+        self.spinner.running = false;
+        self.command_input_state.spinner = None;
+        if let Some(echo) = &self.echo {
+            let lines = echo
+                .lines
+                .iter()
+                .cloned()
+                .map(|line| (line, vec![]))
+                .collect();
+            self.command_output_state.replace_block(echo.handle, lines);
+        }
+    }
+
+    pub fn is_spinning(&self) -> bool {
+        self.spinner.running
+    }
+
+    /// Advances the spinner to its next frame. Has to be called regularly while the spinner is shown,
+    /// e.g. on every tick of the event loop.
+    pub fn tick(&mut self) {
+        if self.spinner.running {
+            self.spinner.frame = (self.spinner.frame + 1) % self.spinner.frames.len().max(1);
+            self.show_spinner();
+        }
+    }
+
+    /// Sets the frames the spinner cycles through, braille dots by default
+    pub fn set_spinner_frames(&mut self, frames: &[&str]) {
+        self.spinner.frames = frames.iter().map(|frame| frame.to_string()).collect();
+        self.spinner.frame = 0;
+    }
+
+    /// Sets the style of the spinner, cyan by default
+    pub fn set_spinner_style(&mut self, style: Style) {
+        self.spinner.style = style;
+    }
+
+    fn show_spinner(&mut self) {
+        let frame = match self.spinner.frames.get(self.spinner.frame) {
+            Some(frame) => frame.clone(),
+            None => return,
+        };
+        self.command_input_state.spinner = Some((frame.clone(), self.spinner.style));
+        if let Some(echo) = &self.echo {
+            let mut lines: Vec<_> = echo
+                .lines
+                .iter()
+                .cloned()
+                .map(|line| (line, vec![]))
+                .collect();
+            if let Some((last, _)) = lines.last_mut() {
+                last.0
+                    .push(Span::styled(format!(" {}", frame), self.spinner.style));
+            }
+            self.command_output_state.replace_block(echo.handle, lines);
+        }
+    }
+}
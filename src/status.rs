@@ -0,0 +1,71 @@
+//! Structured results of commands with an exit status, which `TuiClap` records for the last command
+
+use crate::TuiClap;
+use tui::style::Style;
+
+/// The exit status of commands which failed to parse, like clap's exit code of usage errors
+pub(crate) const USAGE_ERROR: i32 = 2;
+
+/// The result of a command returned by a handler registered with `TuiClap::on_result`: its exit status, which is 0 if
+/// the command succeeded, the lines it writes to the output and an error written to the error stream.
+/// The output of failed commands is styled distinctly, see `TuiClap::set_failure_style`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandResult {
+    pub status: i32,
+    pub output: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl CommandResult {
+    /// The result of a command which succeeded, writing `output`
+    pub fn success(output: Vec<String>) -> CommandResult {
+        CommandResult {
+            status: 0,
+            output,
+            error: None,
+        }
+    }
+
+    /// The result of a command which failed with the exit status `status`, which should not be 0, and `error`
+    pub fn failure(status: i32, error: impl Into<String>) -> CommandResult {
+        CommandResult {
+            status,
+            output: vec![],
+            error: Some(error.into()),
+        }
+    }
+
+    /// Sets the lines the command writes to the output, e.g. the partial output of a failed command
+    pub fn with_output(mut self, output: Vec<String>) -> CommandResult {
+        self.output = output;
+        self
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+/// `Ok` is a success and `Err` a failure with the exit status 1
+impl From<Result<Vec<String>, String>> for CommandResult {
+    fn from(result: Result<Vec<String>, String>) -> Self {
+        match result {
+            Ok(output) => CommandResult::success(output),
+            Err(err) => CommandResult::failure(1, err),
+        }
+    }
+}
+
+impl TuiClap {
+    /// The exit status of the last command run, e.g. to show it in the prompt: 0 if it succeeded, 1 if a handler
+    /// returned an error, 2 if it failed to parse or the status returned by a handler registered with
+    /// `TuiClap::on_result`. `None` before the first command or while a command runs.
+    pub fn last_status(&self) -> Option<i32> {
+        self.command_input_state.last_status
+    }
+
+    /// Sets the style of the output of failed commands, light red by default
+    pub fn set_failure_style(&mut self, style: Style) {
+        self.failure_style = style;
+    }
+}
@@ -0,0 +1,125 @@
+//! Writing tabular data to the output with aligned columns
+
+use crate::output::Level;
+use crate::{CommandOutput, TuiClap};
+use tui::style::Style;
+use tui::text::{Span, Spans};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+impl CommandOutput {
+    /// Sets whether tables written with `TuiClap::write_table` separate their columns with lines
+    /// and the header from the rows, disabled by default
+    pub fn table_separators(&mut self, enabled: bool) {
+        self.table_separators = enabled;
+    }
+
+    /// Sets the style of the header of tables, bold by default
+    pub fn table_header_style(&mut self, style: Style) {
+        self.table_header_style = style;
+    }
+
+    /// Sets the style of the separator lines of tables, dark gray by default
+    pub fn table_separator_style(&mut self, style: Style) {
+        self.table_separator_style = style;
+    }
+}
+
+impl TuiClap {
+    /// Writes `rows` as a table below `headers` to the output widget, with the columns aligned and numbers aligned
+    /// to the right. If the table is wider than the output widget was rendered, the widest columns are shrunk
+    /// and their cells cut off with `…`.
+    pub fn write_table(&mut self, headers: &[impl AsRef<str>], rows: &[Vec<impl AsRef<str>>]) {
+        // line breaks would break the alignment
+        let clean = |cell: &str| cell.replace('\n', " ");
+        let headers: Vec<String> = headers.iter().map(|cell| clean(cell.as_ref())).collect();
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| row.iter().map(|cell| clean(cell.as_ref())).collect())
+            .collect();
+
+        let columns = rows
+            .iter()
+            .map(Vec::len)
+            .chain([headers.len()])
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in rows.iter().chain([&headers]) {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(cell.width());
+            }
+        }
+
+        let output = &self.command_output_widget;
+        let gap = if output.table_separators {
+            " │ "
+        } else {
+            "  "
+        };
+        let available = self.command_output_state.width;
+        if available > 0 {
+            let gaps = gap.width() * columns.saturating_sub(1);
+            while widths.iter().sum::<usize>() + gaps > available {
+                match widths.iter_mut().max() {
+                    Some(widest) if *widest > 1 => *widest -= 1,
+                    _ => break,
+                }
+            }
+        }
+
+        let line = |row: &[String], style: Style| {
+            let mut spans = vec![];
+            for (column, &width) in widths.iter().enumerate() {
+                if column > 0 {
+                    spans.push(Span::styled(gap, output.table_separator_style));
+                }
+                let cell = row.get(column).map(String::as_str).unwrap_or("");
+                let cell = truncate(cell, width);
+                let padding = " ".repeat(width - cell.width());
+                let numeric = cell.trim().parse::<f64>().is_ok();
+                let cell = match (numeric, column + 1 == columns) {
+                    (true, _) => padding + &cell,
+                    // the last column is not padded, to not end lines with spaces
+                    (false, true) => cell,
+                    (false, false) => cell + &padding,
+                };
+                spans.push(Span::styled(cell, style));
+            }
+            Spans::from(spans)
+        };
+
+        let mut lines = vec![line(&headers, output.table_header_style)];
+        if output.table_separators {
+            let rule: Vec<String> = widths.iter().map(|&width| "─".repeat(width)).collect();
+            lines.push(Spans::from(Span::styled(
+                rule.join("─┼─"),
+                output.table_separator_style,
+            )));
+        }
+        for row in &rows {
+            lines.push(line(row, Style::default()));
+        }
+        for line in lines {
+            self.command_output_state
+                .push_line(line, vec![], Level::Info);
+        }
+    }
+}
+
+/// `text` cut off at `width` columns, ending with `…` if it was cut off
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    for grapheme in text.graphemes(true) {
+        if truncated_width + grapheme.width() + 1 > width {
+            break;
+        }
+        truncated.push_str(grapheme);
+        truncated_width += grapheme.width();
+    }
+    truncated + "…"
+}
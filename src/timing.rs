@@ -0,0 +1,92 @@
+//! Wall-clock durations of handlers, shown behind the echo of their command
+
+use crate::spinner::Echo;
+use crate::TuiClap;
+use std::time::Duration;
+use tui::style::{Color, Style};
+use tui::text::Span;
+
+pub(crate) struct Timing {
+    /// Whether durations are shown behind the echo of their command
+    show: bool,
+    style: Style,
+    /// The duration of the last handler which completed
+    last: Option<Duration>,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            show: false,
+            style: Style::default().fg(Color::DarkGray),
+            last: None,
+        }
+    }
+}
+
+/// `duration` in milliseconds below a second, e.g. `134 ms`, in seconds otherwise, e.g. `2.35 s`
+pub(crate) fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{} ms", duration.as_millis())
+    } else {
+        format!("{:.2} s", duration.as_secs_f64())
+    }
+}
+
+impl TuiClap {
+    /// Sets whether the wall-clock duration of each handler is appended to the echo of its command once it returned,
+    /// e.g. `build (134 ms)`, see `TuiClap::set_echo_commands`, and to the line of a background job which completed.
+    /// Disabled by default.
+    pub fn set_show_timing(&mut self, enabled: bool) {
+        self.timing.show = enabled;
+    }
+
+    /// Sets the style of the durations shown by `TuiClap::set_show_timing`, dark gray by default
+    pub fn set_timing_style(&mut self, style: Style) {
+        self.timing.style = style;
+    }
+
+    /// The wall-clock duration of the last handler which completed, also of an asynchronous handler or background job
+    pub fn last_duration(&self) -> Option<Duration> {
+        self.timing.last
+    }
+
+    /// Records the `duration` of a handler and appends it to `echo`, the echo of its command, if durations are shown
+    pub(crate) fn record_duration(&mut self, duration: Duration, echo: Option<Echo>) {
+        self.timing.last = Some(duration);
+        let mut echo = match echo {
+            Some(echo) if self.timing.show => echo,
+            _ => return,
+        };
+        if let Some(last) = echo.lines.last_mut() {
+            last.0.push(self.timing_span(duration));
+        }
+        let lines = echo
+            .lines
+            .iter()
+            .cloned()
+            .map(|line| (line, vec![]))
+            .collect();
+        self.command_output_state.replace_block(echo.handle, lines);
+        // the spinner restores the echo once it stops
+        if self
+            .echo
+            .as_ref()
+            .is_some_and(|current| current.handle == echo.handle)
+        {
+            self.echo = Some(echo);
+        }
+    }
+
+    /// The span showing `duration`, empty unless durations are shown
+    pub(crate) fn timing_span(&self, duration: Duration) -> Span<'static> {
+        if self.timing.show {
+            Span::styled(
+                format!(" ({})", format_duration(duration)),
+                self.timing.style,
+            )
+        } else {
+            Span::raw("")
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! Splitting command lines into words like a shell does, with quotes and escapes
+
+use std::error::Error;
+use std::fmt;
+use std::iter;
+
+/// A word of a command line with the byte range it was written at, quotes and escapes included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Word {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// The word without quotes and escapes
+    pub(crate) text: String,
+}
+
+/// Error of `tokenize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizeError {
+    /// A quote, `'` or `"`, is not closed by the end of the line
+    UnterminatedQuote(char),
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenizeError::UnterminatedQuote(quote) => write!(f, "unterminated quote {}", quote),
+        }
+    }
+}
+
+impl Error for TokenizeError {}
+
+/// Splits `line` into words like a POSIX shell does, e.g. `add --name "John Doe"` into `add`, `--name` and `John Doe`.
+/// Words are separated by any amount of whitespace. Whitespace is kept within single and double quotes,
+/// and after a backslash, which keeps the next character as it is. Within double quotes, backslashes only escape
+/// `"` and `\`. Quotes are removed, so that `""` is an empty word.
+pub fn tokenize(line: &str) -> Result<Vec<String>, TokenizeError> {
+    let (words, open_quote) = words(line);
+    match open_quote {
+        Some(quote) => Err(TokenizeError::UnterminatedQuote(quote)),
+        None => Ok(words.into_iter().map(|word| word.text).collect()),
+    }
+}
+
+/// Quotes `text` so that `tokenize` reads it as a single word, e.g. `'my file.txt'`, if it contains whitespace, quotes,
+/// backslashes or chars separating commands, pipes or variables. Single quotes within `text` are escaped by a
+/// backslash outside of the quotes. Unless `close` is set, the last quote is left open, so that the word can be typed on.
+pub(crate) fn quote(text: &str, close: bool) -> String {
+    let special = |c: char| c.is_whitespace() || "'\"\\;&|$".contains(c);
+    if !text.contains(special) {
+        return text.to_string();
+    }
+    let mut quoted = String::with_capacity(text.len() + 2);
+    let mut parts = text.split('\'').peekable();
+    while let Some(part) = parts.next() {
+        let last = parts.peek().is_none();
+        if !part.is_empty() {
+            quoted.push('\'');
+            quoted.push_str(part);
+            if !last || close {
+                quoted.push('\'');
+            }
+        }
+        if !last {
+            quoted.push_str("\\'");
+        }
+    }
+    quoted
+}
+
+/// Splits `line` into words like `tokenize`, but leniently for lines being typed: an unterminated quote extends to
+/// the end of the line and is returned along with the words
+pub(crate) fn words(line: &str) -> (Vec<Word>, Option<char>) {
+    let mut words = vec![];
+    let mut word: Option<Word> = None;
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = i + c.len_utf8();
+        if quote.is_none() && c.is_whitespace() {
+            words.extend(word.take());
+            continue;
+        }
+        let current = word.get_or_insert_with(|| Word {
+            start: i,
+            end,
+            text: String::new(),
+        });
+        current.end = end;
+        match (quote, c) {
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (Some('\''), _) => current.text.push(c),
+            (Some(_), '\\') if matches!(chars.peek(), Some((_, '"')) | Some((_, '\\'))) => {
+                if let Some((i, escaped)) = chars.next() {
+                    current.text.push(escaped);
+                    current.end = i + escaped.len_utf8();
+                }
+            }
+            (None, '\\') => match chars.next() {
+                Some((i, escaped)) => {
+                    current.text.push(escaped);
+                    current.end = i + escaped.len_utf8();
+                }
+                None => current.text.push(c),
+            },
+            _ => current.text.push(c),
+        }
+    }
+    words.extend(word);
+    (words, quote)
+}
+
+/// How a command of a chain is run, see `split_commands`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Chain {
+    /// The first command, or one following `;`, which is always run
+    Always,
+    /// A command following `&&`, which is only run if the previous one succeeded
+    OnSuccess,
+}
+
+/// The chars of `line` with their byte offsets which are outside of quotes and not escaped by a backslash
+fn unquoted_chars(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut chars = line.char_indices();
+    iter::from_fn(move || loop {
+        let (i, c) = chars.next()?;
+        match (quote, c) {
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                chars.next();
+            }
+            (None, _) => return Some((i, c)),
+            _ => {}
+        }
+    })
+}
+
+/// Splits `line` into the commands chained by `;`, `&&` and `&` outside of quotes and not escaped by a backslash,
+/// e.g. `build && test; clean` into `build `, ` test` and ` clean`. Commands followed by a single `&`, which run in
+/// the background, are flagged.
+pub(crate) fn split_commands(line: &str) -> Vec<(&str, Chain, bool)> {
+    let mut commands = vec![];
+    let mut start = 0;
+    let mut chain = Chain::Always;
+    let mut chars = unquoted_chars(line).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ';' => {
+                commands.push((&line[start..i], chain, false));
+                start = i + 1;
+                chain = Chain::Always;
+            }
+            '&' if matches!(chars.peek(), Some(&(next, '&')) if next == i + 1) => {
+                chars.next();
+                commands.push((&line[start..i], chain, false));
+                start = i + 2;
+                chain = Chain::OnSuccess;
+            }
+            '&' => {
+                commands.push((&line[start..i], chain, true));
+                start = i + 1;
+                chain = Chain::Always;
+            }
+            _ => {}
+        }
+    }
+    commands.push((&line[start..], chain, false));
+    commands
+}
+
+/// Splits `command` into the command and the filters its output is piped through, separated by `|` outside of quotes
+/// and not escaped by a backslash, e.g. `logs | grep error` into `logs ` and ` grep error`
+pub(crate) fn split_pipeline(command: &str) -> Vec<&str> {
+    let mut stages = vec![];
+    let mut start = 0;
+    for (i, c) in unquoted_chars(command) {
+        if c == '|' {
+            stages.push(&command[start..i]);
+            start = i + 1;
+        }
+    }
+    stages.push(&command[start..]);
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote, split_commands, split_pipeline, tokenize, words, Chain, TokenizeError};
+
+    fn words_of(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn splits_at_whitespace() {
+        assert_eq!(words_of("add --name x"), ["add", "--name", "x"]);
+        assert_eq!(words_of("  a \t  b  "), ["a", "b"]);
+        assert!(words_of("   ").is_empty());
+    }
+
+    #[test]
+    fn keeps_whitespace_within_quotes() {
+        assert_eq!(
+            words_of("add --name \"John Doe\""),
+            ["add", "--name", "John Doe"]
+        );
+        assert_eq!(words_of("'a b' \"c d\""), ["a b", "c d"]);
+        assert_eq!(words_of("\"it's\" 'say \"hi\"'"), ["it's", "say \"hi\""]);
+        assert_eq!(words_of("\"héllo wörld\""), ["héllo wörld"]);
+    }
+
+    #[test]
+    fn removes_quotes() {
+        assert_eq!(words_of("\"\" ''"), ["", ""]);
+        assert_eq!(words_of("a\"\"b 'x'\"y\"z"), ["ab", "xyz"]);
+    }
+
+    #[test]
+    fn backslash_escapes_outside_of_quotes() {
+        assert_eq!(words_of(r"a\ b"), ["a b"]);
+        assert_eq!(words_of(r#"\" \'"#), ["\"", "'"]);
+        assert_eq!(words_of(r"a\\b"), [r"a\b"]);
+        // a trailing backslash is kept
+        assert_eq!(words_of(r"a\"), [r"a\"]);
+    }
+
+    #[test]
+    fn backslash_escapes_only_quote_and_backslash_within_double_quotes() {
+        assert_eq!(words_of(r#""a\"b""#), [r#"a"b"#]);
+        assert_eq!(words_of(r#""a\\b""#), [r"a\b"]);
+        assert_eq!(words_of(r#""a\nb""#), [r"a\nb"]);
+    }
+
+    #[test]
+    fn backslash_is_literal_within_single_quotes() {
+        assert_eq!(words_of(r"'a\b' 'c\'"), [r"a\b", r"c\"]);
+    }
+
+    #[test]
+    fn unterminated_quotes_fail() {
+        assert_eq!(
+            tokenize("echo \"abc"),
+            Err(TokenizeError::UnterminatedQuote('"'))
+        );
+        assert_eq!(
+            tokenize("echo 'abc"),
+            Err(TokenizeError::UnterminatedQuote('\''))
+        );
+        assert_eq!(
+            tokenize(r#"echo "a\""#),
+            Err(TokenizeError::UnterminatedQuote('"'))
+        );
+    }
+
+    #[test]
+    fn words_have_byte_offsets() {
+        let (found, open) = words("echo \"a b\" ç");
+        let offsets: Vec<(usize, usize)> =
+            found.iter().map(|word| (word.start, word.end)).collect();
+        assert_eq!(offsets, [(0, 4), (5, 10), (11, 13)]);
+        assert_eq!(open, None);
+
+        let (found, open) = words("echo 'a b");
+        assert_eq!(found.last().map(|word| word.text.as_str()), Some("a b"));
+        assert_eq!(open, Some('\''));
+    }
+
+    #[test]
+    fn splits_chained_commands() {
+        assert_eq!(
+            split_commands("build && test; clean"),
+            [
+                ("build ", Chain::Always, false),
+                (" test", Chain::OnSuccess, false),
+                (" clean", Chain::Always, false),
+            ]
+        );
+        assert_eq!(
+            split_commands("backup & ls"),
+            [
+                ("backup ", Chain::Always, true),
+                (" ls", Chain::Always, false)
+            ]
+        );
+        assert_eq!(
+            split_commands("a;"),
+            [("a", Chain::Always, false), ("", Chain::Always, false)]
+        );
+    }
+
+    #[test]
+    fn keeps_quoted_and_escaped_separators() {
+        for line in [r#"echo "a;b" 'c&&d'"#, r"echo a\;b a\&b", r#"echo "a\"&b""#] {
+            assert_eq!(split_commands(line), [(line, Chain::Always, false)]);
+        }
+    }
+
+    #[test]
+    fn splits_background_commands() {
+        assert_eq!(
+            split_commands("backup & ls"),
+            [
+                ("backup ", Chain::Always, true),
+                (" ls", Chain::Always, false)
+            ]
+        );
+        assert_eq!(
+            split_commands("a && b &"),
+            [
+                ("a ", Chain::Always, false),
+                (" b ", Chain::OnSuccess, true),
+                ("", Chain::Always, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_quoted_and_escaped_ampersands() {
+        for line in [r#"echo "a&b" 'c&d'"#, r"echo a\&b", r#"echo "a\"&b""#] {
+            assert_eq!(split_commands(line), [(line, Chain::Always, false)]);
+        }
+    }
+
+    #[test]
+    fn splits_pipelines() {
+        assert_eq!(
+            split_pipeline("logs | grep error"),
+            ["logs ", " grep error"]
+        );
+        assert_eq!(split_pipeline("a|b|c"), ["a", "b", "c"]);
+        assert_eq!(
+            split_pipeline(r#"echo "a|b" | head"#),
+            [r#"echo "a|b" "#, " head"]
+        );
+        assert_eq!(split_pipeline(r"echo a\|b"), [r"echo a\|b"]);
+    }
+
+    #[test]
+    fn quote_roundtrips_through_tokenize() {
+        for text in [
+            "plain",
+            "my file.txt",
+            "it's",
+            "'",
+            "a\"b",
+            "back\\slash",
+            "$HOME",
+            "a;b&&c|d",
+            "x''y",
+        ] {
+            let quoted = quote(text, true);
+            assert_eq!(tokenize(&quoted), Ok(vec![text.to_string()]), "{}", quoted);
+        }
+    }
+
+    #[test]
+    fn quote_keeps_plain_words() {
+        assert_eq!(quote("status", true), "status");
+        assert_eq!(quote("--name=x", false), "--name=x");
+    }
+
+    #[test]
+    fn open_quote_is_left_open() {
+        assert_eq!(quote("my dir/", false), "'my dir/");
+        assert_eq!(quote("it's", false), "'it'\\''s");
+    }
+}
@@ -0,0 +1,117 @@
+//! Variables substituted into commands before they are run, like `ping $host` after `set host=10.0.0.1`
+
+use crate::TuiClap;
+
+/// Whether `c` can be part of a variable name
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl TuiClap {
+    /// Sets the variable `name` to `value`, which is substituted for `$name` or `${name}` in commands before they are
+    /// run, except within single quotes or after a backslash. Variables can be set with the built-in command
+    /// `set name=value` as well, unless the app has a `set` subcommand of its own.
+    pub fn set_variable(&mut self, name: &str, value: &str) {
+        self.variables.insert(name.to_string(), value.to_string());
+    }
+
+    /// Removes the variable `name`, also done by the built-in command `unset` unless the app has an `unset` subcommand.
+    /// Returns `false` if there is no such variable.
+    pub fn remove_variable(&mut self, name: &str) -> bool {
+        self.variables.remove(name).is_some()
+    }
+
+    /// The value of the variable `name`
+    pub fn variable(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(String::as_str)
+    }
+
+    /// The variables and their values sorted by name
+    pub fn variables(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// `line` with the values of the variables substituted for their references. A value is inserted as it is, so
+    /// that it is split into several words at whitespace unless the reference is within double quotes.
+    /// Returns an error if a variable is not set.
+    pub(crate) fn substitute_variables(&self, line: &str) -> Result<String, String> {
+        let mut substituted = String::with_capacity(line.len());
+        let mut quote = None;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (None, '\'') | (None, '"') => quote = Some(c),
+                (Some(open), _) if c == open => quote = None,
+                (Some('\''), _) => {}
+                // the escape is kept for the tokenizer
+                (_, '\\') => {
+                    substituted.push(c);
+                    if let Some(escaped) = chars.next() {
+                        substituted.push(escaped);
+                    }
+                    continue;
+                }
+                (_, '$') => {
+                    let braced = chars.next_if_eq(&'{').is_some();
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|&c| is_name_char(c)) {
+                        name.push(c);
+                    }
+                    if braced && chars.next_if_eq(&'}').is_none() {
+                        return Err(format!("error: bad substitution ${{{}", name));
+                    }
+                    if name.is_empty() {
+                        substituted.push('$');
+                        if braced {
+                            substituted.push_str("{}");
+                        }
+                        continue;
+                    }
+                    match self.variables.get(&name) {
+                        Some(value) => substituted.push_str(value),
+                        None => return Err(format!("error: ${} is not set", name)),
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            substituted.push(c);
+        }
+        Ok(substituted)
+    }
+
+    /// `set` lists the variables and `set <name>=<value>…` sets variables
+    pub(crate) fn builtin_set(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        if args.is_empty() {
+            return Ok(self
+                .variables()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect());
+        }
+        for arg in args {
+            match arg.split_once('=') {
+                Some((name, value)) if !name.is_empty() && name.chars().all(is_name_char) => {
+                    self.set_variable(name, value)
+                }
+                Some((name, _)) => return Err(format!("set: invalid name '{}'", name)),
+                None => return Err(format!("set: missing value of '{}'", arg)),
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// `unset <name>…` removes variables
+    pub(crate) fn builtin_unset(&mut self, args: &[String]) -> Result<Vec<String>, String> {
+        if args.is_empty() {
+            return Err("unset: missing name".to_string());
+        }
+        for name in args {
+            if !self.remove_variable(name) {
+                return Err(format!("unset: {}: not set", name));
+            }
+        }
+        Ok(vec![])
+    }
+}
@@ -0,0 +1,171 @@
+//! Vi-style modal editing for `CommandInputState`
+
+use crate::CommandInputState;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::cmp::{max, min};
+
+/// The current mode of the vi editing mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViMode {
+    Insert,
+    Normal,
+}
+
+impl ViMode {
+    /// A short indicator of the mode that can be rendered next to the input
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            ViMode::Insert => "-- INSERT --",
+            ViMode::Normal => "-- NORMAL --",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ViState {
+    mode: ViMode,
+    /// Operator waiting for its motion, e.g. the `d` of `dw`
+    pending_operator: Option<char>,
+}
+
+impl CommandInputState {
+    /// Enables the vi editing mode, starting in insert mode
+    pub fn enable_vi_mode(&mut self) {
+        self.vi = Some(ViState {
+            mode: ViMode::Insert,
+            pending_operator: None,
+        });
+    }
+
+    pub fn disable_vi_mode(&mut self) {
+        self.vi = None;
+    }
+
+    /// The current vi mode or `None` if the vi editing mode is disabled
+    pub fn vi_mode(&self) -> Option<ViMode> {
+        self.vi.map(|vi| vi.mode)
+    }
+
+    /// Handles `key` according to the vi editing mode.
+    /// Returns `false` if the key was not handled and should be processed as usual. This is the case for every key
+    /// if the vi editing mode is disabled, for every key but `Esc` in insert mode and for keys without a vi meaning,
+    /// like `Enter`, in normal mode.
+    ///
+    /// Normal mode supports the motions `h`, `l`, `w`, `b`, `e`, `0` and `$`, the operators `d` and `c` combined
+    /// with a motion or doubled for the whole content, and `x`, `D`, `C`, `p`, `i`, `a`, `I` and `A`.
+    pub fn handle_vi_key(&mut self, key: KeyEvent) -> bool {
+        let mut vi = match self.vi {
+            Some(vi) => vi,
+            None => return false,
+        };
+        let handled = match vi.mode {
+            ViMode::Insert if key.code == KeyCode::Esc => {
+                vi.mode = ViMode::Normal;
+                self.move_cursor_left();
+                true
+            }
+            ViMode::Insert => false,
+            ViMode::Normal => self.handle_vi_normal_key(&mut vi, key),
+        };
+        self.vi = Some(vi);
+        handled
+    }
+
+    fn handle_vi_normal_key(&mut self, vi: &mut ViState, key: KeyEvent) -> bool {
+        let c = match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                c
+            }
+            KeyCode::Left => 'h',
+            KeyCode::Right => 'l',
+            KeyCode::Home => '0',
+            KeyCode::End => '$',
+            KeyCode::Esc => {
+                vi.pending_operator = None;
+                return true;
+            }
+            _ => {
+                vi.pending_operator = None;
+                return false;
+            }
+        };
+
+        if let Some(operator) = vi.pending_operator.take() {
+            // like in vi, `cw` changes up to the end of the word only
+            let motion = if operator == 'c' && c == 'w' { 'e' } else { c };
+            let range = if c == operator {
+                Some((0, self.content.len()))
+            } else {
+                self.vi_motion(motion)
+                    .map(|target| (min(self.cursor, target), max(self.cursor, target)))
+            };
+            if let Some((start, end)) = range {
+                self.kill(start, end);
+                if operator == 'c' {
+                    vi.mode = ViMode::Insert;
+                }
+            }
+            return true;
+        }
+
+        match c {
+            'i' => vi.mode = ViMode::Insert,
+            'a' => {
+                self.move_cursor_right();
+                vi.mode = ViMode::Insert;
+            }
+            'I' => {
+                self.move_cursor_to_start();
+                vi.mode = ViMode::Insert;
+            }
+            'A' => {
+                self.move_cursor_to_end();
+                vi.mode = ViMode::Insert;
+            }
+            'x' => self.del_next_char(),
+            'D' => self.kill_to_end(),
+            'C' => {
+                self.kill_to_end();
+                vi.mode = ViMode::Insert;
+            }
+            'p' => {
+                self.move_cursor_right();
+                self.yank();
+            }
+            'd' | 'c' => vi.pending_operator = Some(c),
+            _ => {
+                if let Some(target) = self.vi_motion(c) {
+                    self.selection_anchor = None;
+                    self.cursor = target;
+                }
+            }
+        }
+        true
+    }
+
+    /// The cursor position after the vi motion `motion`
+    fn vi_motion(&self, motion: char) -> Option<usize> {
+        match motion {
+            'h' => Some(self.prev_grapheme_boundary()),
+            'l' => Some(self.next_grapheme_boundary()),
+            'w' => Some(self.next_word_start()),
+            'b' => Some(self.prev_word_boundary()),
+            'e' => Some(self.next_word_boundary()),
+            '0' => Some(self.line_start()),
+            '$' => Some(self.line_end()),
+            _ => None,
+        }
+    }
+
+    /// Start of the next word, skipping the rest of the current one
+    fn next_word_start(&self) -> usize {
+        let after = &self.content[self.cursor..];
+        let word_end = after.find(char::is_whitespace).unwrap_or(after.len());
+        let rest = &after[word_end..];
+        self.cursor + word_end + (rest.len() - rest.trim_start().len())
+    }
+}
@@ -0,0 +1,56 @@
+//! Events sent to the channel of `Events` by work finishing in the background, waking a loop waiting for events
+
+use crate::{Events, TerminalEvent, TuiClap};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// Sends events to the channel of `Events` from other threads.
+/// The sender is dropped once the thread reading the terminal ends, so that `Events::next` still reports the
+/// disconnection instead of waiting for events only sent by background work.
+#[derive(Clone, Default)]
+pub(crate) struct Waker(Arc<Mutex<Option<Sender<TerminalEvent>>>>);
+
+impl Waker {
+    pub(crate) fn new(tx: Sender<TerminalEvent>) -> Waker {
+        Waker(Arc::new(Mutex::new(Some(tx))))
+    }
+
+    /// Sends `event`, unless no `Events` are connected or they are gone
+    pub(crate) fn wake(&self, event: TerminalEvent) {
+        if let Ok(Some(tx)) = self.0.lock().as_deref() {
+            // the receiver is only gone if `Events` was dropped
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Drops the sender, once no more input events are read
+    pub(crate) fn close(&self) {
+        if let Ok(mut tx) = self.0.lock() {
+            *tx = None;
+        }
+    }
+}
+
+impl TuiClap {
+    /// Sends the events of work finishing in the background to `events`, like `TerminalEvent::CompletionReady`,
+    /// so that a loop waiting with `Events::wait` wakes up to handle them. Called by `TuiClap::fetch_event`.
+    pub fn connect_events(&mut self, events: &Events) {
+        self.waker = events.waker.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Waker;
+    use crate::TerminalEvent;
+    use std::sync::mpsc;
+
+    #[test]
+    fn closed_waker_sends_nothing() {
+        let (tx, rx) = mpsc::channel();
+        let waker = Waker::new(tx);
+        waker.close();
+        waker.wake(TerminalEvent::CompletionReady);
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+    }
+}
@@ -0,0 +1,150 @@
+//! Asking for the missing required arguments of a command one at a time instead of failing to parse it
+
+use crate::grammar::Walker;
+use crate::TuiClap;
+use clap::Arg;
+
+/// A required argument missing in a command line
+struct MissingArg {
+    question: String,
+    /// The long or short flag of an option, `None` for a positional argument
+    flag: Option<String>,
+}
+
+impl MissingArg {
+    fn new(arg: &Arg) -> MissingArg {
+        let question = match arg.get_help() {
+            Some(help) => format!("{}: ", help),
+            None => format!("{}: ", value_name(arg)),
+        };
+        let flag = if arg.is_positional() {
+            None
+        } else if let Some(long) = arg.get_long() {
+            Some(format!("--{}", long))
+        } else {
+            arg.get_short().map(|short| format!("-{}", short))
+        };
+        MissingArg { question, flag }
+    }
+}
+
+/// The name of the value of `arg` as shown in the usage, e.g. `<name>`
+fn value_name(arg: &Arg) -> String {
+    match arg.get_value_names() {
+        Some(names) if !names.is_empty() => format!("<{}>", names[0]),
+        _ => format!("<{}>", arg.get_id()),
+    }
+}
+
+/// A command line whose missing arguments are asked for
+struct Wizard {
+    /// The words of the command line, starting with the binary name
+    words: Vec<String>,
+    /// The arguments still to be asked for, the next one last
+    missing: Vec<MissingArg>,
+    /// The options answered so far with their flags
+    options: Vec<String>,
+    /// The positional arguments answered so far
+    positionals: Vec<String>,
+    /// Whether `--` was given in the command line, so that the positional arguments can follow without it
+    only_positionals: bool,
+}
+
+impl Wizard {
+    /// The command line with the answers, the options first
+    fn into_words(self) -> Vec<String> {
+        let mut words = self.words;
+        words.extend(self.options);
+        if !self.positionals.is_empty() && !self.only_positionals {
+            // keeps the answers from being taken as values of an option taking several values
+            words.push("--".to_string());
+        }
+        words.extend(self.positionals);
+        words
+    }
+}
+
+impl TuiClap {
+    /// Sets whether a command missing required arguments asks for each of them instead of failing to parse,
+    /// using the help of the argument as question, and runs once all of them are answered. Disabled by default.
+    /// Since the command runs after `TuiClap::execute` returned, it needs a handler, see `TuiClap::on`.
+    pub fn set_wizard(&mut self, enabled: bool) {
+        self.wizard = enabled;
+    }
+
+    /// Asks for the required arguments missing in `words`, the command line starting with the binary name,
+    /// the positional arguments first. Returns `false` if none of them can be asked for.
+    pub(crate) fn start_wizard(&mut self, words: Vec<String>) -> bool {
+        let mut walker = Walker::new(&self.clap, true);
+        for word in &words {
+            walker.next(word);
+        }
+        if walker.pending_option.is_some() {
+            return false;
+        }
+        let mut missing: Vec<MissingArg> = walker
+            .remaining_positionals()
+            .into_iter()
+            // the last positional argument is remaining if it takes several values, even if given
+            .filter(|arg| arg.is_required_set() && arg.get_index() > Some(walker.positionals))
+            .map(MissingArg::new)
+            .collect();
+        let options = walker
+            .command
+            .get_arguments()
+            .filter(|arg| arg.is_required_set() && !arg.is_positional())
+            .filter(|arg| {
+                !walker
+                    .given
+                    .iter()
+                    .any(|given| given.get_id() == arg.get_id())
+            })
+            .map(MissingArg::new);
+        missing.extend(options);
+        // options are taken as positional arguments after `--`
+        if missing.is_empty()
+            || (walker.only_positionals && missing.iter().any(|arg| arg.flag.is_some()))
+        {
+            return false;
+        }
+        missing.reverse();
+        let wizard = Wizard {
+            only_positionals: walker.only_positionals,
+            words,
+            missing,
+            options: vec![],
+            positionals: vec![],
+        };
+        self.ask_next_argument(wizard);
+        true
+    }
+
+    /// Asks for the next missing argument, or runs the command line once all are answered
+    fn ask_next_argument(&mut self, mut wizard: Wizard) {
+        let MissingArg { question, flag } = match wizard.missing.pop() {
+            Some(arg) => arg,
+            None => {
+                let words = wizard.into_words();
+                let line = words[1..].join(" ");
+                if let Ok(matches) = self.parse_words(words, &line) {
+                    if !self.dispatch(&matches) {
+                        self.write_error(format!("error: '{}' has no handler to run it", line));
+                        self.command_input_state.report_success(false);
+                    }
+                }
+                return;
+            }
+        };
+        self.ask_with(
+            &question,
+            Box::new(move |answer, tui| {
+                match flag {
+                    Some(flag) => wizard.options.extend([flag, answer.to_string()]),
+                    None => wizard.positionals.push(answer.to_string()),
+                }
+                tui.ask_next_argument(wizard);
+                None
+            }),
+        );
+    }
+}